@@ -0,0 +1,70 @@
+//! Run with `cargo bench`. Prints the best time of several runs for every case.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use line_adjustment::transform;
+
+const RUNS: usize = 10;
+
+fn bench(name: &str, f: impl Fn()) {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed());
+    }
+    println!("{name:<45} {best:>12.3?}");
+}
+
+/// Roughly `size` bytes of `words` separated by single spaces.
+fn corpus(words: &[&str], size: usize) -> String {
+    let mut result = String::with_capacity(size + 32);
+    let mut idx = 0;
+    while result.len() < size {
+        if !result.is_empty() {
+            result.push(' ');
+        }
+        result.push_str(words[idx * 7 % words.len()]);
+        idx += 1;
+    }
+    result
+}
+
+fn main() {
+    const MB: usize = 1 << 20;
+
+    let latin = corpus(
+        &["lorem", "ipsum", "dolor", "sit", "amet,", "consectetur", "adipiscing", "elit"],
+        MB,
+    );
+    let cyrillic = corpus(
+        &["Съешь", "ещё", "этих", "мягких", "французских", "булок,", "да", "выпей", "чаю."],
+        MB,
+    );
+    let long_word = "Тридцатитрёхбуквенный".repeat(15);
+    let long_cyrillic = corpus(&[&long_word, "и", "ещё"], MB);
+    let log_line = corpus(
+        &["ts=2024-05-01T12:00:00Z", "level=info", "path=/api/v1/items", "status=200", "took=12ms"],
+        4 * MB,
+    );
+
+    bench("latin 1 MB, width 80", || {
+        black_box(transform(black_box(&latin), 80));
+    });
+    bench("latin 1 MB, width 5 (split words)", || {
+        black_box(transform(black_box(&latin), 5));
+    });
+    bench("cyrillic 1 MB, width 80", || {
+        black_box(transform(black_box(&cyrillic), 80));
+    });
+    bench("cyrillic 1 MB, width 5 (split words)", || {
+        black_box(transform(black_box(&cyrillic), 5));
+    });
+    bench("log line 4 MB, width 120", || {
+        black_box(transform(black_box(&log_line), 120));
+    });
+    bench("cyrillic 1 MB, 315-char words, width 80", || {
+        black_box(transform(black_box(&long_cyrillic), 80));
+    });
+}
@@ -0,0 +1,46 @@
+//! Checks that the crate builds without `std`. Build it for a target that
+//! has no `std`:
+//!
+//! ```sh
+//! cargo build --target thumbv7em-none-eabihf
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+
+use line_adjustment::{
+    transform, transform_with_options, try_transform, Adjuster, Alignment, TransformError,
+    TransformOptions,
+};
+
+pub fn adjusted(input: &str, line_width: u32) -> String {
+    transform(input, line_width)
+}
+
+pub fn centered(input: &str, line_width: u32) -> String {
+    let mut options = TransformOptions::new(line_width);
+    options.align = Alignment::Center;
+    transform_with_options(input, &options)
+}
+
+pub fn checked(input: &str, line_width: u32) -> Result<String, TransformError> {
+    try_transform(input, line_width)
+}
+
+pub fn streamed(pieces: &[&str], line_width: u32) -> String {
+    let mut adjuster = Adjuster::new(line_width);
+    let mut output = String::new();
+    for piece in pieces {
+        if let Some(lines) = adjuster.push_str(piece) {
+            output.push_str(&lines);
+            output.push('\n');
+        }
+    }
+    if let Some(lines) = adjuster.finish() {
+        output.push_str(&lines);
+    }
+    output
+}
@@ -0,0 +1,35 @@
+//! Run with `cargo +nightly fuzz run try_transform` from the crate root.
+//!
+//! Input is the line width as 4 little-endian bytes followed by the text.
+//! Widths are taken modulo a small bound most of the time, as huge widths
+//! only pad lines with more spaces.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use line_adjustment::{try_transform, TransformError};
+
+fuzz_target!(|data: &[u8]| {
+    let Some((width, text)) = data.split_first_chunk::<4>() else {
+        return;
+    };
+    let Ok(input) = std::str::from_utf8(text) else {
+        return;
+    };
+    let width = u32::from_le_bytes(*width);
+    let width = match width >> 31 {
+        0 => width % 64,
+        _ => width,
+    };
+
+    match try_transform(input, width) {
+        Ok(output) => {
+            for line in output.lines() {
+                assert_eq!(line.chars().count(), width as usize, "{input:?} at {width}");
+            }
+        }
+        Err(TransformError::ZeroWidth) => assert_eq!(width, 0),
+        Err(TransformError::WidthTooLarge { .. }) => assert!(width > 1 << 20),
+        Err(err) => panic!("unexpected error {err}"),
+    }
+});
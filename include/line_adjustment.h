@@ -0,0 +1,47 @@
+/*
+ * C interface of the line_adjustment crate.
+ *
+ * Build the crate as a static or dynamic library with the `ffi` feature,
+ * e.g. `cargo rustc --release --lib --features ffi --crate-type cdylib`
+ * or `--crate-type staticlib`, and link against `libline_adjustment`.
+ */
+
+#ifndef LINE_ADJUSTMENT_H
+#define LINE_ADJUSTMENT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define LINE_ADJUST_OK 0
+#define LINE_ADJUST_NULL_ARGUMENT 1
+#define LINE_ADJUST_INVALID_UTF8 2
+#define LINE_ADJUST_ZERO_WIDTH 3
+#define LINE_ADJUST_INTERNAL_ERROR 4
+#define LINE_ADJUST_WIDTH_TOO_LARGE 5
+
+/*
+ * Adjusts the NUL-terminated UTF-8 string `input` to `width`.
+ *
+ * Returns a NUL-terminated UTF-8 string, which must be released with
+ * `line_adjust_free`. Its length in bytes, without the terminator, is
+ * written to `out_len` unless it is NULL.
+ *
+ * Returns NULL on failure, `line_adjust_last_error` tells the reason.
+ */
+char *line_adjust_transform(const char *input, uint32_t width, size_t *out_len);
+
+/* Releases a string returned by `line_adjust_transform`. NULL is ignored. */
+void line_adjust_free(char *ptr);
+
+/* Error code of the last `line_adjust_transform` call on this thread. */
+int line_adjust_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* LINE_ADJUSTMENT_H */
@@ -0,0 +1,461 @@
+use alloc::string::{String, ToString};
+#[cfg(feature = "std")]
+use std::{io, vec::Vec};
+
+use crate::{split_word, FitResult, LinePlan, LinePolicy, TransformOptions, WidthMode, Word};
+
+/// Adjusts text that arrives in pieces, e.g. words from a network stream.
+///
+/// Works like [`crate::transform`], but keeps its state between calls
+/// instead of borrowing the whole input: the words of the current line
+/// and a word that is not terminated by whitespace yet.
+///
+/// Every returned string holds one or more complete lines, separated by
+/// line endings. Joining all of them with a line ending gives the same
+/// text as `transform` of the concatenated input.
+///
+/// Pushing text panics when `line_width` is 0.
+pub struct Adjuster {
+    options: TransformOptions,
+    line_width: usize,
+    initial_width: Option<usize>,
+    line_ending: &'static str,
+
+    /// Words of the current line, separated by single spaces
+    line: String,
+    n_words: usize,
+    total_len: usize,
+    chk_len: usize,
+
+    /// Bytes that do not form a complete char yet
+    #[cfg(feature = "std")]
+    bytes: Vec<u8>,
+    /// Word that is not terminated by whitespace yet
+    word: String,
+    /// Newlines since the last word, two of them end the paragraph
+    newlines: usize,
+
+    has_lines: bool,
+    paragraph_break: bool,
+    /// Whether no line of the current paragraph is written yet
+    paragraph_start: bool,
+}
+
+impl Adjuster {
+    pub fn new(line_width: u32) -> Self {
+        Adjuster::with_options(TransformOptions::new(line_width))
+    }
+
+    /// `number_lines`, `lines_per_page`, `max_lines`, `min_lines`,
+    /// `blank_line_for_empty`, `skip_line`, `french_spacing`, `atomic_delimiters`,
+    /// `cjk_latin_spacing`, `break_after_chars`, `break_hint`, `orphan_words`,
+    /// `avoid_orphan_last_line`, `field_width`, `markup`, `width_mode`,
+    /// `hang_punctuation` and the margins of `options` are ignored.
+    pub fn with_options(mut options: TransformOptions) -> Self {
+        // Words are split like `transform` splits them without these
+        options.break_after_chars.clear();
+        options.break_hint = None;
+        options.markup = None;
+        options.width_mode = WidthMode::Chars;
+        Adjuster {
+            line_width: options.line_width as usize,
+            initial_width: options.initial_width.map(|width| width as usize),
+            line_ending: options.line_ending.as_str(),
+            options,
+            line: String::new(),
+            n_words: 0,
+            total_len: 0,
+            chk_len: 0,
+            #[cfg(feature = "std")]
+            bytes: Vec::new(),
+            word: String::new(),
+            newlines: 0,
+            has_lines: false,
+            paragraph_break: false,
+            paragraph_start: true,
+        }
+    }
+
+    /// Adds a single word and returns lines it completes. A word started
+    /// by [`Adjuster::push_str`] is ended first.
+    ///
+    /// `word` must not contain whitespace, use `push_str` for such text.
+    pub fn push_word(&mut self, word: &str) -> Option<String> {
+        let mut out = String::new();
+        self.push_pending_word(&mut out);
+        self.push_word_to(word, &mut out);
+        self.completed(out)
+    }
+
+    /// Splits `text` into words and returns lines they complete. A word
+    /// may continue in the next call.
+    pub fn push_str(&mut self, text: &str) -> Option<String> {
+        let mut out = String::new();
+        self.push_str_to(text, &mut out);
+        self.completed(out)
+    }
+
+    /// Returns the remaining lines, adjusted as the end of text. With
+    /// `final_newline`, they end with a line ending.
+    pub fn finish(mut self) -> Option<String> {
+        let mut out = String::new();
+        self.end_text_to(&mut out);
+        self.completed(out)
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn line_width(&self) -> usize {
+        self.line_width
+    }
+
+    /// Drops the line ending that separates `out` from previously returned lines.
+    fn completed(&self, out: String) -> Option<String> {
+        match out.strip_prefix(self.line_ending) {
+            Some(lines) => Some(lines.to_string()),
+            None if out.is_empty() => None,
+            None => Some(out),
+        }
+    }
+
+    /// Splits `text` into words and appends completed lines to `out`.
+    /// Words may be split between calls.
+    pub(crate) fn push_str_to(&mut self, text: &str, out: &mut String) {
+        for chr in text.chars() {
+            if !chr.is_whitespace() {
+                if self.word.is_empty() {
+                    if self.newlines >= 2 && self.options.preserve_paragraphs {
+                        self.end_paragraph(out);
+                    }
+                    self.newlines = 0;
+                }
+                self.word.push(chr);
+                continue;
+            }
+
+            if chr == '\n' {
+                self.newlines += 1;
+            }
+            self.push_pending_word(out);
+        }
+    }
+
+    /// Same as [`Adjuster::push_str_to`], but takes UTF-8 bytes. Chars may be
+    /// split between calls.
+    #[cfg(feature = "std")]
+    pub(crate) fn push_bytes_to(&mut self, bytes: &[u8], out: &mut String) -> io::Result<()> {
+        // Only an incomplete char from the previous call is copied
+        if self.bytes.is_empty() {
+            let valid_len = self.push_valid(bytes, out)?;
+            self.bytes.extend_from_slice(&bytes[valid_len..]);
+        } else {
+            let mut pending = core::mem::take(&mut self.bytes);
+            pending.extend_from_slice(bytes);
+            let valid_len = self.push_valid(&pending, out)?;
+            pending.drain(..valid_len);
+            self.bytes = pending;
+        }
+
+        Ok(())
+    }
+
+    /// Same as [`Adjuster::finish_to`], but fails when bytes pushed so far
+    /// end with an incomplete char.
+    #[cfg(feature = "std")]
+    pub(crate) fn finish_bytes_to(&mut self, out: &mut String) -> io::Result<()> {
+        if !self.bytes.is_empty() {
+            self.bytes.clear();
+            return Err(invalid_utf8());
+        }
+
+        self.end_text_to(out);
+        Ok(())
+    }
+
+    /// Pushes complete chars of `bytes` and returns their length.
+    #[cfg(feature = "std")]
+    fn push_valid(&mut self, bytes: &[u8], out: &mut String) -> io::Result<usize> {
+        let valid_len = match core::str::from_utf8(bytes) {
+            Ok(text) => text.len(),
+            // Char is not complete yet
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => return Err(invalid_utf8()),
+        };
+
+        let text = core::str::from_utf8(&bytes[..valid_len]).expect("Validated above");
+        self.push_str_to(text, out);
+        Ok(valid_len)
+    }
+
+    /// Adds a single word, which must not contain whitespace, and appends
+    /// completed lines to `out`.
+    pub(crate) fn push_word_to(&mut self, word: &str, out: &mut String) {
+        let mut word = Word::new(word);
+
+        if self.n_words > 0 {
+            if self.chk_len + word.width <= self.width() {
+                self.add_to_line(word.text, word.width);
+                return;
+            }
+
+            // First part of a word that is split anyway ends the line
+            // when it fits there, like in `transform`
+            if word.width > self.line_width {
+                let (part, rest) = split_word(word, self.line_width, &self.options, Word::new);
+                if self.chk_len + part.width <= self.width() {
+                    self.add_to_line(part.text, part.width);
+                    word = rest;
+                }
+            }
+            self.write_line(out, false);
+        }
+        let word_len = word.width;
+
+        if word_len <= self.width() {
+            self.add_to_line(word.text, word_len);
+            return;
+        }
+
+        // Word that only fits lines after the shorter first one starts
+        // on the second line
+        if word_len <= self.line_width {
+            self.begin_line(out);
+            let policy = LinePolicy::new(&self.options, false);
+            LinePlan::new(&FitResult::word(Word::new("")), self.width(), policy).push(out);
+            self.paragraph_start = false;
+            self.add_to_line(word.text, word_len);
+            return;
+        }
+
+        // Every part except the last one takes a whole line, the last one
+        // starts the next line
+        let mut rest = word;
+        while rest.width > self.width() {
+            self.begin_line(out);
+            let (part, next) = split_word(rest, self.width(), &self.options, Word::new);
+            let policy = LinePolicy::new(&self.options, false);
+            LinePlan::new(&FitResult::word(part), self.width(), policy).push(out);
+            self.paragraph_start = false;
+            rest = next;
+        }
+        self.add_to_line(rest.text, rest.width);
+    }
+
+    /// Writes everything that is left as the end of a paragraph. Text
+    /// pushed after this starts on a new line.
+    pub(crate) fn finish_to(&mut self, out: &mut String) {
+        self.push_pending_word(out);
+        self.write_line(out, true);
+        self.paragraph_start = true;
+    }
+
+    /// Same as [`Adjuster::finish_to`], but also ends the last line with
+    /// a line ending, when `final_newline` asks for it. Text pushed after
+    /// this starts on a new line as well.
+    fn end_text_to(&mut self, out: &mut String) {
+        self.finish_to(out);
+        if self.options.final_newline && self.has_lines {
+            out.push_str(self.line_ending);
+            // Next line goes right after the line ending
+            self.has_lines = false;
+        }
+    }
+
+    fn end_paragraph(&mut self, out: &mut String) {
+        self.finish_to(out);
+        self.paragraph_break = self.has_lines;
+    }
+
+    fn push_pending_word(&mut self, out: &mut String) {
+        if !self.word.is_empty() {
+            let word = core::mem::take(&mut self.word);
+            self.push_word_to(&word, out);
+
+            // Keep allocation for the next word
+            self.word = word;
+            self.word.clear();
+        }
+    }
+
+    fn add_to_line(&mut self, word: &str, word_len: usize) {
+        const ONE_SPACE: usize = 1;
+
+        if self.n_words > 0 {
+            self.line.push(' ');
+        }
+        self.line.push_str(word);
+
+        self.n_words += 1;
+        self.total_len += word_len;
+
+        // Assuming there will space before next word
+        self.chk_len += word_len + ONE_SPACE;
+    }
+
+    fn write_line(&mut self, out: &mut String, is_last: bool) {
+        if self.n_words == 0 {
+            return;
+        }
+
+        self.begin_line(out);
+        let fit_result = FitResult {
+            text: &self.line,
+            n_words: self.n_words,
+            total_len: self.total_len,
+            total_bytes: self.line.len() - (self.n_words - 1),
+        };
+        let policy = LinePolicy::new(&self.options, is_last);
+        LinePlan::new(&fit_result, self.width(), policy).push(out);
+        self.paragraph_start = false;
+
+        self.line.clear();
+        self.n_words = 0;
+        self.total_len = 0;
+        self.chk_len = 0;
+    }
+
+    /// Width of the current line.
+    fn width(&self) -> usize {
+        match self.initial_width {
+            Some(width) if self.paragraph_start => width,
+            _ => self.line_width,
+        }
+    }
+
+    fn begin_line(&mut self, out: &mut String) {
+        if self.has_lines {
+            out.push_str(self.line_ending);
+        }
+        if self.paragraph_break {
+            out.push_str(self.line_ending);
+            self.paragraph_break = false;
+        }
+        self.has_lines = true;
+    }
+}
+
+#[cfg(feature = "std")]
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Adjuster;
+    use crate::{transform, transform_with_options, TransformOptions};
+
+    const INPUT: &str = "Вез корабль карамель, наскочил корабль на мель, \
+                         матросы две недели карамель на мели ели. Тридцатитрёхбуквенный 🤩 hello \
+                         ab—cdefgh xy cd—efghijk";
+
+    fn collect(
+        adjuster: Adjuster,
+        feed: impl FnOnce(&mut Adjuster) -> Vec<Option<String>>,
+    ) -> String {
+        let mut adjuster = adjuster;
+        let mut lines = feed(&mut adjuster);
+        lines.push(adjuster.finish());
+        lines.into_iter().flatten().collect::<Vec<_>>().join("\n")
+    }
+
+    #[test]
+    fn word_by_word() {
+        for line_width in [1, 3, 7, 12, 100] {
+            let result = collect(Adjuster::new(line_width), |adjuster| {
+                INPUT
+                    .split_whitespace()
+                    .map(|word| adjuster.push_word(word))
+                    .collect()
+            });
+            assert_eq!(result, transform(INPUT, line_width));
+        }
+    }
+
+    #[test]
+    fn random_fragments() {
+        let chars: Vec<char> = INPUT.chars().collect();
+
+        for seed in 1..20u32 {
+            let mut state = seed;
+            let mut fragments = Vec::new();
+            let mut start = 0;
+            while start < chars.len() {
+                // Tiny linear congruential generator for fragment lengths
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                let len = (state >> 16) as usize % 7;
+                let end = (start + len).min(chars.len());
+                fragments.push(chars[start..end].iter().collect::<String>());
+                start = end;
+            }
+
+            let line_width = 1 + seed % 15;
+            let result = collect(Adjuster::new(line_width), |adjuster| {
+                fragments
+                    .iter()
+                    .map(|fragment| adjuster.push_str(fragment))
+                    .collect()
+            });
+            assert_eq!(result, transform(INPUT, line_width));
+        }
+    }
+
+    #[test]
+    fn completed_lines() {
+        let mut adjuster = Adjuster::new(10);
+        assert_eq!(adjuster.push_word("first"), None);
+        assert_eq!(adjuster.push_str("para"), None);
+        assert_eq!(
+            adjuster.push_str("graph sup"),
+            Some(String::from("first     "))
+        );
+        assert_eq!(
+            adjuster.push_word("extraordinary"),
+            Some(String::from("paragraph \nsup       \nextraordin"))
+        );
+        assert_eq!(adjuster.finish(), Some(String::from("ary       ")));
+
+        assert_eq!(Adjuster::new(10).finish(), None);
+    }
+
+    #[test]
+    fn paragraphs() {
+        let input = "First paragraph.\n\nSecond\n  \nThird one";
+        let mut options = TransformOptions::new(10);
+        options.preserve_paragraphs = true;
+
+        let result = collect(Adjuster::with_options(options.clone()), |adjuster| {
+            input
+                .split_inclusive(' ')
+                .map(|fragment| adjuster.push_str(fragment))
+                .collect()
+        });
+        assert_eq!(result, transform_with_options(input, &options));
+
+        options.final_newline = true;
+        let result = collect(Adjuster::with_options(options.clone()), |adjuster| {
+            vec![adjuster.push_str(input)]
+        });
+        assert_eq!(result, transform_with_options(input, &options));
+        assert_eq!(Adjuster::with_options(options).finish(), None);
+    }
+
+    #[test]
+    fn initial_width() {
+        let input = "First paragraph.\n\nextraordinary Second\n  \nThird one";
+        for initial_width in [1, 4, 8, 20] {
+            let mut options = TransformOptions::new(10);
+            options.initial_width = Some(initial_width);
+            options.preserve_paragraphs = true;
+
+            let result = collect(Adjuster::with_options(options.clone()), |adjuster| {
+                input
+                    .split_inclusive(' ')
+                    .map(|fragment| adjuster.push_str(fragment))
+                    .collect()
+            });
+            assert_eq!(result, transform_with_options(input, &options));
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Global allocator for tests that counts allocations made by the current thread.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+const LARGE_BLOCK: usize = 4096;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+    static REALLOCS: Cell<usize> = const { Cell::new(0) };
+    static LARGE_REALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Counts {
+    pub(crate) allocs: usize,
+    pub(crate) reallocs: usize,
+    /// Reallocations to at least `LARGE_BLOCK` bytes
+    pub(crate) large_reallocs: usize,
+}
+
+fn snapshot() -> Counts {
+    Counts {
+        allocs: ALLOCS.with(Cell::get),
+        reallocs: REALLOCS.with(Cell::get),
+        large_reallocs: LARGE_REALLOCS.with(Cell::get),
+    }
+}
+
+struct Counter;
+
+unsafe impl GlobalAlloc for Counter {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = REALLOCS.try_with(|count| count.set(count.get() + 1));
+        if new_size >= LARGE_BLOCK {
+            let _ = LARGE_REALLOCS.try_with(|count| count.set(count.get() + 1));
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counter = Counter;
+
+/// Runs `f` and counts allocations it made.
+pub(crate) fn count<T>(f: impl FnOnce() -> T) -> (T, Counts) {
+    let before = snapshot();
+    let value = f();
+    let after = snapshot();
+    let counts = Counts {
+        allocs: after.allocs - before.allocs,
+        reallocs: after.reallocs - before.reallocs,
+        large_reallocs: after.large_reallocs - before.large_reallocs,
+    };
+    (value, counts)
+}
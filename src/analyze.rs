@@ -0,0 +1,171 @@
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+
+use crate::{
+    stats::StatsBuilder, width::in_units, LinePart, Lines, Paragraphs, TransformOptions, WrapStats,
+};
+
+/// Splits `input` into paragraphs at whitespace-only lines and wraps every
+/// paragraph on its own, like [`crate::transform_with_options`] does with
+/// `preserve_paragraphs`.
+///
+/// Joining the lines of every paragraph with line breaks, and paragraphs
+/// with empty lines between them, gives the output of
+/// `transform_with_options` with `preserve_paragraphs`. Only the width,
+/// `initial_width`, `width_mode`, `align` and `pad_last_line` of `options`
+/// are used.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn analyze<'a>(input: &'a str, options: &TransformOptions) -> Vec<Paragraph<'a>> {
+    let options = &*in_units(options);
+    let line_width = options.line_width as usize;
+    let mut paragraphs = Vec::new();
+    for text in Paragraphs::new(input, true) {
+        let start = text.as_ptr() as usize - input.as_ptr() as usize;
+        let mut stats = StatsBuilder::default();
+        let mut lines = Vec::new();
+
+        let mut planned = Lines::new(text, options, line_width);
+        while let Some(plan) = stats.add_next(&mut planned) {
+            let mut line = JustifiedLine {
+                text: String::with_capacity(plan.len()),
+                words: Vec::with_capacity(plan.n_words),
+                padding: plan.padding(),
+            };
+            plan.push(&mut line.text);
+            plan.for_each_part(|part| match part {
+                LinePart::Word(word) if !word.is_empty() => {
+                    // Lines borrow options as long as the input
+                    let offset = word.as_ptr() as usize - input.as_ptr() as usize;
+                    line.words.push(&input[offset..offset + word.len()]);
+                }
+                _ => {}
+            });
+            lines.push(line);
+        }
+
+        paragraphs.push(Paragraph {
+            range: start..start + text.trim_end().len(),
+            prefix: prefix(text),
+            lines,
+            stats: stats.finish(),
+        });
+    }
+    paragraphs
+}
+
+/// Paragraph of the input, returned by [`analyze`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paragraph<'a> {
+    /// Byte range of the paragraph in the input, from the start of its
+    /// first line to its last non-whitespace char.
+    pub range: Range<usize>,
+    /// Indent of the first line, together with a bullet after it and the
+    /// spaces that follow: `-`, `*`, `+`, `•`, or a number followed by
+    /// `.` or `)`. The bullet is still wrapped as a word.
+    pub prefix: &'a str,
+    /// Output lines of the paragraph.
+    pub lines: Vec<JustifiedLine<'a>>,
+    /// Metrics of the lines, as [`crate::stats`] gives them.
+    pub stats: WrapStats,
+}
+
+/// Output line of a [`Paragraph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JustifiedLine<'a> {
+    /// Line as it is written, without line ending.
+    pub text: String,
+    /// Words of the line, or parts of split words, borrowed from the input.
+    pub words: Vec<&'a str>,
+    /// Spaces added to fill the line, i.e. all spaces except a single
+    /// one between adjacent words.
+    pub padding: usize,
+}
+
+/// Indent and bullet at the start of `text`.
+fn prefix(text: &str) -> &str {
+    let is_space = |chr: char| chr == ' ' || chr == '\t';
+    let rest = text.trim_start_matches(is_space);
+    let indent = text.len() - rest.len();
+
+    let bullet_len = match rest.chars().next() {
+        Some(chr @ ('-' | '*' | '+' | '•')) => chr.len_utf8(),
+        Some('0'..='9') => {
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            match rest.as_bytes().get(digits) {
+                Some(b'.' | b')') => digits + 1,
+                _ => 0,
+            }
+        }
+        _ => 0,
+    };
+    let after = &rest[bullet_len..];
+    if bullet_len == 0 || !after.starts_with(char::is_whitespace) {
+        return &text[..indent];
+    }
+    let spaces = after.len() - after.trim_start_matches(is_space).len();
+    &text[..indent + bullet_len + spaces]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::analyze;
+    use crate::{stats, transform_with_options, Alignment, TransformOptions};
+
+    const INPUT: &str = "  - Съешь ещё этих мягких\nфранцузских булок,  \n\n \t\n\
+                         12) да выпей чаю\r\n\r\nТридцатитрёхбуквенный\n*no bullet\n";
+
+    #[test]
+    fn source_ranges() {
+        let paragraphs = analyze(INPUT, &TransformOptions::new(12));
+        let texts: Vec<&str> = paragraphs.iter().map(|p| &INPUT[p.range.clone()]).collect();
+        assert_eq!(
+            texts,
+            [
+                "  - Съешь ещё этих мягких\nфранцузских булок,",
+                "12) да выпей чаю",
+                "Тридцатитрёхбуквенный\n*no bullet"
+            ]
+        );
+
+        let prefixes: Vec<&str> = paragraphs.iter().map(|p| p.prefix).collect();
+        assert_eq!(prefixes, ["  - ", "12) ", ""]);
+
+        let last = &paragraphs[2];
+        assert_eq!(last.stats, stats(&INPUT[last.range.clone()], 12));
+        assert_eq!(last.lines[1].words, ["буквенный"]);
+        assert_eq!(last.lines[1].text, "буквенный   ");
+        assert_eq!(last.lines[1].padding, 3);
+        assert_eq!(last.lines[2].words, ["*no", "bullet"]);
+        assert_eq!(last.lines[2].padding, 2);
+        assert_eq!(last.stats.forced_splits, 1);
+        assert_eq!(analyze(" \n\t", &TransformOptions::new(12)), []);
+    }
+
+    #[test]
+    fn renders_like_transform() {
+        for line_width in [1, 5, 12, 40] {
+            let mut options = TransformOptions::new(line_width);
+            options.align = Alignment::Center;
+            options.pad_last_line = line_width % 2 == 0;
+            options.initial_width = Some(line_width + 2);
+            options.preserve_paragraphs = true;
+
+            let paragraphs: Vec<String> = analyze(INPUT, &options)
+                .iter()
+                .map(|paragraph| {
+                    let lines: Vec<&str> = paragraph
+                        .lines
+                        .iter()
+                        .map(|line| line.text.as_str())
+                        .collect();
+                    lines.join("\n")
+                })
+                .collect();
+            assert_eq!(
+                paragraphs.join("\n\n"),
+                transform_with_options(INPUT, &options)
+            );
+        }
+    }
+}
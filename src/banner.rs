@@ -0,0 +1,88 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{truncate_to_width, Alignment};
+
+/// Single line of exactly `width` chars with `text` centered in it and
+/// the rest filled with `pattern`, e.g. `"== Results =="`.
+///
+/// See [`banner_aligned`].
+pub fn banner(text: &str, width: u32, pattern: &str) -> String {
+    banner_aligned(text, width, pattern, Alignment::Center)
+}
+
+/// Same as [`banner`], but `text` is placed like a single word of a line
+/// aligned by `align`, so `Justify` places it like `Left`.
+///
+/// The fill is `pattern` repeated from the start of the line and cut at
+/// char boundaries, so fills on both sides look like a single pattern
+/// behind the text. The fill next to the text starts or ends with a space
+/// instead. Without text the line is all pattern, and an empty pattern
+/// fills with spaces. Text wider than `width` is cut to it. Widths are
+/// counted in chars.
+pub fn banner_aligned(text: &str, width: u32, pattern: &str, align: Alignment) -> String {
+    let text = truncate_to_width(text, width, "");
+    let width = width as usize;
+    let free_space = width - text.chars().count();
+    let before = match align {
+        Alignment::Right => free_space,
+        Alignment::Center => free_space / 2,
+        Alignment::Left | Alignment::Justify => 0,
+    };
+
+    let mut pattern: Vec<char> = pattern.chars().collect();
+    if pattern.is_empty() {
+        pattern.push(' ');
+    }
+    let fill = |col: usize| pattern[col % pattern.len()];
+    let spaced = !text.is_empty();
+
+    let mut result = String::with_capacity(width * pattern.len() + text.len());
+    result.extend((0..before).map(fill));
+    if spaced && before > 0 {
+        result.pop();
+        result.push(' ');
+    }
+    result += &text;
+
+    let after = before + text.chars().count();
+    if spaced && after < width {
+        result.push(' ');
+        result.extend((after + 1..width).map(fill));
+    } else {
+        result.extend((after..width).map(fill));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{banner, banner_aligned};
+    use crate::Alignment;
+
+    #[test]
+    fn patterns() {
+        assert_eq!(banner("Results", 21, "-"), "------ Results ------");
+        assert_eq!(banner("Results", 22, "-"), "------ Results -------");
+        assert_eq!(banner("Results", 21, "=-"), "=-=-=- Results -=-=-=");
+        assert_eq!(banner("Итоги", 12, "─"), "── Итоги ───");
+        assert_eq!(banner("Итоги", 13, "─═"), "─═─ Итоги ─═─");
+
+        // Without text, or without room around it
+        assert_eq!(banner("", 5, "=-"), "=-=-=");
+        assert_eq!(banner("", 3, "─"), "───");
+        assert_eq!(banner("", 0, "─"), "");
+        assert_eq!(banner("Итоги", 6, "─"), "Итоги ");
+        assert_eq!(banner("Итоги", 7, "─"), " Итоги ");
+        assert_eq!(banner("Итоги", 3, "─"), "Ито");
+        assert_eq!(banner("Итоги", 8, ""), " Итоги  ");
+    }
+
+    #[test]
+    fn aligned() {
+        let line = |align| banner_aligned("Results", 14, "=-", align);
+        assert_eq!(line(Alignment::Left), "Results =-=-=-");
+        assert_eq!(line(Alignment::Justify), "Results =-=-=-");
+        assert_eq!(line(Alignment::Right), "=-=-=- Results");
+        assert_eq!(line(Alignment::Center), "=- Results -=-");
+    }
+}
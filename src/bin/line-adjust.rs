@@ -0,0 +1,222 @@
+//! Reads text from stdin and writes it adjusted to stdout.
+//!
+//! Input is processed line by line: every output line is written as soon
+//! as it can no longer change, so the tool works on endless streams.
+
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+use line_adjustment::{transform_with_options, LineEnding, TransformOptions};
+
+const USAGE: &str = "\
+Usage: line-adjust [OPTIONS] < input
+
+Options:
+  -w, --width N            Width of output lines (default: terminal width)
+  -a, --align MODE         left, right, center or justify (default: justify)
+      --no-pad-last        Do not pad or justify the last line of a paragraph
+      --preserve-paragraphs
+                           Keep paragraphs separated by blank lines
+      --crlf               Terminate lines with CRLF
+  -h, --help               Print this message";
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("line-adjust: {message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        // Reader has gone away, e.g. `line-adjust | head`
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("line-adjust: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Returns `Ok(None)` when help is requested.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<TransformOptions>, String> {
+    let mut options = TransformOptions::for_terminal();
+
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => {
+                (name.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+        let mut value = |name: &str| {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for {name}"))
+        };
+
+        match name.as_str() {
+            "-w" | "--width" => {
+                let width = value(&name)?;
+                options.line_width = match width.parse() {
+                    Ok(0) => return Err(String::from("width must be greater than 0")),
+                    Ok(width) if width > options.max_width => {
+                        return Err(format!("width must be at most {}", options.max_width))
+                    }
+                    Ok(width) => width,
+                    Err(_) => return Err(format!("invalid width '{width}'")),
+                };
+            }
+            "-a" | "--align" => {
+                options.align = value(&name)?.parse().map_err(|err| format!("{err}"))?;
+            }
+            "--no-pad-last" => options.pad_last_line = false,
+            "--preserve-paragraphs" => options.preserve_paragraphs = true,
+            "--crlf" => options.line_ending = LineEnding::CrLf,
+            "-h" | "--help" => return Ok(None),
+            other => return Err(format!("unknown argument '{other}'")),
+        }
+    }
+
+    Ok(Some(options))
+}
+
+fn run(options: &TransformOptions) -> io::Result<()> {
+    let mut output = io::stdout().lock();
+    let mut paragraph = Paragraph::new(options);
+    let mut has_output = false;
+
+    for (idx, line) in io::stdin().lock().split(b'\n').enumerate() {
+        let line = String::from_utf8(line?).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("input is not valid UTF-8 (line {})", idx + 1),
+            )
+        })?;
+
+        if options.preserve_paragraphs && line.trim().is_empty() {
+            has_output |= paragraph.finish(&mut output, has_output)?;
+            continue;
+        }
+
+        paragraph.push(&line);
+        has_output |= paragraph.write_complete(&mut output, has_output)?;
+    }
+
+    paragraph.finish(&mut output, has_output)?;
+    output.flush()
+}
+
+/// Words of the current paragraph that are not written yet.
+struct Paragraph<'a> {
+    options: &'a TransformOptions,
+    words: Vec<String>,
+    started: bool,
+}
+
+impl<'a> Paragraph<'a> {
+    fn new(options: &'a TransformOptions) -> Self {
+        Paragraph {
+            options,
+            words: Vec::new(),
+            started: false,
+        }
+    }
+
+    fn push(&mut self, line: &str) {
+        self.words.extend(line.split_whitespace().map(String::from));
+    }
+
+    /// Writes lines that further words can no longer affect. Returns
+    /// whether anything was written.
+    fn write_complete(&mut self, output: &mut impl Write, has_output: bool) -> io::Result<bool> {
+        let line_width = self.options.line_width as usize;
+        let mut written = false;
+
+        loop {
+            // Enough words to overflow the first line, plus one more, so
+            // the first line is adjusted as a non-last one.
+            let mut window_len = 0;
+            let mut window = 0;
+            while window < self.words.len() && window_len <= line_width {
+                window_len += self.words[window].chars().count() + 1;
+                window += 1;
+            }
+            if window == self.words.len() {
+                break;
+            }
+            window += 1;
+
+            let adjusted = self.adjust(&self.words[..window]);
+            let first_len = self.words[0].chars().count();
+            if first_len > line_width {
+                // Parts of a long word take whole lines, except the last
+                // one, which starts the next line
+                let n_parts = (first_len - 1) / line_width;
+                let lines: Vec<&str> = adjusted.split('\n').take(n_parts).collect();
+                self.write_lines(output, &lines, has_output || written)?;
+                self.words[0] = self.words[0].chars().skip(n_parts * line_width).collect();
+            } else {
+                let first_line = adjusted.split('\n').next().unwrap_or_default();
+                self.write_lines(output, &[first_line], has_output || written)?;
+                self.words.drain(..first_line.split_whitespace().count());
+            }
+            written = true;
+        }
+
+        Ok(written)
+    }
+
+    /// Writes the remaining words, ending the paragraph. Returns whether
+    /// anything was written.
+    fn finish(&mut self, output: &mut impl Write, has_output: bool) -> io::Result<bool> {
+        if self.words.is_empty() {
+            self.started = false;
+            return Ok(false);
+        }
+
+        let adjusted = self.adjust(&self.words);
+        let lines: Vec<&str> = adjusted.split('\n').collect();
+        self.write_lines(output, &lines, has_output)?;
+
+        self.words.clear();
+        self.started = false;
+        Ok(true)
+    }
+
+    fn adjust(&self, words: &[String]) -> String {
+        let mut options = TransformOptions::new(self.options.line_width);
+        options.align = self.options.align;
+        options.pad_last_line = self.options.pad_last_line;
+        transform_with_options(&words.join(" "), &options)
+    }
+
+    fn write_lines(
+        &mut self,
+        output: &mut impl Write,
+        lines: &[&str],
+        has_output: bool,
+    ) -> io::Result<()> {
+        let line_ending = self.options.line_ending.as_str();
+
+        // Blank line between paragraphs
+        if !self.started && has_output {
+            output.write_all(line_ending.as_bytes())?;
+        }
+        self.started = true;
+
+        for line in lines {
+            output.write_all(line.as_bytes())?;
+            output.write_all(line_ending.as_bytes())?;
+        }
+
+        Ok(())
+    }
+}
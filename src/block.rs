@@ -0,0 +1,277 @@
+use alloc::string::String;
+use core::fmt;
+
+use crate::{
+    push_spaces, tokens, transform, Alignment, FitResult, LinePlan, LinePolicy, TransformOptions,
+    Word,
+};
+
+/// Text every line of which is exactly `width` chars wide, like the
+/// output of [`crate::transform`].
+///
+/// Lines are split like [`str::lines`] splits them, so they end with `\n`
+/// or `\r\n`, and a line ending after the last line is allowed. Text
+/// without lines is a block of any width.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JustifiedBlock {
+    text: String,
+    width: u32,
+    height: usize,
+}
+
+/// Same as [`crate::transform`], but the output carries its width.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn transform_block(input: &str, line_width: u32) -> JustifiedBlock {
+    let text = transform(input, line_width);
+    JustifiedBlock {
+        height: text.lines().count(),
+        text,
+        width: line_width,
+    }
+}
+
+impl JustifiedBlock {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn into_string(self) -> String {
+        self.text
+    }
+
+    /// Lines without line endings.
+    pub fn lines(&self) -> core::str::Lines<'_> {
+        self.text.lines()
+    }
+
+    /// Width of every line, in chars.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Number of lines.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+impl TryFrom<(&str, u32)> for JustifiedBlock {
+    type Error = BlockWidthError;
+
+    /// Checks that every line of the text is `width` chars wide.
+    fn try_from((text, width): (&str, u32)) -> Result<Self, Self::Error> {
+        let mut height = 0;
+        for (line, text) in text.lines().enumerate() {
+            let line_width = text.chars().count();
+            if line_width != width as usize {
+                return Err(BlockWidthError {
+                    line,
+                    width: line_width,
+                    expected: width,
+                });
+            }
+            height += 1;
+        }
+
+        Ok(JustifiedBlock {
+            text: String::from(text),
+            width,
+            height,
+        })
+    }
+}
+
+impl fmt::Display for JustifiedBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Pads every line of `input` to exactly `width` chars as `align` says,
+/// without wrapping it again, e.g. text broken into lines by another tool.
+///
+/// Whitespace at both ends of a line is dropped first. `Left`, `Right`
+/// and `Center` add spaces around the rest of the line, `Justify`
+/// stretches gaps between its words like [`crate::transform`] does, and
+/// pads a line of a single word after it. Blank lines become `width`
+/// spaces. Lines are split like [`str::lines`] splits them and keep their
+/// line endings, a line ending at the end of `input` included.
+///
+/// Fails with the first line that is wider than `width`, counted from 0.
+pub fn pad_block(input: &str, width: u32, align: Alignment) -> Result<String, LineTooLong> {
+    let mut options = TransformOptions::new(width);
+    options.align = align;
+    let line_width = width as usize;
+
+    let mut result = String::with_capacity(input.len());
+    for (line, text) in input.split_inclusive('\n').enumerate() {
+        let content = text.trim();
+        let ending = &text[text.trim_end_matches(['\r', '\n']).len()..];
+        let content_width = Word::new(content).width;
+        if content_width > line_width {
+            return Err(LineTooLong {
+                line,
+                width: content_width,
+                max_width: width,
+            });
+        }
+
+        let free_space = line_width - content_width;
+        let before = match align {
+            Alignment::Right => free_space,
+            Alignment::Center => free_space / 2,
+            Alignment::Left => 0,
+            Alignment::Justify => {
+                let mut fit_result = FitResult::word(Word::new(""));
+                fit_result.text = content;
+                for word in tokens(content) {
+                    fit_result.n_words += 1;
+                    fit_result.total_len += Word::new(word).width;
+                    fit_result.total_bytes += word.len();
+                }
+                let policy = LinePolicy::new(&options, false);
+                LinePlan::new(&fit_result, line_width, policy).push(&mut result);
+                result += ending;
+                continue;
+            }
+        };
+        push_spaces(&mut result, before);
+        result += content;
+        push_spaces(&mut result, free_space - before);
+        result += ending;
+    }
+    Ok(result)
+}
+
+/// Returned when text is not a [`JustifiedBlock`]: the first line that
+/// is not `expected` chars wide, counted from 0, is `width` chars wide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockWidthError {
+    pub line: usize,
+    pub width: usize,
+    pub expected: u32,
+}
+
+impl fmt::Display for BlockWidthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {} is {} chars wide instead of {}",
+            self.line, self.width, self.expected
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BlockWidthError {}
+
+/// Returned by [`pad_block`]: `line`, counted from 0, is `width` chars
+/// wide, more than `max_width`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineTooLong {
+    pub line: usize,
+    pub width: usize,
+    pub max_width: u32,
+}
+
+impl fmt::Display for LineTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {} is {} chars wide, more than {}",
+            self.line, self.width, self.max_width
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LineTooLong {}
+
+#[cfg(test)]
+mod tests {
+    use super::{pad_block, transform_block, BlockWidthError, JustifiedBlock, LineTooLong};
+    use crate::{transform, Alignment};
+
+    const INPUT: &str = "Съешь ещё этих мягких французских булок, да выпей чаю";
+
+    #[test]
+    fn from_transform() {
+        let block = transform_block(INPUT, 12);
+        assert_eq!(block.as_str(), transform(INPUT, 12));
+        assert_eq!(block.to_string(), transform(INPUT, 12));
+        assert_eq!(block.width(), 12);
+        assert_eq!(block.height(), 5);
+        assert_eq!(block.lines().nth(1), Some("этих  мягких"));
+        assert!(block.lines().all(|line| line.chars().count() == 12));
+
+        let block = transform_block("", 0);
+        assert_eq!((block.height(), block.lines().next()), (0, None));
+    }
+
+    #[test]
+    fn validated() {
+        let text = "Съешь    ещё\r\nэтих  мягких\n";
+        let block = JustifiedBlock::try_from((text, 12)).unwrap();
+        assert_eq!(block.height(), 2);
+        assert_eq!(block.into_string(), text);
+        assert_eq!(
+            JustifiedBlock::try_from((transform(INPUT, 12).as_str(), 12)),
+            Ok(transform_block(INPUT, 12))
+        );
+        assert!(JustifiedBlock::try_from(("", 7)).is_ok());
+
+        let error = BlockWidthError {
+            line: 1,
+            width: 11,
+            expected: 12,
+        };
+        let text = "Съешь    ещё\nэтих мягких\nфранцузских";
+        assert_eq!(JustifiedBlock::try_from((text, 12)), Err(error.clone()));
+        assert_eq!(error.to_string(), "line 1 is 11 chars wide instead of 12");
+
+        // Empty lines are lines too
+        let error = JustifiedBlock::try_from(("ab\n\ncd", 2)).unwrap_err();
+        assert_eq!((error.line, error.width), (1, 0));
+    }
+
+    #[test]
+    fn padded() {
+        let input = "Съешь ещё\n  этих\r\nмягких   булок\n";
+        let padded = |align| pad_block(input, 14, align).unwrap();
+        assert_eq!(
+            padded(Alignment::Left),
+            "Съешь ещё     \nэтих          \r\nмягких   булок\n"
+        );
+        assert_eq!(
+            padded(Alignment::Right),
+            "     Съешь ещё\n          этих\r\nмягких   булок\n"
+        );
+        assert_eq!(
+            padded(Alignment::Center),
+            "  Съешь ещё   \n     этих     \r\nмягких   булок\n"
+        );
+        assert_eq!(
+            padded(Alignment::Justify),
+            "Съешь      ещё\nэтих          \r\nмягких   булок\n"
+        );
+        assert_eq!(
+            pad_block("a\n\nb", 2, Alignment::Right).unwrap(),
+            " a\n  \n b"
+        );
+        assert_eq!(pad_block("", 2, Alignment::Left).unwrap(), "");
+    }
+
+    #[test]
+    fn too_long() {
+        let error = LineTooLong {
+            line: 2,
+            width: 13,
+            max_width: 12,
+        };
+        let input = "Съешь ещё\nэтих\nмягких булок!\n";
+        assert_eq!(pad_block(input, 12, Alignment::Left), Err(error.clone()));
+        assert_eq!(error.to_string(), "line 2 is 13 chars wide, more than 12");
+        assert!(pad_block(input, 13, Alignment::Left).is_ok());
+    }
+}
@@ -0,0 +1,63 @@
+use alloc::string::String;
+
+use crate::{transform, Adjuster, TransformOptions};
+
+/// Same as [`crate::transform`] of `String::from_utf8_lossy(input)`, but
+/// the text is only copied when `input` is not valid UTF-8. Then invalid
+/// sequences are replaced by U+FFFD as the valid chunks around them are
+/// adjusted.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn transform_bytes(input: &[u8], line_width: u32) -> String {
+    if let Ok(text) = core::str::from_utf8(input) {
+        return transform(text, line_width);
+    }
+
+    let mut adjuster = Adjuster::with_options(TransformOptions::new(line_width));
+    let mut result = String::with_capacity(input.len());
+    for chunk in input.utf8_chunks() {
+        adjuster.push_str_to(chunk.valid(), &mut result);
+        if !chunk.invalid().is_empty() {
+            adjuster.push_str_to(
+                char::REPLACEMENT_CHARACTER.encode_utf8(&mut [0; 4]),
+                &mut result,
+            );
+        }
+    }
+    adjuster.finish_to(&mut result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transform_bytes;
+    use crate::transform;
+
+    fn assert_lossy(input: &[u8], line_width: u32) {
+        let expected = transform(&String::from_utf8_lossy(input), line_width);
+        assert_eq!(transform_bytes(input, line_width), expected);
+    }
+
+    #[test]
+    fn same_as_lossy() {
+        let inputs: [&[u8]; 6] = [
+            b"Lorem ipsum dolor sit amet, consectetur adipiscing elit",
+            "Съешь ещё этих мягких французских булок, да выпей чаю".as_bytes(),
+            b"stray \xFF byte\xFF\xFF and\xFF \xFFalone \xFF",
+            b"\xD0\xA1\xD1 cut \xE2\x82 chars\n\n\xF0\x9F\xA4",
+            b"\xFF",
+            b"",
+        ];
+        for input in inputs {
+            for line_width in [1, 3, 8, 12] {
+                assert_lossy(input, line_width);
+            }
+        }
+        assert_eq!(
+            transform_bytes(b"ab \xFFcd \xFF", 4),
+            "ab  \n\u{FFFD}cd \n\u{FFFD}   "
+        );
+
+        assert_eq!(transform_bytes(b" ", 0), "");
+    }
+}
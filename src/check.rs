@@ -0,0 +1,318 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use crate::{width::in_units, Alignment, TransformOptions, WidthMode, Word};
+
+/// Checks text that is wrapped already, e.g. by another tool, against the
+/// rules of [`crate::transform_with_options`] output for lines of `width`
+/// and `options`, and returns every broken rule. Lines count from 0.
+///
+/// - every line is exactly `width` wide, or `initial_width` for the first
+///   line of a paragraph, except the last line of a paragraph without
+///   `pad_last_line`, which may be narrower;
+/// - lines are empty only between paragraphs, with `preserve_paragraphs`;
+/// - justified lines of several words have no spaces at their ends, but
+///   the last lines of paragraphs without `pad_last_line`;
+/// - there are no spaces after the last word of lines that are not
+///   padded after it, i.e. right-aligned lines and the last lines of
+///   paragraphs without `pad_last_line`;
+/// - no line that is filled up exactly, without padding or stretched
+///   gaps, ends with a word broken after a hyphen, e.g. `well-`, when the
+///   next line starts with a lowercase letter and the whole word would fit
+///   a line. This one is a guess, as such a word may be two words.
+///
+/// Widths are measured like the output is, and only `initial_width`,
+/// `width_mode`, `markup`, `align`, `pad_last_line`,
+/// `preserve_paragraphs` and `hang_punctuation` of `options` are used. A
+/// line ending after the last line is allowed.
+pub fn check(input: &str, width: u32, options: &TransformOptions) -> Vec<Violation> {
+    let mut options = options.clone();
+    options.line_width = width;
+    let options = &*in_units(&options);
+
+    let mut violations = Vec::new();
+    let lines: Vec<&str> = input.lines().collect();
+    let is_blank = |idx: Option<usize>| {
+        idx.and_then(|idx| lines.get(idx))
+            .is_none_or(|text| text.trim().is_empty())
+    };
+
+    for (line, &text) in lines.iter().enumerate() {
+        if text.trim().is_empty() {
+            let between = options.preserve_paragraphs && !is_blank(line.checked_sub(1));
+            if !between || is_blank(Some(line + 1)) || !text.is_empty() {
+                violations.push(Violation::EmptyLine { line });
+            }
+            continue;
+        }
+
+        let line_width = match options.initial_width {
+            Some(initial_width) if is_blank(line.checked_sub(1)) => initial_width as usize,
+            _ => options.line_width as usize,
+        };
+        let is_last = is_blank(Some(line + 1));
+        let unpadded = is_last && !options.pad_last_line;
+        let text_width = visible_width(text, options);
+        if is_wrong_width(text, text_width, line_width, unpadded, options) {
+            violations.push(Violation::WrongWidth {
+                line,
+                width: text_width,
+                expected: line_width,
+            });
+        }
+
+        let padded_ends =
+            text.starts_with(char::is_whitespace) || text.ends_with(char::is_whitespace);
+        let several_words = text.split_whitespace().nth(1).is_some();
+        if options.align == Alignment::Justify && several_words && !unpadded && padded_ends {
+            violations.push(Violation::UnjustifiedGap { line });
+        } else if (options.align == Alignment::Right || unpadded)
+            && text.ends_with(char::is_whitespace)
+        {
+            violations.push(Violation::TrailingWhitespace { line });
+        }
+
+        // Only a line filled up exactly, with single spaces between words,
+        // may have had its last word split to fill it
+        let filled = text_width == line_width && text.split(' ').all(|word| !word.is_empty());
+        let next_word = lines
+            .get(line + 1)
+            .and_then(|text| text.split_whitespace().next());
+        let broken = text
+            .split_whitespace()
+            .next_back()
+            .zip(next_word)
+            .filter(|_| filled)
+            .filter(|(end, start)| {
+                let mut chars = end.chars().rev();
+                let after_letter =
+                    chars.next() == Some('-') && chars.next().is_some_and(char::is_alphabetic);
+                let word_width = visible_width(end, options) + visible_width(start, options);
+                after_letter
+                    && start.starts_with(char::is_lowercase)
+                    && word_width <= options.line_width as usize
+            });
+        if broken.is_some() {
+            violations.push(Violation::BrokenWord { line });
+        }
+    }
+    violations
+}
+
+/// Width of `text` in chars or half units, without markers of `markup`.
+pub(crate) fn visible_width(text: &str, options: &TransformOptions) -> usize {
+    let markers = options
+        .markup
+        .as_ref()
+        .map_or(0, |markup| markup.markers_width(text));
+    let width = match options.width_mode {
+        WidthMode::Chars => text.chars().count(),
+        WidthMode::HalfWidthUnits => Word::half_units(text).width,
+    };
+    width - markers
+}
+
+/// Whether a line that ends with `body` and is `width` wide breaks the
+/// rule that it is `expected` wide, or narrower when `may_be_narrower`.
+/// With `hang_punctuation`, it may be wider by its last char when that is
+/// one of `hanging_punctuation`.
+pub(crate) fn is_wrong_width(
+    body: &str,
+    width: usize,
+    expected: usize,
+    may_be_narrower: bool,
+    options: &TransformOptions,
+) -> bool {
+    let hanging = body
+        .chars()
+        .next_back()
+        .filter(|chr| options.hang_punctuation && options.hanging_punctuation.contains(chr))
+        .map_or(0, |chr| {
+            visible_width(chr.encode_utf8(&mut [0; 4]), options)
+        });
+    width != expected
+        && !(may_be_narrower && width < expected)
+        && !(hanging > 0 && width == expected + hanging)
+}
+
+/// Rule broken by text, found by [`check`], or by `verify_invariants`
+/// with the `test-util` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// Line is not as wide as it should be, in chars.
+    WrongWidth {
+        line: usize,
+        width: usize,
+        expected: usize,
+    },
+    /// Words of the output differ from the words of the input, starting
+    /// with the word `found` on `line`. `found` is empty when the output
+    /// ends before all words of the input.
+    WordsMismatch {
+        line: usize,
+        expected: String,
+        found: String,
+    },
+    /// Line is empty, but does not separate paragraphs.
+    EmptyLine { line: usize },
+    /// Page has more lines than `lines_per_page`.
+    PageTooLong {
+        page: usize,
+        lines: usize,
+        max_lines: usize,
+    },
+    /// Line does not start with `margin_left` spaces.
+    MissingMargin { line: usize },
+    /// Output has more lines of text than `max_lines`.
+    TooManyLines { lines: usize, max_lines: usize },
+    /// Output has fewer lines than `min_lines`.
+    TooFewLines { lines: usize, min_lines: usize },
+    /// Line ends with whitespace where lines are not padded.
+    TrailingWhitespace { line: usize },
+    /// Line of several words is padded at its ends instead of having its
+    /// gaps stretched.
+    UnjustifiedGap { line: usize },
+    /// Line filled up exactly ends with a word broken after a hyphen, which
+    /// would fit the next line whole.
+    BrokenWord { line: usize },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::WrongWidth {
+                line,
+                width,
+                expected,
+            } => write!(f, "line {line} is {width} chars wide instead of {expected}"),
+            Violation::WordsMismatch {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line} has '{found}' where '{expected}' is expected"
+            ),
+            Violation::EmptyLine { line } => write!(f, "line {line} is empty"),
+            Violation::PageTooLong {
+                page,
+                lines,
+                max_lines,
+            } => write!(f, "page {page} has {lines} lines, more than {max_lines}"),
+            Violation::MissingMargin { line } => write!(f, "line {line} has no margin"),
+            Violation::TooManyLines { lines, max_lines } => {
+                write!(f, "output has {lines} lines, more than {max_lines}")
+            }
+            Violation::TooFewLines { lines, min_lines } => {
+                write!(f, "output has {lines} lines, fewer than {min_lines}")
+            }
+            Violation::TrailingWhitespace { line } => {
+                write!(f, "line {line} ends with whitespace")
+            }
+            Violation::UnjustifiedGap { line } => write!(f, "line {line} is not justified"),
+            Violation::BrokenWord { line } => write!(f, "line {line} ends with a broken word"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Violation {}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, Violation};
+    use crate::{transform_with_options, Alignment, TransformOptions};
+
+    const INPUT: &str = "Съешь ещё этих мягких французских булок, да выпей чаю. \
+                         Тридцатитрёхбуквенный well-known";
+
+    #[test]
+    fn transform_output() {
+        for align in [
+            Alignment::Left,
+            Alignment::Right,
+            Alignment::Center,
+            Alignment::Justify,
+        ] {
+            for pad_last_line in [false, true] {
+                let mut options = TransformOptions::new(12);
+                options.align = align;
+                options.pad_last_line = pad_last_line;
+                options.preserve_paragraphs = true;
+                options.initial_width = Some(9);
+                let input = format!("{INPUT}\n\n{INPUT}");
+                let output = transform_with_options(&input, &options);
+                assert_eq!(check(&output, 12, &options), [], "{output:?}");
+            }
+        }
+        assert_eq!(check("", 12, &TransformOptions::new(12)), []);
+    }
+
+    #[test]
+    fn any_transform_output() {
+        let inputs = [
+            INPUT,
+            "Поддержка кодировки utf-8 в коде",
+            "Бык тупогуб, тупогубенький бычок, у быка губа тупа.",
+            "Hand-made, well-known x-ray pictures of self-made men",
+            "ab cd\u{2014}efghijk rock\u{2014}paper\u{2014}scissors, 1939\u{2013}1945",
+        ];
+        for input in inputs {
+            for line_width in 1..=40 {
+                for align in [Alignment::Left, Alignment::Right, Alignment::Justify] {
+                    let mut options = TransformOptions::new(line_width);
+                    options.align = align;
+                    let output = transform_with_options(input, &options);
+                    assert_eq!(check(&output, line_width, &options), [], "{output:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn corrupted() {
+        let options = TransformOptions::new(12);
+        let output = transform_with_options(INPUT, &options);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "Съешь    ещё");
+
+        let mut corrupted = lines.clone();
+        corrupted[0] = "Съешь   ещё";
+        corrupted[1] = "этих мягких ";
+        let expected = [
+            Violation::WrongWidth {
+                line: 0,
+                width: 11,
+                expected: 12,
+            },
+            Violation::UnjustifiedGap { line: 1 },
+        ];
+        assert_eq!(check(&corrupted.join("\n"), 12, &options), expected);
+
+        let mut options = TransformOptions::new(12);
+        options.align = Alignment::Right;
+        options.pad_last_line = false;
+        let expected = [
+            Violation::TrailingWhitespace { line: 0 },
+            Violation::EmptyLine { line: 1 },
+        ];
+        assert_eq!(check("  Съешь ещё \n\nчаю", 12, &options), expected);
+
+        let options = TransformOptions::new(14);
+        let expected = [Violation::BrokenWord { line: 0 }];
+        assert_eq!(
+            check("abcdefgh well-\nknown         ", 14, &options),
+            expected
+        );
+        assert_eq!(expected[0].to_string(), "line 0 ends with a broken word");
+        assert_eq!(check("well-\nknown", 5, &TransformOptions::new(5)), []);
+        assert_eq!(check("abcdefgh Well-\nKnown         ", 14, &options), []);
+        // Padded lines and stretched gaps are not filled up by splitting
+        let expected = [Violation::UnjustifiedGap { line: 0 }];
+        assert_eq!(
+            check("abcdefg well- \nknown         ", 14, &options),
+            expected
+        );
+        assert_eq!(check("abcdefg  well-\nknown         ", 14, &options), []);
+    }
+}
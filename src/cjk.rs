@@ -0,0 +1,135 @@
+//! Spaces between CJK and Latin text.
+
+use alloc::{borrow::Cow, string::String};
+
+use crate::TransformOptions;
+
+/// Whether `chr` is a Han ideograph, kana or another CJK letter that takes
+/// a space next to Latin text. CJK punctuation, e.g. `，` or `「`, is not.
+fn is_cjk(chr: char) -> bool {
+    matches!(
+        chr,
+        '\u{2E80}'..='\u{2EFF}'
+            | '\u{2F00}'..='\u{2FDF}'
+            | '\u{3040}'..='\u{309F}'
+            | '\u{30A0}'..='\u{30FA}'
+            | '\u{30FC}'..='\u{30FF}'
+            | '\u{3100}'..='\u{312F}'
+            | '\u{31F0}'..='\u{31FF}'
+            | '\u{3200}'..='\u{32FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{20000}'..='\u{2FA1F}'
+    )
+}
+
+/// Whether `chr` is a Latin letter or an ASCII digit.
+fn is_latin(chr: char) -> bool {
+    chr.is_ascii_alphanumeric() || matches!(chr, '\u{C0}'..='\u{24F}') && chr.is_alphabetic()
+}
+
+/// `input` with a space between every CJK char and a Latin letter or
+/// digit right next to it, with `cjk_latin_spacing`. Lines copied as they
+/// are by `skip_line` are left as they are.
+pub(crate) fn spaced<'a>(input: &'a str, options: &TransformOptions) -> Cow<'a, str> {
+    if !options.cjk_latin_spacing {
+        return Cow::Borrowed(input);
+    }
+    let skip_line = options
+        .skip_line
+        .as_ref()
+        .filter(|_| options.preserve_paragraphs);
+
+    let mut text = String::new();
+    let mut copied = 0;
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let body = line.strip_suffix('\n').unwrap_or(line);
+        let body = body.strip_suffix('\r').unwrap_or(body);
+        if skip_line.is_some_and(|filter| filter.matches(body)) {
+            continue;
+        }
+
+        let mut prev = None;
+        for (idx, chr) in line.char_indices() {
+            let boundary = prev
+                .is_some_and(|prev| is_cjk(prev) && is_latin(chr) || is_latin(prev) && is_cjk(chr));
+            if boundary {
+                let idx = start + idx;
+                text.reserve(input.len() - copied + 1);
+                text += &input[copied..idx];
+                text.push(' ');
+                copied = idx;
+            }
+            prev = Some(chr);
+        }
+    }
+
+    if text.is_empty() {
+        return Cow::Borrowed(input);
+    }
+    text += &input[copied..];
+    Cow::Owned(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::spaced;
+    use crate::{
+        line_count_with_options, transform_with_options, verify::verify_invariants, Alignment,
+        LineFilter, TransformOptions, WidthMode,
+    };
+
+    #[test]
+    fn boundaries() {
+        let mut options = TransformOptions::new(12);
+        assert_eq!(spaced("使用Rust编写", &options), "使用Rust编写");
+
+        options.cjk_latin_spacing = true;
+        assert_eq!(spaced("使用Rust编写", &options), "使用 Rust 编写");
+        assert_eq!(spaced("共3个crate", &options), "共 3 个 crate");
+        assert_eq!(spaced("Rustで書く", &options), "Rust で書く");
+
+        // Whitespace and CJK punctuation are left as they are
+        assert_eq!(spaced("使用 Rust\n编写", &options), "使用 Rust\n编写");
+        assert_eq!(spaced("「Rust」，v1。", &options), "「Rust」，v1。");
+        assert_eq!(spaced("Café咖啡", &options), "Café 咖啡");
+        assert_eq!(spaced("鱼x", &options), "鱼 x");
+
+        options.preserve_paragraphs = true;
+        options.skip_line = Some(LineFilter::new(|line| line.starts_with("    ")));
+        assert_eq!(
+            spaced("    用Rust\n用Rust\r\n", &options),
+            "    用Rust\n用 Rust\r\n"
+        );
+    }
+
+    #[test]
+    fn justified() {
+        let input = "我们使用Rust编写了3个crate，并在2024年发布。";
+        let mut options = TransformOptions::new(12);
+        options.cjk_latin_spacing = true;
+        let output = transform_with_options(input, &options);
+        assert_eq!(
+            output,
+            "我们使用    Rust\n编写了      3 个\ncrate，并在    \n2024    年发布。"
+        );
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        assert_eq!(line_count_with_options(input, &options), 4);
+
+        // Display width of CJK chars is 2 half units
+        options.width_mode = WidthMode::HalfWidthUnits;
+        options.line_width = 8;
+        options.align = Alignment::Left;
+        let output = transform_with_options(input, &options);
+        assert_eq!(
+            output,
+            "我们使用 Rust   \n编写了 3 个     \ncrate，并在 2024\n年发布。        "
+        );
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        assert_eq!(line_count_with_options(input, &options), 4);
+    }
+}
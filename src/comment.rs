@@ -0,0 +1,161 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{transform_with_options, Alignment, TransformOptions};
+
+/// Decoration of comment lines reflowed by [`reflow_comment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentStyle {
+    /// `// ` before every line.
+    Line,
+    /// `/// ` before every line.
+    Doc,
+    /// `# ` before every line.
+    Hash,
+    /// `/*` and `*/` on lines of their own, ` * ` before every line
+    /// between them.
+    Block,
+}
+
+impl CommentStyle {
+    /// Marker every line of the comment starts with.
+    fn marker(self) -> &'static str {
+        match self {
+            CommentStyle::Line => "//",
+            CommentStyle::Doc => "///",
+            CommentStyle::Hash => "#",
+            CommentStyle::Block => " *",
+        }
+    }
+}
+
+/// Wraps the text of comment `input` again so lines, decoration
+/// included, are at most `width` chars wide.
+///
+/// Indentation of the first line, the marker of `style` and a single
+/// space after it are stripped from every line, and written before every
+/// output line instead. Text is wrapped like
+/// [`crate::transform_with_options`] wraps it left-aligned, without
+/// trailing spaces. Blank comment lines separate paragraphs and are
+/// written as the marker alone. With [`CommentStyle::Block`], the lines
+/// with `/*` and `*/` are kept, and text after `/*` or before `*/` is
+/// moved to the lines between them. Lines get at least a char of text
+/// whatever `width` is. A line break at the end of `input` is kept.
+pub fn reflow_comment(input: &str, width: u32, style: CommentStyle) -> String {
+    let indent_len = input.len() - input.trim_start_matches([' ', '\t']).len();
+    let indent = &input[..indent_len];
+    let marker = style.marker();
+
+    let mut lines: Vec<&str> = input.lines().collect();
+    let mut opening = None;
+    let mut closing = false;
+    if style == CommentStyle::Block {
+        if let Some(first) = lines.first_mut() {
+            let rest = first.trim_start();
+            if let Some(text) = rest.strip_prefix("/**").or_else(|| rest.strip_prefix("/*")) {
+                opening = Some(&rest[..rest.len() - text.len()]);
+                *first = text;
+            }
+        }
+        if let Some(last) = lines.last_mut() {
+            if let Some(text) = last.trim_end().strip_suffix("*/") {
+                closing = true;
+                *last = text;
+            }
+        }
+    }
+
+    let mut body = String::with_capacity(input.len());
+    for line in lines {
+        let line = line.trim_start();
+        let line = line.strip_prefix(marker.trim_start()).unwrap_or(line);
+        body += line.strip_prefix(' ').unwrap_or(line);
+        body += "\n";
+    }
+
+    let decoration = indent.chars().count() + marker.chars().count() + 1;
+    let text_width = (width as usize).saturating_sub(decoration).max(1);
+    let mut options = TransformOptions::new(u32::try_from(text_width).unwrap_or(u32::MAX));
+    options.align = Alignment::Left;
+    options.pad_last_line = false;
+    options.preserve_paragraphs = true;
+    let wrapped = transform_with_options(&body, &options);
+
+    let mut result = String::with_capacity(wrapped.len() + input.len());
+    if let Some(opening) = opening {
+        result += indent;
+        result += opening;
+        result += "\n";
+    }
+    for line in wrapped.lines() {
+        result += indent;
+        result += marker;
+        let line = line.trim_end();
+        if !line.is_empty() {
+            result += " ";
+            result += line;
+        }
+        result += "\n";
+    }
+    if closing {
+        result += indent;
+        result += " */";
+        result += "\n";
+    }
+    if !input.ends_with('\n') {
+        result.pop();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reflow_comment, CommentStyle};
+
+    #[test]
+    fn doc_comment() {
+        let input = "    /// Wraps the text of comment `input` again so lines, decoration\n    \
+                     /// included, are at most `width` chars wide.\n    ///\n    \
+                     /// Blank comment lines separate paragraphs.\n";
+        let result = reflow_comment(input, 60, CommentStyle::Doc);
+        assert_eq!(
+            result,
+            "    /// Wraps the text of comment `input` again so lines,\n    \
+             /// decoration included, are at most `width` chars wide.\n    ///\n    \
+             /// Blank comment lines separate paragraphs.\n"
+        );
+        assert!(result.lines().all(|line| line.chars().count() <= 60));
+        assert_eq!(reflow_comment(&result, 60, CommentStyle::Doc), result);
+
+        assert_eq!(
+            reflow_comment("// aaa bbb\n//ccc", 10, CommentStyle::Line),
+            "// aaa bbb\n// ccc"
+        );
+        assert_eq!(
+            reflow_comment("# aaa bbb ccc", 9, CommentStyle::Hash),
+            "# aaa bbb\n# ccc"
+        );
+    }
+
+    #[test]
+    fn block_comment() {
+        let input =
+            "/*\n * Съешь ещё этих мягких французских булок, да выпей чаю.\n * Вез корабль \
+                     карамель, наскочил корабль на мель.\n *\n * Тридцатитрёхбуквенный\n */";
+        let result = reflow_comment(input, 60, CommentStyle::Block);
+        assert_eq!(
+            result,
+            "/*\n * Съешь ещё этих мягких французских булок, да выпей чаю.\n * Вез корабль \
+             карамель, наскочил корабль на мель.\n *\n * Тридцатитрёхбуквенный\n */"
+        );
+        assert_eq!(reflow_comment(&result, 60, CommentStyle::Block), result);
+
+        assert_eq!(
+            reflow_comment(
+                "\t/** Съешь ещё\n\t * этих мягких */\n",
+                20,
+                CommentStyle::Block
+            ),
+            "\t/**\n\t * Съешь ещё этих\n\t * мягких\n\t */\n"
+        );
+    }
+}
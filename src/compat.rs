@@ -0,0 +1,142 @@
+//! Functions named and behaving like those of `textwrap`, enabled by the
+//! `compat` feature, to move code over from it.
+//!
+//! Lines are wrapped by this crate, left-aligned and without padding, so
+//! the output differs from `textwrap` where the engines differ:
+//!
+//! - whitespace between words is collapsed to a single space, and
+//!   whitespace before the first word of a line is dropped, while
+//!   `textwrap` keeps it;
+//! - words are split at any whitespace, tabs and no-break spaces included,
+//!   and not after hyphens;
+//! - words wider than a line are always broken, `break_words` is ignored;
+//! - lines are filled one by one, like `textwrap::WrapAlgorithm::FirstFit`
+//!   does, while `textwrap` balances them by default.
+
+use std::borrow::Cow;
+
+use crate::{transform_cow, Alignment, TransformOptions};
+
+/// Wraps every line of `text` at `width` chars, like `textwrap::fill`.
+pub fn fill(text: &str, width: usize) -> String {
+    wrap(text, width).join("\n")
+}
+
+/// Wraps every line of `text` at `width` chars, like `textwrap::wrap`.
+/// Lines that are left as they are borrow `text`.
+///
+/// Line breaks of `text` are kept, a blank line gives an empty line.
+pub fn wrap(text: &str, width: usize) -> Vec<Cow<'_, str>> {
+    let mut options = TransformOptions::new(u32::try_from(width.max(1)).unwrap_or(u32::MAX));
+    options.align = Alignment::Left;
+    options.pad_last_line = false;
+
+    let mut lines = Vec::new();
+    for line in text.split('\n') {
+        match transform_cow(line, &options) {
+            Cow::Borrowed(line) => lines.push(Cow::Borrowed(line)),
+            Cow::Owned(wrapped) if wrapped.is_empty() => lines.push(Cow::Borrowed("")),
+            Cow::Owned(wrapped) => lines.extend(
+                wrapped
+                    .lines()
+                    .map(|line| Cow::Owned(String::from(line.trim_end()))),
+            ),
+        }
+    }
+    lines
+}
+
+/// Takes `width` and the width of the indents, which are not written, so
+/// lines hold the same words as they do with `textwrap`: the first line
+/// of a paragraph is `width` without `initial_indent` wide, the other ones
+/// `width` without `subsequent_indent`. Lines are left-aligned, and the
+/// last one is not padded. Other options are ignored.
+impl From<textwrap::Options<'_>> for TransformOptions {
+    fn from(options: textwrap::Options<'_>) -> Self {
+        let text_width = |indent: &str| {
+            let width = options.width.saturating_sub(indent.chars().count()).max(1);
+            u32::try_from(width).unwrap_or(u32::MAX)
+        };
+
+        let mut result = TransformOptions::new(text_width(options.subsequent_indent));
+        let initial_width = text_width(options.initial_indent);
+        result.initial_width = Some(initial_width).filter(|&width| width != result.line_width);
+        result.align = Alignment::Left;
+        result.pad_last_line = false;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use textwrap::WrapAlgorithm;
+
+    use super::{fill, wrap};
+    use crate::{transform_with_options, TransformOptions};
+
+    const CORPUS: [&str; 6] = [
+        "Съешь ещё этих мягких французских булок, да выпей чаю",
+        "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor",
+        "Тридцатитрёхбуквенный и consectetur_adipiscing_elit",
+        "first line\nsecond line of text\n\nafter a blank line",
+        "",
+        "end with space ",
+    ];
+
+    fn first_fit(width: usize) -> textwrap::Options<'static> {
+        textwrap::Options::new(width).wrap_algorithm(WrapAlgorithm::FirstFit)
+    }
+
+    #[test]
+    fn same_as_textwrap() {
+        for text in CORPUS {
+            for width in [1, 4, 8, 12, 20, 80] {
+                assert_eq!(wrap(text, width), textwrap::wrap(text, first_fit(width)));
+                assert_eq!(fill(text, width), textwrap::fill(text, first_fit(width)));
+            }
+        }
+    }
+
+    #[test]
+    fn documented_differences() {
+        assert_eq!(wrap("foo  bar\tbaz qux", 9), ["foo bar", "baz qux"]);
+        assert_eq!(
+            textwrap::wrap("foo  bar\tbaz qux", first_fit(9)),
+            ["foo  bar\t", "baz qux"]
+        );
+
+        assert_eq!(wrap("  indented text", 10), ["indented", "text"]);
+        assert_eq!(
+            textwrap::wrap("  indented text", first_fit(10)),
+            ["  indented", "text"]
+        );
+
+        assert_eq!(wrap("well-known fact", 7), ["well-kn", "own", "fact"]);
+        assert_eq!(
+            textwrap::wrap("well-known fact", first_fit(7)),
+            ["well-", "known", "fact"]
+        );
+    }
+
+    #[test]
+    fn options() {
+        let text = CORPUS[0];
+        let options = first_fit(14).initial_indent("* ").subsequent_indent("  ");
+        let wrapped = transform_with_options(text, &TransformOptions::from(options.clone()));
+        let expected = textwrap::wrap(text, options);
+        let expected: Vec<&str> = expected.iter().map(|line| &line[2..]).collect();
+        // Lines but the last one are padded
+        let lines: Vec<&str> = wrapped.lines().map(str::trim_end).collect();
+        assert_eq!(lines, expected);
+        assert!(wrapped
+            .lines()
+            .rev()
+            .skip(1)
+            .all(|line| line.chars().count() == 12));
+
+        let options = TransformOptions::from(textwrap::Options::new(10).initial_indent("> "));
+        assert_eq!((options.line_width, options.initial_width), (10, Some(8)));
+        let options = TransformOptions::from(textwrap::Options::new(10));
+        assert_eq!((options.line_width, options.initial_width), (10, None));
+    }
+}
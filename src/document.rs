@@ -0,0 +1,224 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use crate::{
+    adjust, decorate, tokens, transform_with_options, width::in_units, Paragraphs,
+    TransformOptions, SPACE_STR,
+};
+
+/// Text that is adjusted again after every change, e.g. in an editor.
+///
+/// Wrapped paragraphs are kept between updates, keyed by their words, so
+/// only paragraphs whose words changed are wrapped again, wherever they
+/// moved. Output is identical to [`crate::transform_with_options`].
+///
+/// Paragraphs are only independent when neither `options.max_lines`,
+/// `options.skip_line` nor `options.number_lines` are set. Otherwise the
+/// whole text is adjusted on every update.
+pub struct Document {
+    options: TransformOptions,
+    /// Wrapped paragraphs by their words, joined by single spaces
+    cache: BTreeMap<String, String>,
+    output: String,
+    /// Paragraphs wrapped by the last update
+    wrapped: usize,
+}
+
+impl Document {
+    pub fn new(line_width: u32) -> Self {
+        Document::with_options(TransformOptions::new(line_width))
+    }
+
+    pub fn with_options(options: TransformOptions) -> Self {
+        Document {
+            options,
+            cache: BTreeMap::new(),
+            output: String::new(),
+            wrapped: 0,
+        }
+    }
+
+    /// Adjusts `new_input` and returns the output. Paragraphs are wrapped
+    /// only when the last update had no paragraph with the same words.
+    ///
+    /// Panics when `line_width` is 0 and `new_input` is not blank.
+    pub fn update(&mut self, new_input: &str) -> &str {
+        let options = &self.options;
+        let dependent = options.max_lines.is_some()
+            || options.skip_line.is_some()
+            || options.number_lines.is_some();
+        if dependent {
+            self.cache.clear();
+            self.wrapped = 1;
+            self.output = transform_with_options(new_input, options);
+            return &self.output;
+        }
+
+        self.wrapped = 0;
+        let options = &*in_units(options);
+        let line_width = options.line_width as usize;
+        let mut cache = BTreeMap::new();
+        let mut keys = Vec::new();
+        for paragraph in Paragraphs::new(new_input, options.preserve_paragraphs) {
+            let key = tokens(paragraph).collect::<Vec<_>>().join(SPACE_STR);
+            if !cache.contains_key(&key) {
+                let wrapped = match self.cache.remove(&key) {
+                    Some(wrapped) => wrapped,
+                    None => {
+                        self.wrapped += 1;
+                        adjust(paragraph, options, line_width, None)
+                            .expect("Output size is not limited")
+                    }
+                };
+                cache.insert(key.clone(), wrapped);
+            }
+            keys.push(key);
+        }
+        self.cache = cache;
+
+        let body = match keys.is_empty() {
+            // Blank input may still give a blank line
+            true => adjust(new_input, options, line_width, None),
+            false => {
+                // Paragraphs of break hints alone come out empty
+                let paragraphs: Vec<&str> = keys
+                    .iter()
+                    .map(|key| self.cache[key].as_str())
+                    .filter(|paragraph| !paragraph.is_empty())
+                    .collect();
+                Ok(paragraphs.join("\n\n"))
+            }
+        };
+        self.output = body
+            .and_then(|body| decorate(body, options, None))
+            .expect("Output size is not limited");
+        &self.output
+    }
+
+    /// Output of the last update.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Number of paragraphs wrapped by the last update. Text adjusted as a
+    /// whole counts as a single paragraph.
+    pub fn wrapped(&self) -> usize {
+        self.wrapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Document;
+    use crate::{transform_with_options, Alignment, NumberStyle, TransformOptions};
+
+    fn paragraphs(n: usize) -> Vec<String> {
+        let words = [
+            "Съешь",
+            "ещё",
+            "этих",
+            "мягких",
+            "французских",
+            "булок,",
+            "да",
+            "выпей",
+            "чаю",
+        ];
+        (0..n)
+            .map(|idx| {
+                let len = idx % 17 + 1;
+                let paragraph: Vec<&str> = (0..len)
+                    .map(|word| words[(idx + word) % words.len()])
+                    .collect();
+                format!("{} {idx}", paragraph.join(" "))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn only_changed_paragraphs_are_wrapped() {
+        let mut options = TransformOptions::new(24);
+        options.preserve_paragraphs = true;
+        options.align = Alignment::Center;
+        options.margin_left = 2;
+        let mut document = Document::with_options(options.clone());
+
+        let mut paragraphs = paragraphs(5_000);
+        let input = paragraphs.join("\n\n");
+        assert_eq!(
+            document.update(&input),
+            transform_with_options(&input, &options)
+        );
+        assert_eq!(document.wrapped(), 5_000);
+
+        // Same words with other whitespace
+        paragraphs[2_500] = paragraphs[2_500].replace(' ', "\n");
+        let input = paragraphs.join("\n \n");
+        assert_eq!(
+            document.update(&input),
+            transform_with_options(&input, &options)
+        );
+        assert_eq!(document.wrapped(), 0);
+
+        paragraphs[2_500] += " и ещё";
+        let input = paragraphs.join("\n\n");
+        assert_eq!(
+            document.update(&input),
+            transform_with_options(&input, &options)
+        );
+        assert_eq!(document.wrapped(), 1);
+
+        paragraphs.insert(10, String::from("Новый абзац"));
+        paragraphs.remove(4_000);
+        paragraphs.swap(0, 1);
+        let input = paragraphs.join("\n\n");
+        assert_eq!(
+            document.update(&input),
+            transform_with_options(&input, &options)
+        );
+        assert_eq!(document.wrapped(), 1);
+
+        assert_eq!(document.update(""), "");
+        assert_eq!(document.wrapped(), 0);
+        let output = document.update(&input).to_string();
+        assert_eq!(document.output(), output);
+        assert_eq!(document.wrapped(), 5_000);
+    }
+
+    #[test]
+    fn paragraphs_of_hints() {
+        let mut options = TransformOptions::new(10);
+        options.preserve_paragraphs = true;
+        options.break_hint = Some(String::from("%%"));
+        let mut document = Document::with_options(options.clone());
+        for input in ["a b\n\n%%\n\nc d", "%%\n\na b", "%% %%"] {
+            assert_eq!(
+                document.update(input),
+                transform_with_options(input, &options)
+            );
+        }
+        assert_eq!(document.output(), "");
+    }
+
+    #[test]
+    fn dependent_paragraphs() {
+        let input = paragraphs(50).join("\n\n");
+        let mut options = TransformOptions::new(20);
+        options.preserve_paragraphs = true;
+        options.number_lines = Some(NumberStyle::default());
+        let mut document = Document::with_options(options.clone());
+        assert_eq!(
+            document.update(&input),
+            transform_with_options(&input, &options)
+        );
+
+        let mut options = TransformOptions::new(20);
+        options.blank_line_for_empty = true;
+        let mut document = Document::with_options(options.clone());
+        assert_eq!(
+            document.update(&input),
+            transform_with_options(&input, &options)
+        );
+        assert_eq!(document.update(" \n "), " ".repeat(20));
+        assert_eq!(document.wrapped(), 1);
+    }
+}
@@ -0,0 +1,39 @@
+use core::fmt;
+
+/// Reasons for [`crate::try_transform`] to fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransformError {
+    /// `line_width` is 0, so not even a single char fits into a line.
+    ZeroWidth,
+    /// `line_width` exceeds [`crate::TransformOptions::max_width`].
+    WidthTooLarge { width: u32, max_width: u32 },
+    /// [`crate::TransformOptions::field_width`] is narrower than the
+    /// widest line, `line_width` or `initial_width`.
+    FieldTooNarrow { field_width: u32, line_width: u32 },
+    /// Output grew beyond [`crate::TransformOptions::max_output_bytes`].
+    /// `produced` is the output length when adjusting was stopped.
+    OutputTooLarge { produced: usize, limit: usize },
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransformError::ZeroWidth => write!(f, "line width must be greater than 0"),
+            TransformError::WidthTooLarge { width, max_width } => {
+                write!(f, "line width {width} exceeds the maximum of {max_width}")
+            }
+            TransformError::FieldTooNarrow {
+                field_width,
+                line_width,
+            } => {
+                write!(f, "field width {field_width} is less than line width {line_width}")
+            }
+            TransformError::OutputTooLarge { produced, limit } => {
+                write!(f, "output of {produced} bytes exceeds the limit of {limit}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TransformError {}
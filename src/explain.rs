@@ -0,0 +1,246 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{width::in_units, LinePart, Lines, TransformOptions};
+
+/// Records why every line of wrapping `input` at `line_width` with
+/// `options` holds the words it does, taken from the same planning pass
+/// [`crate::transform_with_options`] makes.
+///
+/// Lines are planned like [`crate::line_badness`] plans them, so
+/// `french_spacing`, `atomic_delimiters`, `cjk_latin_spacing`,
+/// `number_lines`, `max_lines` and whatever is added around the lines are
+/// left out. Widths are in chars, or in half units with
+/// [`crate::WidthMode::HalfWidthUnits`].
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn explain<'a>(
+    input: &'a str,
+    line_width: u32,
+    options: &TransformOptions,
+) -> Vec<LineDecision<'a>> {
+    let mut options = options.clone();
+    options.line_width = line_width;
+    let options = &*in_units(&options);
+    // Lines borrow options as long as the input
+    let borrowed = |text: &str| {
+        let offset = text.as_ptr() as usize - input.as_ptr() as usize;
+        &input[offset..offset + text.len()]
+    };
+
+    let mut decisions: Vec<LineDecision<'a>> = Vec::new();
+    let mut lines = Lines::new(input, options, options.line_width as usize);
+    while let Some(plan) = lines.next_plan() {
+        if let Some(last) = decisions.last_mut() {
+            if last.reason == BreakReason::EndOfInput {
+                last.reason = BreakReason::HardBreak;
+            }
+        }
+
+        let mut decision = LineDecision {
+            line_width: plan.line_width,
+            words: Vec::with_capacity(plan.n_words),
+            widths: Vec::with_capacity(plan.n_words),
+            free_space: plan.line_width,
+            gaps: Vec::with_capacity(plan.n_words.saturating_sub(1)),
+            reason: BreakReason::EndOfInput,
+        };
+        let mut gap = None;
+        plan.for_each_part(|part| match part {
+            LinePart::Word(word) if !word.is_empty() => {
+                decision
+                    .gaps
+                    .extend(gap.take().filter(|_| !decision.words.is_empty()));
+                let width = lines.word(word).width;
+                decision.free_space = decision.free_space.saturating_sub(width);
+                decision.words.push(borrowed(word));
+                decision.widths.push(width);
+            }
+            LinePart::Word(_) => {}
+            LinePart::Spaces(count) => gap = Some(count),
+        });
+
+        decision.reason = if !lines.unfitted.text.is_empty() {
+            BreakReason::ForcedSplit
+        } else if let Some(&next) = lines.tokens.peek() {
+            let n_gaps = decision.words.len().saturating_sub(1);
+            let available = decision.free_space.saturating_sub(n_gaps);
+            let needed = next.width + usize::from(!decision.words.is_empty());
+            match needed > available {
+                false if decision.words.is_empty() => BreakReason::HardBreak,
+                true => BreakReason::NextWordTooWide {
+                    word: borrowed(next.text),
+                    needed,
+                    available,
+                },
+                false => BreakReason::AvoidsOrphan {
+                    word: decision.words.last().copied().unwrap_or_default(),
+                },
+            }
+        } else {
+            BreakReason::EndOfInput
+        };
+        decisions.push(decision);
+    }
+    decisions
+}
+
+/// Layout of an output line and why it ends, returned by [`explain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineDecision<'a> {
+    /// Width the line is laid out for.
+    pub line_width: usize,
+    /// Words of the line, or parts of split words, borrowed from the input.
+    pub words: Vec<&'a str>,
+    /// Width of every word.
+    pub widths: Vec<usize>,
+    /// Width of the line left after the words, taken by gaps and padding.
+    pub free_space: usize,
+    /// Width of every gap between adjacent words.
+    pub gaps: Vec<usize>,
+    /// Why the next word starts the next line.
+    pub reason: BreakReason<'a>,
+}
+
+/// Why a line ends, see [`LineDecision`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakReason<'a> {
+    /// Next `word`, together with a space before it when the line has
+    /// words, needs `needed` of the line, but `available` is left.
+    NextWordTooWide {
+        word: &'a str,
+        needed: usize,
+        available: usize,
+    },
+    /// Line ends with a part of a word wider than a line, which goes on
+    /// in the next line.
+    ForcedSplit,
+    /// Next word would fit, but `orphan_words` or `avoid_orphan_last_line`
+    /// moves it down to keep it with the words after it, and `word` the
+    /// line ends with now.
+    AvoidsOrphan { word: &'a str },
+    /// Line is the last one.
+    EndOfInput,
+    /// Paragraph, or a line copied as it is, ends with the line, or the
+    /// line is an empty one between paragraphs.
+    HardBreak,
+}
+
+impl fmt::Display for LineDecision<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} of widths {:?} in {}, {} free, gaps {:?}: {}",
+            self.words, self.widths, self.line_width, self.free_space, self.gaps, self.reason
+        )
+    }
+}
+
+impl fmt::Display for BreakReason<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BreakReason::NextWordTooWide {
+                word,
+                needed,
+                available,
+            } => write!(f, "next word '{word}' needs {needed}, {available} left"),
+            BreakReason::ForcedSplit => write!(f, "word is split"),
+            BreakReason::AvoidsOrphan { word } => {
+                write!(f, "next word is moved down to avoid ending with '{word}'")
+            }
+            BreakReason::EndOfInput => write!(f, "end of input"),
+            BreakReason::HardBreak => write!(f, "end of paragraph"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{explain, BreakReason, LineDecision};
+    use crate::{transform_with_options, TransformOptions};
+
+    #[test]
+    fn oversized_word() {
+        let input = "Съешь ещё Тридцатитрёхбуквенный чаю";
+        let options = TransformOptions::new(12);
+        // "Съешь    ещё\nТридцатитрёх\nбуквенный   \nчаю         "
+        let decisions = explain(input, 12, &options);
+        let reasons: Vec<BreakReason> = decisions.iter().map(|line| line.reason).collect();
+        assert_eq!(
+            reasons,
+            [
+                BreakReason::NextWordTooWide {
+                    word: "Тридцатитрёхбуквенный",
+                    needed: 22,
+                    available: 3,
+                },
+                BreakReason::ForcedSplit,
+                BreakReason::NextWordTooWide {
+                    word: "чаю",
+                    needed: 4,
+                    available: 3,
+                },
+                BreakReason::EndOfInput,
+            ]
+        );
+        assert_eq!(
+            decisions[0],
+            LineDecision {
+                line_width: 12,
+                words: vec!["Съешь", "ещё"],
+                widths: vec![5, 3],
+                free_space: 4,
+                gaps: vec![4],
+                reason: reasons[0],
+            }
+        );
+        assert_eq!(decisions[1].words, ["Тридцатитрёх"]);
+        assert_eq!(decisions[2].gaps, [] as [usize; 0]);
+
+        let result = transform_with_options(input, &options);
+        let words: Vec<&str> = decisions
+            .iter()
+            .flat_map(|line| line.words.clone())
+            .collect();
+        assert_eq!(words, result.split_whitespace().collect::<Vec<_>>());
+
+        assert_eq!(
+            decisions[0].to_string(),
+            "[\"Съешь\", \"ещё\"] of widths [5, 3] in 12, 4 free, gaps [4]: \
+             next word 'Тридцатитрёхбуквенный' needs 22, 3 left"
+        );
+    }
+
+    #[test]
+    fn paragraphs_and_orphans() {
+        let mut options = TransformOptions::new(12);
+        options.preserve_paragraphs = true;
+        options.orphan_words = Some(vec![String::from("и")]);
+        let decisions = explain("Съешь ещё и чаю\n\nчаю", 12, &options);
+        let reasons: Vec<BreakReason> = decisions.iter().map(|line| line.reason).collect();
+        assert_eq!(
+            reasons,
+            [
+                BreakReason::AvoidsOrphan { word: "ещё" },
+                BreakReason::HardBreak,
+                BreakReason::HardBreak,
+                BreakReason::EndOfInput,
+            ]
+        );
+        assert_eq!(decisions[1].words, ["и", "чаю"]);
+        assert!(decisions[2].words.is_empty());
+        assert_eq!(decisions[2].free_space, 12);
+
+        // Word that only fits lines after the shorter first one
+        options.initial_width = Some(2);
+        let decisions = explain("чаю", 12, &options);
+        let expected = BreakReason::NextWordTooWide {
+            word: "чаю",
+            needed: 3,
+            available: 2,
+        };
+        assert_eq!(decisions[0].reason, expected);
+        assert_eq!(decisions[1].words, ["чаю"]);
+        assert!(explain(" ", 12, &options).is_empty());
+    }
+}
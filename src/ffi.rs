@@ -0,0 +1,151 @@
+//! C bindings, enabled by the `ffi` feature. See `include/line_adjustment.h`.
+//! The crate is built as a library for C with e.g.
+//! `cargo rustc --release --lib --features ffi --crate-type cdylib`.
+//!
+//! Errors are reported through return values and [`line_adjust_last_error`],
+//! panics never unwind into the caller.
+
+use std::cell::Cell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic;
+
+use crate::{try_transform, TransformError};
+
+pub const LINE_ADJUST_OK: c_int = 0;
+pub const LINE_ADJUST_NULL_ARGUMENT: c_int = 1;
+pub const LINE_ADJUST_INVALID_UTF8: c_int = 2;
+pub const LINE_ADJUST_ZERO_WIDTH: c_int = 3;
+pub const LINE_ADJUST_INTERNAL_ERROR: c_int = 4;
+pub const LINE_ADJUST_WIDTH_TOO_LARGE: c_int = 5;
+
+thread_local! {
+    static LAST_ERROR: Cell<c_int> = const { Cell::new(LINE_ADJUST_OK) };
+}
+
+/// Adjusts the NUL-terminated UTF-8 string `input` to `width`.
+///
+/// Returns a NUL-terminated UTF-8 string, which must be released with
+/// [`line_adjust_free`]. Its length in bytes, without the terminator, is
+/// written to `out_len` unless it is NULL.
+///
+/// Returns NULL on failure, [`line_adjust_last_error`] tells the reason.
+///
+/// # Safety
+///
+/// `input` must be NULL or point to a NUL-terminated string. `out_len`
+/// must be NULL or point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn line_adjust_transform(
+    input: *const c_char,
+    width: u32,
+    out_len: *mut usize,
+) -> *mut c_char {
+    if input.is_null() {
+        return fail(LINE_ADJUST_NULL_ARGUMENT);
+    }
+
+    let Ok(input) = CStr::from_ptr(input).to_str() else {
+        return fail(LINE_ADJUST_INVALID_UTF8);
+    };
+
+    let result = match panic::catch_unwind(|| try_transform(input, width)) {
+        Ok(Ok(result)) => result,
+        Ok(Err(TransformError::ZeroWidth)) => return fail(LINE_ADJUST_ZERO_WIDTH),
+        Ok(Err(TransformError::WidthTooLarge { .. })) => return fail(LINE_ADJUST_WIDTH_TOO_LARGE),
+        // Output size and field are not limited, so neither are too large
+        Ok(Err(TransformError::OutputTooLarge { .. } | TransformError::FieldTooNarrow { .. }))
+        | Err(_) => {
+            return fail(LINE_ADJUST_INTERNAL_ERROR)
+        }
+    };
+
+    // Output consists of input chars only, so it has no interior NULs
+    let Ok(result) = CString::new(result) else {
+        return fail(LINE_ADJUST_INTERNAL_ERROR);
+    };
+
+    if !out_len.is_null() {
+        *out_len = result.as_bytes().len();
+    }
+    LAST_ERROR.with(|last| last.set(LINE_ADJUST_OK));
+    result.into_raw()
+}
+
+/// Releases a string returned by [`line_adjust_transform`]. NULL is ignored.
+///
+/// # Safety
+///
+/// `ptr` must be NULL or a pointer returned by [`line_adjust_transform`]
+/// that is not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn line_adjust_free(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(CString::from_raw(ptr));
+    }
+}
+
+/// Error code of the last [`line_adjust_transform`] call on this thread.
+#[no_mangle]
+pub extern "C" fn line_adjust_last_error() -> c_int {
+    LAST_ERROR.with(|last| last.get())
+}
+
+fn fail(code: c_int) -> *mut c_char {
+    LAST_ERROR.with(|last| last.set(code));
+    std::ptr::null_mut()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{CStr, CString};
+
+    use super::*;
+
+    fn round_trip(input: &[u8], width: u32) -> Result<(String, usize), c_int> {
+        let input = CString::new(input).unwrap();
+        let mut len = 0;
+
+        unsafe {
+            let ptr = line_adjust_transform(input.as_ptr(), width, &mut len);
+            if ptr.is_null() {
+                return Err(line_adjust_last_error());
+            }
+            let result = CStr::from_ptr(ptr).to_str().unwrap().to_string();
+            line_adjust_free(ptr);
+            Ok((result, len))
+        }
+    }
+
+    #[test]
+    fn transform_round_trip() {
+        let expected = "Съешь    ещё\nэтих  мягких\nфранцузских \nбулок       ";
+        let input = "Съешь ещё этих мягких французских булок";
+
+        assert_eq!(
+            round_trip(input.as_bytes(), 12),
+            Ok((expected.to_string(), expected.len()))
+        );
+        assert_eq!(line_adjust_last_error(), LINE_ADJUST_OK);
+        assert_eq!(round_trip(b"", 12), Ok((String::new(), 0)));
+    }
+
+    #[test]
+    fn transform_errors() {
+        assert_eq!(
+            round_trip(b"invalid \xFF", 12),
+            Err(LINE_ADJUST_INVALID_UTF8)
+        );
+        assert_eq!(round_trip(b"text", 0), Err(LINE_ADJUST_ZERO_WIDTH));
+        assert_eq!(
+            round_trip(b"text", u32::MAX),
+            Err(LINE_ADJUST_WIDTH_TOO_LARGE)
+        );
+
+        unsafe {
+            let ptr = line_adjust_transform(std::ptr::null(), 12, std::ptr::null_mut());
+            assert!(ptr.is_null());
+            assert_eq!(line_adjust_last_error(), LINE_ADJUST_NULL_ARGUMENT);
+            line_adjust_free(ptr);
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! Spaces of French typography and of atomic phrases, which neither
+//! break nor stretch.
+
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+
+use crate::{hint, TransformOptions};
+
+/// Marks that take a space before them.
+const CLOSING_MARKS: [char; 5] = [';', ':', '!', '?', '»'];
+
+/// Text with the spaces before [`CLOSING_MARKS`] and after `«` replaced
+/// by `glue`, and spaces within phrases of `atomic_delimiters` replaced by
+/// `phrase_glue`. Glue chars are not whitespace and not in the original
+/// text. Words joined by glue are wrapped as a single word.
+pub(crate) struct Glued {
+    pub(crate) text: String,
+    glue: char,
+    phrase_glue: char,
+}
+
+impl Glued {
+    /// Returns `None` when `input` has no such spaces. Whitespace that
+    /// ends a paragraph is left as it is. Phrases wider than `line_width`
+    /// are not glued.
+    pub(crate) fn new(input: &str, options: &TransformOptions, line_width: usize) -> Option<Self> {
+        // Glue is measured like any other char, so it is not a part of
+        // markers or break hints either
+        let markup = options.markup.as_ref();
+        let in_markers = |chr: char| {
+            markup.is_some_and(|markup| {
+                markup.open.contains(chr)
+                    || markup.close.contains(chr)
+                    || markup.hyperlinks && chr == '\u{7}'
+            })
+        };
+        let in_hint = |chr: char| hint::break_hint(options).is_some_and(|hint| hint.contains(chr));
+        let is_free = |chr: &char| !input.contains(*chr) && !in_markers(*chr) && !in_hint(*chr);
+        let mut candidates = ('\u{1}'..='\u{8}').chain('\u{E000}'..='\u{F8FF}');
+        let glue = candidates.find(is_free)?;
+        let phrase_glue = candidates.find(is_free)?;
+        let phrases = phrases(input, &options.atomic_delimiters, line_width);
+
+        let mut text = String::new();
+        let mut copied = 0;
+        let mut prev = None;
+        let mut chars = input.char_indices().peekable();
+        while let Some((start, chr)) = chars.next() {
+            if !chr.is_whitespace() {
+                prev = Some(chr);
+                continue;
+            }
+
+            let mut newlines = usize::from(chr == '\n');
+            let mut end = start + chr.len_utf8();
+            while let Some(&(idx, chr)) = chars.peek().filter(|(_, chr)| chr.is_whitespace()) {
+                newlines += usize::from(chr == '\n');
+                end = idx + chr.len_utf8();
+                chars.next();
+            }
+
+            let Some((prev, &(_, next))) = prev.zip(chars.peek()) else {
+                continue;
+            };
+            let french = options.french_spacing && (prev == '«' || CLOSING_MARKS.contains(&next));
+            let in_phrase = || {
+                phrases
+                    .iter()
+                    .any(|phrase| phrase.start < start && end < phrase.end)
+            };
+            let chr = match french {
+                _ if newlines > 1 => continue,
+                true => glue,
+                false if in_phrase() => phrase_glue,
+                false => continue,
+            };
+            text.reserve(input.len() - copied);
+            text += &input[copied..start];
+            text.push(chr);
+            copied = end;
+        }
+
+        if text.is_empty() {
+            return None;
+        }
+        text += &input[copied..];
+        Some(Glued {
+            text,
+            glue,
+            phrase_glue,
+        })
+    }
+
+    /// Writes glue of `output` as single spaces, or French glue as narrow
+    /// no-break spaces when `narrow` is set.
+    pub(crate) fn restore(&self, output: &str, narrow: bool) -> String {
+        let space = match narrow {
+            true => '\u{202F}',
+            false => ' ',
+        };
+        output
+            .chars()
+            .map(|chr| match chr {
+                chr if chr == self.glue => space,
+                chr if chr == self.phrase_glue => ' ',
+                chr => chr,
+            })
+            .collect()
+    }
+}
+
+/// Byte ranges of phrases of `input` between `delimiters` that fit
+/// `line_width` together with the words they touch. Phrases do not nest,
+/// a phrase ends at the first closing delimiter after its start.
+fn phrases(input: &str, delimiters: &[(char, char)], line_width: usize) -> Vec<Range<usize>> {
+    let mut phrases = Vec::new();
+    let mut idx = 0;
+    while let Some(chr) = input[idx..].chars().next() {
+        let start = idx;
+        idx += chr.len_utf8();
+        let Some(&(_, close)) = delimiters.iter().find(|(open, _)| *open == chr) else {
+            continue;
+        };
+        let Some(len) = input[idx..].find(close) else {
+            continue;
+        };
+        idx += len + close.len_utf8();
+
+        // Phrase is wrapped with the words it is attached to
+        let unit_start = input[..start].trim_end_matches(|chr: char| !chr.is_whitespace());
+        let unit_end = input[idx..]
+            .find(char::is_whitespace)
+            .map_or(input.len(), |len| idx + len);
+        let unit = &input[unit_start.len()..unit_end];
+        let width = unit
+            .split_whitespace()
+            .map(|word| word.chars().count() + 1)
+            .sum::<usize>();
+        let paragraph_break = unit
+            .split(|chr: char| !chr.is_whitespace())
+            .any(|spaces| spaces.matches('\n').count() > 1);
+        if width - 1 <= line_width && !paragraph_break {
+            phrases.push(start..idx);
+        } else {
+            // Openers inside a rejected phrase may start phrases of their own
+            idx = start + chr.len_utf8();
+        }
+    }
+    phrases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Glued;
+    use crate::{
+        line_count_with_options, transform_with_options, verify::verify_invariants, Alignment,
+        Document, MarkupConfig, TransformOptions,
+    };
+
+    #[test]
+    fn marks_stay_on_the_line() {
+        let input = "Il a dit « citation » ici. Vraiment ?";
+        let mut options = TransformOptions::new(16);
+        options.french_spacing = true;
+        assert_eq!(
+            transform_with_options(input, &options),
+            "Il     a     dit\n« citation »    \nici.  Vraiment ?"
+        );
+
+        options.line_width = 12;
+        options.align = Alignment::Left;
+        options.french_narrow_spaces = true;
+        assert_eq!(
+            transform_with_options(input, &options),
+            "Il a dit    \n«\u{202F}citation\u{202F}»\nici.        \nVraiment\u{202F}?  "
+        );
+
+        // Only the single space before a mark is kept
+        let mut options = TransformOptions::new(14);
+        options.french_spacing = true;
+        let input = "Oui ! Vraiment ?\tNon :  si";
+        let output = transform_with_options(input, &options);
+        assert_eq!(output, "Oui !         \nVraiment ?    \nNon :       si");
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        assert_eq!(line_count_with_options(input, &options), 3);
+
+        options.french_spacing = false;
+        assert_eq!(
+            transform_with_options("Oui ! Vraiment ?", &options),
+            "Oui ! Vraiment\n?             "
+        );
+    }
+
+    #[test]
+    fn paragraphs_are_not_glued() {
+        let mut options = TransformOptions::new(12);
+        options.french_spacing = true;
+        assert!(Glued::new("Oui\n\n!", &options, 12).is_none());
+        assert!(Glued::new(" ? Oui", &options, 12).is_none());
+        assert!(Glued::new("Oui «\n", &options, 12).is_none());
+
+        let glued = Glued::new("a  ?\n! \u{1}«\tb", &options, 12).unwrap();
+        assert_eq!(glued.text, "a\u{2}?\u{2}! \u{1}«\u{2}b");
+        assert_eq!(
+            glued.restore(&glued.text, true),
+            "a\u{202F}?\u{202F}! \u{1}«\u{202F}b"
+        );
+    }
+
+    #[test]
+    fn glue_with_markup() {
+        let mut options = TransformOptions::new(3);
+        options.french_spacing = true;
+        options.markup = Some(MarkupConfig {
+            open: String::from("\u{1}"),
+            close: String::from("\u{2}"),
+            reopen_on_wrap: false,
+            hyperlinks: false,
+        });
+        options.break_hint = Some(String::from("\u{3}"));
+        let glued = Glued::new("b !", &options, 3).unwrap();
+        assert_eq!(glued.text, "b\u{4}!");
+
+        let input = "b !";
+        for (line_width, expected) in [(3, "b !"), (1, "b\n \n!")] {
+            options.line_width = line_width;
+            let output = transform_with_options(input, &options);
+            assert_eq!(output, expected);
+            assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        }
+    }
+
+    #[test]
+    fn atomic_phrases() {
+        let input = "He said \"see you soon\" and left.";
+        let mut options = TransformOptions::new(20);
+        assert_eq!(
+            transform_with_options(input, &options),
+            "He   said   \"see you\nsoon\"      and left."
+        );
+
+        // Phrase moves to the next line as a whole
+        options.atomic_delimiters = vec![('"', '"'), ('(', ')'), ('`', '`')];
+        let output = transform_with_options(input, &options);
+        assert_eq!(
+            output,
+            "He              said\n\"see you soon\"   and\nleft.               "
+        );
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+
+        // Words attached to a phrase go with it, the first closer wins
+        assert_eq!(
+            transform_with_options("Run (`cargo test --all`) now.", &options),
+            "Run                 \n(`cargo test --all`)\nnow.                "
+        );
+        assert_eq!(
+            transform_with_options("(un (deux) trois) quatre", &options),
+            "(un (deux)    trois)\nquatre              "
+        );
+
+        // Phrases wider than a line are broken as usual
+        let input = "A \"phrase much too wide for a line\" x";
+        assert_eq!(
+            transform_with_options(input, &options),
+            "A \"phrase much   too\nwide for a line\"   x"
+        );
+
+        // Phrase after an opener left unmatched in an earlier paragraph
+        let mut options = TransformOptions::new(8);
+        options.atomic_delimiters = vec![('«', '»')];
+        options.preserve_paragraphs = true;
+        let input = "x «\n\nab « c d »";
+        let output = transform_with_options(input, &options);
+        assert_eq!(output, "x      «\n\nab      \n« c d » ");
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        let mut document = Document::with_options(options.clone());
+        assert_eq!(document.update(input), output);
+        #[cfg(feature = "parallel")]
+        assert_eq!(crate::transform_par(input, &options), output);
+    }
+}
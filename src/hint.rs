@@ -0,0 +1,69 @@
+//! Break hints, markers in words where they may be split.
+
+use crate::{width::char_units, MarkupConfig, TransformOptions, Word};
+
+/// `break_hint` of `options`, when it is set and not empty.
+pub(crate) fn break_hint(options: &TransformOptions) -> Option<&str> {
+    options
+        .break_hint
+        .as_deref()
+        .filter(|hint| !hint.is_empty())
+}
+
+/// Whether `token` is made of `hint` alone, so it is no word.
+pub(crate) fn is_only_hints(token: &str, hint: Option<&str>) -> bool {
+    hint.is_some_and(|hint| token.trim_start_matches(hint).is_empty())
+}
+
+/// Same as [`crate::split_manually`], but `hint` and markers of `markup`
+/// take no width, and the part ends right after the last `hint` in it
+/// that follows some text, if any. Close markers right after the part
+/// stay with it, and the part gets at least one char.
+pub(crate) fn split<'a>(
+    unfitted: Word<'a>,
+    line_width: usize,
+    hint: &str,
+    markup: Option<&MarkupConfig>,
+    half_units: bool,
+) -> (Word<'a>, Word<'a>) {
+    assert!(line_width > 0, "line width must be greater than 0");
+
+    let text = unfitted.text;
+    let mut idx = 0;
+    let mut width = 0;
+    // End of the last hint in the part and the width before it
+    let mut last_hint = None;
+    while let Some(chr) = text[idx..].chars().next() {
+        let rest = &text[idx..];
+        if rest.starts_with(hint) {
+            idx += hint.len();
+            if width > 0 {
+                last_hint = Some((idx, width));
+            }
+            continue;
+        }
+        if let Some((len, closes)) = markup.and_then(|markup| markup.marker_len(rest)) {
+            if width == line_width && !closes {
+                break;
+            }
+            idx += len;
+            continue;
+        }
+
+        let units = if half_units { char_units(chr) } else { 1 };
+        if width + units > line_width && width > 0 {
+            break;
+        }
+        idx += chr.len_utf8();
+        width += units;
+    }
+
+    let (end, width) = last_hint.unwrap_or((idx, width));
+    let (part, rest) = text.split_at(end);
+    let part = Word { text: part, width };
+    let rest = Word {
+        text: rest,
+        width: unfitted.width - width,
+    };
+    (part, rest)
+}
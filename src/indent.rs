@@ -0,0 +1,108 @@
+use alloc::string::String;
+
+/// Prepends `prefix` to every line of `text` that is not blank, i.e.
+/// empty or whitespace-only. Line endings, `\n` or `\r\n`, are kept.
+pub fn indent(text: &str, prefix: &str) -> String {
+    indent_lines(text, prefix, false)
+}
+
+/// Same as [`indent`], but blank lines get `prefix` too. Nothing is added
+/// after a line ending at the very end of `text`.
+pub fn indent_all(text: &str, prefix: &str) -> String {
+    indent_lines(text, prefix, true)
+}
+
+fn indent_lines(text: &str, prefix: &str, blank_lines: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        if blank_lines || !split_ending(line).0.trim().is_empty() {
+            result += prefix;
+        }
+        result += line;
+    }
+    result
+}
+
+/// Removes the longest run of whitespace chars that every line of `text`
+/// that is not blank starts with. Runs are compared char by char, so a
+/// tab and spaces have nothing in common. Blank lines lose all their
+/// whitespace except the line ending.
+pub fn dedent(text: &str) -> String {
+    let mut common: Option<&str> = None;
+    for line in text.lines() {
+        let rest = line.trim_start();
+        if rest.is_empty() {
+            continue;
+        }
+        let leading = &line[..line.len() - rest.len()];
+        common = Some(match common {
+            None => leading,
+            Some(common) => {
+                let mismatch = common
+                    .char_indices()
+                    .zip(leading.chars())
+                    .find(|((_, a), b)| a != b);
+                let len = match mismatch {
+                    Some(((idx, _), _)) => idx,
+                    None => common.len().min(leading.len()),
+                };
+                &common[..len]
+            }
+        });
+    }
+    let common_len = common.map_or(0, str::len);
+
+    let mut result = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let (content, ending) = split_ending(line);
+        if !content.trim().is_empty() {
+            result += &content[common_len..];
+        }
+        result += ending;
+    }
+    result
+}
+
+/// Splits `line` into its text and its line ending, if any.
+fn split_ending(line: &str) -> (&str, &str) {
+    let text = line
+        .strip_suffix('\n')
+        .map_or(line, |text| text.strip_suffix('\r').unwrap_or(text));
+    line.split_at(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dedent, indent, indent_all};
+
+    #[test]
+    fn indented() {
+        let text = "Съешь ещё\n\n \t\r\nэтих мягких\r\n";
+        assert_eq!(
+            indent(text, "> "),
+            "> Съешь ещё\n\n \t\r\n> этих мягких\r\n"
+        );
+        assert_eq!(
+            indent_all(text, "> "),
+            "> Съешь ещё\n> \n>  \t\r\n> этих мягких\r\n"
+        );
+        assert_eq!(indent("", "> "), "");
+        assert_eq!(indent_all("", "> "), "");
+        assert_eq!(indent_all("\n", "> "), "> \n");
+    }
+
+    #[test]
+    fn dedented() {
+        let text = "\t  Съешь ещё\n\t \n\n\t    этих\r\n\t  мягких  ";
+        assert_eq!(dedent(text), "Съешь ещё\n\n\n  этих\r\nмягких  ");
+
+        // Tabs and spaces are different chars
+        assert_eq!(dedent("\tа\n    б\n"), "\tа\n    б\n");
+        assert_eq!(dedent("  \tа\n  б"), "\tа\nб");
+        assert_eq!(dedent("\u{3000}а\n\u{3000}\u{3000}б"), "а\n\u{3000}б");
+
+        assert_eq!(dedent(""), "");
+        assert_eq!(dedent("  \n \r\n"), "\n\r\n");
+        assert_eq!(dedent(&indent("а\n\n  б\n", "\t")), "а\n\n  б\n");
+    }
+}
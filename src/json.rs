@@ -0,0 +1,224 @@
+use serde::Serialize;
+
+use crate::{analyze, JustifiedLine, TransformOptions};
+
+/// Version of the schema of [`layout_json`], raised on every change that
+/// is not a new field.
+pub const LAYOUT_JSON_VERSION: u32 = 1;
+
+/// Layout of [`crate::analyze`] as JSON, for tools outside of Rust.
+///
+/// Schema, with byte ranges as `[start, end]` pairs:
+///
+/// ```text
+/// {
+///   "version": 1,
+///   "line_width": 12,
+///   "paragraphs": [{
+///     "range": [0, 42],          // input bytes of the paragraph
+///     "prefix": "- ",            // indent and bullet, see Paragraph::prefix
+///     "lines": [{
+///       "text": "- Съешь   ещё",  // line as it is written
+///       "leading": 0,            // spaces before the first word
+///       "words": [{ "text": "-", "range": [0, 1] }, ...],
+///       "gaps": [1, 3],          // spaces between adjacent words
+///       "trailing": 0,           // spaces after the last word
+///       "split": false           // last word continues on the next line
+///     }]
+///   }]
+/// }
+/// ```
+///
+/// Joining `leading` spaces, words with `gaps` spaces between them and
+/// `trailing` spaces gives `text`. Lines of a paragraph joined with line
+/// breaks, and paragraphs with empty lines between them, give the output
+/// of [`crate::transform_with_options`] with `preserve_paragraphs`. Only the
+/// options [`crate::analyze`] uses are used.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn layout_json(input: &str, options: &TransformOptions) -> String {
+    let paragraphs = analyze(input, options)
+        .into_iter()
+        .map(|paragraph| {
+            let mut lines: Vec<Line<'_>> = paragraph
+                .lines
+                .iter()
+                .map(|line| Line::new(line, input))
+                .collect();
+            // Parts of a split word touch each other in the input
+            for idx in 1..lines.len() {
+                let (before, after) = lines.split_at_mut(idx);
+                let (Some(last), Some(first)) =
+                    (before[idx - 1].words.last(), after[0].words.first())
+                else {
+                    continue;
+                };
+                before[idx - 1].split = last.range[1] == first.range[0];
+            }
+            ParagraphLayout {
+                range: [paragraph.range.start, paragraph.range.end],
+                prefix: paragraph.prefix,
+                lines,
+            }
+        })
+        .collect();
+
+    let layout = Layout {
+        version: LAYOUT_JSON_VERSION,
+        line_width: options.line_width,
+        paragraphs,
+    };
+    serde_json::to_string(&layout).expect("Layout is serializable")
+}
+
+#[derive(Serialize)]
+struct Layout<'a> {
+    version: u32,
+    line_width: u32,
+    paragraphs: Vec<ParagraphLayout<'a>>,
+}
+
+#[derive(Serialize)]
+struct ParagraphLayout<'a> {
+    range: [usize; 2],
+    prefix: &'a str,
+    lines: Vec<Line<'a>>,
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    text: String,
+    leading: usize,
+    words: Vec<WordLayout<'a>>,
+    gaps: Vec<usize>,
+    trailing: usize,
+    split: bool,
+}
+
+#[derive(Serialize)]
+struct WordLayout<'a> {
+    text: &'a str,
+    range: [usize; 2],
+}
+
+impl<'a> Line<'a> {
+    fn new(line: &JustifiedLine<'a>, input: &str) -> Self {
+        let spaces = |text: &str| text.len() - text.trim_start_matches(' ').len();
+
+        let mut rest = line.text.as_str();
+        let leading = spaces(rest);
+        let mut gaps = Vec::new();
+        for (idx, word) in line.words.iter().enumerate() {
+            let gap = spaces(rest);
+            if idx > 0 {
+                gaps.push(gap);
+            }
+            rest = &rest[gap + word.len()..];
+        }
+
+        let words = line
+            .words
+            .iter()
+            .map(|word| {
+                let start = word.as_ptr() as usize - input.as_ptr() as usize;
+                WordLayout {
+                    text: word,
+                    range: [start, start + word.len()],
+                }
+            })
+            .collect();
+        Line {
+            text: line.text.clone(),
+            leading: match line.words.is_empty() {
+                true => 0,
+                false => leading,
+            },
+            words,
+            gaps,
+            trailing: spaces(rest),
+            split: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{layout_json, LAYOUT_JSON_VERSION};
+    use crate::{transform_with_options, Alignment, TransformOptions};
+
+    /// Output text rebuilt from the JSON alone.
+    fn rebuild(json: &str) -> String {
+        let layout: Value = serde_json::from_str(json).unwrap();
+        assert_eq!(layout["version"], LAYOUT_JSON_VERSION);
+
+        let spaces = |count: &Value| " ".repeat(count.as_u64().unwrap() as usize);
+        let mut paragraphs = Vec::new();
+        for paragraph in layout["paragraphs"].as_array().unwrap() {
+            let mut lines = Vec::new();
+            for line in paragraph["lines"].as_array().unwrap() {
+                let mut text = spaces(&line["leading"]);
+                for (idx, word) in line["words"].as_array().unwrap().iter().enumerate() {
+                    if idx > 0 {
+                        text += &spaces(&line["gaps"][idx - 1]);
+                    }
+                    text += word["text"].as_str().unwrap();
+                }
+                text += &spaces(&line["trailing"]);
+                assert_eq!(line["text"], text.as_str());
+                lines.push(text);
+            }
+            paragraphs.push(lines.join("\n"));
+        }
+        paragraphs.join("\n\n")
+    }
+
+    #[test]
+    fn rebuilds_output() {
+        let input = "  - Съешь ещё этих мягких\nфранцузских булок,  \n\n \t\n\
+                     12) да выпей чаю\r\n\r\nТридцатитрёхбуквенный\n";
+        let mut options = TransformOptions::new(12);
+        options.preserve_paragraphs = true;
+        for align in [Alignment::Justify, Alignment::Center, Alignment::Right] {
+            options.align = align;
+            for pad_last_line in [true, false] {
+                options.pad_last_line = pad_last_line;
+                let json = layout_json(input, &options);
+                assert_eq!(rebuild(&json), transform_with_options(input, &options));
+            }
+        }
+    }
+
+    #[test]
+    fn schema() {
+        let input = "Съешь ещё\n\nТридцатитрёхбуквенный";
+        let json: Value =
+            serde_json::from_str(&layout_json(input, &TransformOptions::new(12))).unwrap();
+        assert_eq!(json["line_width"], 12);
+
+        let first = &json["paragraphs"][0];
+        assert_eq!(first["range"], serde_json::json!([0, 17]));
+        assert_eq!(
+            first["lines"][0],
+            serde_json::json!({
+                "text": "Съешь    ещё",
+                "leading": 0,
+                "words": [
+                    { "text": "Съешь", "range": [0, 10] },
+                    { "text": "ещё", "range": [11, 17] },
+                ],
+                "gaps": [4],
+                "trailing": 0,
+                "split": false,
+            })
+        );
+
+        // Parts of a forced split
+        let lines = &json["paragraphs"][1]["lines"];
+        assert_eq!(lines[0]["split"], true);
+        assert_eq!(lines[0]["words"][0]["range"], serde_json::json!([19, 43]));
+        assert_eq!(lines[1]["split"], false);
+        assert_eq!(lines[1]["trailing"], 3);
+    }
+}
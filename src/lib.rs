@@ -1,7 +1,115 @@
-use std::{collections::LinkedList, iter::Peekable};
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::{borrow::Cow, format, string::String, vec::Vec};
+use core::iter::Peekable;
+
+mod adjuster;
+mod analyze;
+mod banner;
+mod block;
+mod bytes;
+mod check;
+mod cjk;
+mod comment;
+#[cfg(feature = "compat")]
+pub mod compat;
+#[cfg(test)]
+mod alloc_counter;
+mod document;
+mod error;
+mod explain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod french;
+mod hint;
+mod indent;
+#[cfg(feature = "json")]
+mod json;
+mod mapped;
+mod markup;
+mod options;
+mod owned;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod prepared;
+#[cfg(feature = "python")]
+mod python;
+mod ranged;
+#[cfg(feature = "std")]
+mod read;
+mod stats;
+mod table;
+#[cfg(feature = "std")]
+mod terminal;
+mod tokens;
+mod truncate;
+mod unjustify;
+#[cfg(any(test, feature = "test-util"))]
+mod verify;
+mod vertical;
+mod width;
+mod wrapper;
+#[cfg(feature = "std")]
+mod write;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use adjuster::Adjuster;
+pub use analyze::{analyze, JustifiedLine, Paragraph};
+pub use banner::{banner, banner_aligned};
+pub use block::{pad_block, transform_block, BlockWidthError, JustifiedBlock, LineTooLong};
+pub use bytes::transform_bytes;
+pub use check::{check, Violation};
+pub use comment::{reflow_comment, CommentStyle};
+pub use document::Document;
+pub use error::TransformError;
+pub use explain::{explain, BreakReason, LineDecision};
+pub use indent::{dedent, indent, indent_all};
+#[cfg(feature = "json")]
+pub use json::{layout_json, LAYOUT_JSON_VERSION};
+pub use mapped::{transform_mapped, transform_with_spans, OffsetMap};
+pub use options::{
+    Alignment, BlockAlignment, LineEnding, LineFilter, MarkupConfig, NumberStyle,
+    ParseAlignmentError, TransformOptions, Truncation, VerticalAlignment, WidthMode,
+};
+pub use owned::{transform_owned, transform_owned_with_options};
+pub use prepared::PreparedText;
+pub use ranged::transform_ranged;
+#[cfg(feature = "parallel")]
+pub use parallel::transform_par;
+#[cfg(feature = "std")]
+pub use read::JustifiedReader;
+pub use stats::{badness, find_forced_splits, line_badness, stats, SplitInfo, WrapStats};
+#[cfg(feature = "std")]
+pub use terminal::detect_width;
+pub use table::{format_table, format_table_aligned};
+pub use truncate::truncate_to_width;
+pub use unjustify::{reflow, unjustify, unjustify_with_options};
+pub use wrapper::Wrapper;
+#[cfg(feature = "test-util")]
+pub use verify::verify_invariants;
+pub use vertical::transform_vertical;
+#[cfg(feature = "async")]
+pub use write::transform_to_async_writer;
+#[cfg(feature = "std")]
+pub use write::{
+    transform_stream, transform_stream_with_options, transform_to_writer, JustifyWriter,
+};
+
+use french::Glued;
+use tokens::{tokens, Tokens};
+use truncate::ellipsized;
 
 const SPACE_STR: &str = " ";
 const NEWLINE_STR: &str = "\n";
+/// En and em dashes, which a word wider than a line is split after
+const DASHES: [char; 2] = ['\u{2013}', '\u{2014}'];
+/// Non-breaking hyphen, which a word is never split after
+const NO_BREAK_HYPHEN: char = '\u{2011}';
+/// Word joiner, which a word is never split before
+const WORD_JOINER: char = '\u{2060}';
 
 /// Accepts string and adjusts it according the `line_width`.
 /// Tries to fit words, separated by any whitespace to one line (limited by `line_width`).
@@ -10,205 +118,3194 @@ const NEWLINE_STR: &str = "\n";
 /// Any whitespace, that written manually will be replaced by `SPACE_STR` with variable length.
 /// 
 /// In the case when single word does not fit into line, this will be splitted into multiple lines.
-/// Last line will be padded with leading `SPACE_STR` to fill whole line
+/// Last part of the word starts a line, which is filled with the next words like any other.
+/// First part of the word ends the line before it when it fits there, e.g. a part that ends
+/// with a dash.
+///
+/// Adjusting the output again with the same width gives the same output.
 pub fn transform(input: &str, line_width: u32) -> String {
-    if input.chars().count() == 0 {
-        return String::new();
+    transform_with_options(input, &TransformOptions::new(line_width))
+}
+
+/// Same as [`transform`], but configured by `options`.
+///
+/// When `options.number_lines` is set, the gutter is subtracted from the
+/// width available for text. If the gutter alone takes the whole line,
+/// text is still wrapped at a width of 1 and lines exceed `line_width`.
+///
+/// When `options.lines_per_page` is set, `options.page_separator` is
+/// written after every page except the last one.
+///
+/// Like [`transform`], adjusting the output again with the same options
+/// gives the same output, except with `number_lines`, whose gutters are
+/// wrapped as text, and with `lines_per_page`, whose separators are.
+///
+/// Panics when `line_width` is 0 and `input` is not blank, see
+/// [`try_transform_with_options`]. `options.max_width` is not checked:
+/// every line is padded to `line_width`, so a huge width allocates that
+/// many spaces per line and may abort the process when memory runs out.
+pub fn transform_with_options(input: &str, options: &TransformOptions) -> String {
+    transform_by(input, options, adjust, None).expect("Output size is not limited")
+}
+
+/// Wraps body text of `input` at a given width. Fails once the output
+/// exceeds the limit, if any.
+type AdjustFn =
+    fn(&str, &TransformOptions, usize, Option<usize>) -> Result<String, TransformError>;
+
+/// Same as [`transform_with_options`], but body text is wrapped by `adjust`
+/// and the output may not exceed `limit` bytes.
+fn transform_by(
+    input: &str,
+    options: &TransformOptions,
+    adjust: AdjustFn,
+    limit: Option<usize>,
+) -> Result<String, TransformError> {
+    let options = &*width::in_units(options);
+    // Output only grows after the body is adjusted, so the body may not
+    // exceed the limit either
+    let body = match &options.number_lines {
+        Some(style) => number_lines(input, options, style, adjust, limit)?,
+        None => adjust(input, options, options.line_width as usize, limit)?,
+    };
+    decorate(body, options, limit)
+}
+
+/// Adds blank lines, margins, page separators and line endings of
+/// `options` to adjusted `result`.
+fn decorate(
+    mut result: String,
+    options: &TransformOptions,
+    limit: Option<usize>,
+) -> Result<String, TransformError> {
+    if let Some(min_lines) = options.min_lines {
+        result = fill_lines(result, min_lines, options);
     }
 
-    let line_width = line_width as usize;
+    if let Some(field_width) = options.field_width {
+        result = place_in_field(&result, field_width as usize, options);
+    }
 
-    let mut result = String::new();
-    let tokens = input.split_whitespace();
+    if options.margin_left > 0 || options.margin_right > 0 {
+        result = add_margins(&result, options);
+    }
+
+    if let Some(lines_per_page) = options.lines_per_page {
+        let separator = String::from(NEWLINE_STR) + &options.page_separator;
+        result = paginate(&result, lines_per_page).join(&separator);
+    }
+
+    if options.line_ending != LineEnding::Lf {
+        result = result.replace(NEWLINE_STR, options.line_ending.as_str());
+    }
+    if options.final_newline && !result.is_empty() {
+        result += options.line_ending.as_str();
+    }
+
+    check_output_size(result.len(), limit)?;
+    Ok(result)
+}
 
-    let mut need_newline = false;
-    let mut peekable = tokens.peekable();
+/// Adds blank lines to `text` until it has `min_lines` lines, placed
+/// according to `options.vertical_align`.
+fn fill_lines(text: String, min_lines: usize, options: &TransformOptions) -> String {
+    let n_lines = match text.is_empty() {
+        true => 0,
+        false => text.matches(NEWLINE_STR).count() + 1,
+    };
+    let Some(n_blank) = min_lines.checked_sub(n_lines).filter(|&n_blank| n_blank > 0) else {
+        return text;
+    };
+
+    let n_above = match options.vertical_align {
+        VerticalAlignment::Top => 0,
+        VerticalAlignment::Middle => n_blank / 2,
+        VerticalAlignment::Bottom => n_blank,
+    };
+    let blank = match options.pad_last_line {
+        true => SPACE_STR.repeat(options.line_width as usize),
+        false => String::new(),
+    };
+
+    let mut lines = Vec::with_capacity(min_lines);
+    lines.extend(core::iter::repeat_n(blank.as_str(), n_above));
+    lines.extend(text.split(NEWLINE_STR).take(n_lines));
+    lines.extend(core::iter::repeat_n(blank.as_str(), n_blank - n_above));
+    lines.join(NEWLINE_STR)
+}
 
-    while let Some(_) = peekable.peek() {
-        let fit_result = fit_strs(&mut peekable, line_width);
+/// Surrounds every non-empty line of `text` with the margins of `options`.
+fn add_margins(text: &str, options: &TransformOptions) -> String {
+    let left = SPACE_STR.repeat(options.margin_left);
+    let right = SPACE_STR.repeat(options.margin_right);
+    let lines: Vec<&str> = text.split(NEWLINE_STR).collect();
 
-        if need_newline {
+    let n_margins = lines.iter().filter(|line| !line.is_empty()).count();
+    let mut result = String::with_capacity(text.len() + n_margins * (left.len() + right.len()));
+    for (idx, line) in lines.iter().enumerate() {
+        if idx > 0 {
             result += NEWLINE_STR;
         }
+        if line.is_empty() {
+            continue;
+        }
 
-        if fit_result.list.len() != 0 {
-            let gaps_info = gaps(fit_result.list.len(), fit_result.total_len, line_width);
-            let n_gaps = fit_result.list.len() - 1;
-            for (idx, token) in fit_result.list.iter().enumerate() {
-                result += token;
+        result += &left;
+        result += line;
+        // Unpadded last line of a paragraph keeps no trailing whitespace
+        let is_last = lines.get(idx + 1).is_none_or(|next| next.is_empty());
+        if options.pad_last_line || !is_last {
+            result += &right;
+        }
+    }
+    result
+}
 
-                let next_idx = idx + 1;
-                if next_idx < n_gaps {
-                    result += &SPACE_STR.repeat(gaps_info.body_gaps_size);
-                } else if next_idx == n_gaps || fit_result.list.len() == 1 {
-                    result += &SPACE_STR.repeat(gaps_info.tail_gap_size);
-                }
-            }
-        } else {
-            // Case when even single word does not fit to required line length.
-            // We should at least split it manually.
-            let peeked = peekable
-                .peek()
-                .expect("Value is already peeked, but results in None");
+/// Pads every non-empty line of `text` to `field_width`. Padding before
+/// the lines is the same for all of them, as `options.block_align` says.
+fn place_in_field(text: &str, field_width: usize, options: &TransformOptions) -> String {
+    let before = field_offset(field_width, options);
+    let free_space = field_width.saturating_sub(options.line_width as usize);
 
-            result += &split_manually(&peeked, line_width);
+    let lines: Vec<&str> = text.split(NEWLINE_STR).collect();
+    let mut result = String::with_capacity(text.len() + lines.len() * free_space);
+    for (idx, line) in lines.iter().enumerate() {
+        if idx > 0 {
+            result += NEWLINE_STR;
+        }
+        if line.is_empty() {
+            continue;
+        }
 
-            // Force peekable to jump to the next element to prevent
-            // stucking on large unconsumed word
-            _ = peekable.next();
+        push_spaces(&mut result, before);
+        result += line;
+        // Unpadded last line of a paragraph keeps no trailing whitespace
+        let is_last = lines.get(idx + 1).is_none_or(|next| next.is_empty());
+        if options.pad_last_line || !is_last {
+            let width = before + check::visible_width(line, options);
+            push_spaces(&mut result, field_width.saturating_sub(width));
         }
+    }
+    result
+}
 
-        need_newline = true;
+/// Padding before every line placed in a field of `field_width`.
+fn field_offset(field_width: usize, options: &TransformOptions) -> usize {
+    let block_width = options.line_width.max(options.initial_width.unwrap_or(0)) as usize;
+    let free_space = field_width.saturating_sub(block_width);
+    match options.block_align {
+        BlockAlignment::Left => 0,
+        BlockAlignment::Center => free_space / 2,
+        BlockAlignment::Right => free_space,
     }
+}
 
-    result
+fn check_output_size(produced: usize, limit: Option<usize>) -> Result<(), TransformError> {
+    match limit {
+        Some(limit) if produced > limit => Err(TransformError::OutputTooLarge { produced, limit }),
+        _ => Ok(()),
+    }
 }
 
-fn split_manually(unfitted_str: &str, line_width: usize) -> String {
-    use std::cmp::min;
+/// Same as [`transform`], but reports invalid arguments instead of panicking.
+///
+/// Never panics, for any `input` and `line_width`. Every line of the
+/// output is exactly `line_width` chars wide.
+pub fn try_transform(input: &str, line_width: u32) -> Result<String, TransformError> {
+    try_transform_with_options(input, &TransformOptions::new(line_width))
+}
 
-    let mut result = String::new();
+/// Same as [`transform_with_options`], but reports invalid options instead of panicking.
+pub fn try_transform_with_options(
+    input: &str,
+    options: &TransformOptions,
+) -> Result<String, TransformError> {
+    validate(options)?;
+    transform_by(input, options, adjust, options.max_output_bytes)
+}
 
-    let str_len = unfitted_str.len();
-    let mut elapsed = 0;
+/// Checks the widths of `options`, see [`try_transform_with_options`].
+fn validate(options: &TransformOptions) -> Result<(), TransformError> {
+    check_width(options.line_width, options.max_width)?;
+    if let Some(initial_width) = options.initial_width {
+        check_width(initial_width, options.max_width)?;
+    }
+    let widest = options.line_width.max(options.initial_width.unwrap_or(0));
+    if let Some(field_width) = options.field_width.filter(|&field_width| field_width < widest) {
+        return Err(TransformError::FieldTooNarrow {
+            field_width,
+            line_width: widest,
+        });
+    }
+    let total_width = options.field_width.unwrap_or(widest) as usize;
+    let total_width = total_width
+        .saturating_add(options.margin_left)
+        .saturating_add(options.margin_right);
+    check_width(u32::try_from(total_width).unwrap_or(u32::MAX), options.max_width)
+}
 
-    let mut need_newline = false;
+fn check_width(width: u32, max_width: u32) -> Result<(), TransformError> {
+    if width == 0 {
+        return Err(TransformError::ZeroWidth);
+    }
+    if width > max_width {
+        return Err(TransformError::WidthTooLarge { width, max_width });
+    }
+    Ok(())
+}
 
-    while elapsed != str_len {
-        let tail = &unfitted_str[elapsed..];
+/// Same as [`transform_with_options`], but borrows `input` when it already
+/// is a single adjusted line: words separated by single spaces that need
+/// no padding.
+pub fn transform_cow<'a>(input: &'a str, options: &TransformOptions) -> Cow<'a, str> {
+    if is_adjusted(input, options) {
+        Cow::Borrowed(input)
+    } else {
+        Cow::Owned(transform_with_options(input, options))
+    }
+}
 
-        // line_width is upper limit for characters counting
-        let available_chars = tail.chars().take(line_width).count();
-        let (available, chr) = tail
-            .char_indices()
-            .nth(available_chars - 1)
-            .expect("String has reached end unexpectedly");
+fn is_adjusted(input: &str, options: &TransformOptions) -> bool {
+    let has_margins = options.margin_left > 0 || options.margin_right > 0;
+    let decorated = options.number_lines.is_some()
+        || options.field_width.is_some()
+        || options.final_newline
+        || is_glued(options)
+        || options.cjk_latin_spacing
+        || options.markup.is_some()
+        || options.width_mode != WidthMode::Chars
+        || options.hang_punctuation
+        || options.break_hint.is_some()
+        || has_margins;
+    if decorated || options.max_lines == Some(0) {
+        return false;
+    }
+    if options.blank_line_for_empty && tokens(input).next().is_none() {
+        return false;
+    }
 
-        let to_append_len = min(str_len - elapsed, available + chr.len_utf8());
+    // Single line of non-empty input needs no blank lines
+    let min_lines = usize::from(!input.is_empty());
+    if options.min_lines.is_some_and(|lines| lines > min_lines) {
+        return false;
+    }
 
-        if need_newline {
-            result += NEWLINE_STR;
+    let mut n_chars = 0;
+    let mut prev_space = true;
+    for chr in input.chars() {
+        if chr.is_whitespace() {
+            // Leading, repeated or any other whitespace gets collapsed
+            if chr != ' ' || prev_space {
+                return false;
+            }
+            prev_space = true;
+        } else {
+            prev_space = false;
         }
+        n_chars += 1;
+    }
+
+    // Trailing space is dropped, empty input stays empty
+    if prev_space {
+        return input.is_empty();
+    }
+
+    // Single line is the first line of the paragraph
+    let line_width = options.initial_width.unwrap_or(options.line_width) as usize;
+    let unpadded = !options.pad_last_line
+        && matches!(options.align, Alignment::Left | Alignment::Justify);
+    n_chars == line_width || (n_chars < line_width && unpadded)
+}
+
+/// Adjusts `input` like [`transform`] and splits the output into pages
+/// of at most `lines_per_page` lines. Only the last page may be shorter.
+///
+/// A `lines_per_page` of 0 is treated as 1.
+pub fn transform_pages(input: &str, line_width: u32, lines_per_page: usize) -> Vec<String> {
+    paginate(&transform(input, line_width), lines_per_page)
+}
+
+/// Adjusts `input` like [`transform`] and splits the lines into `n`
+/// columns, filled one after another. Heights of the columns differ by
+/// at most one line before the shorter ones are padded with lines of
+/// `line_width` spaces, so all columns are equally high.
+///
+/// There are always `n` columns, blank ones when there are fewer lines.
+/// An `n` of 0 is treated as 1.
+pub fn wrap_columns(input: &str, line_width: u32, n: usize) -> Vec<Vec<String>> {
+    let n = n.max(1);
+    let text = transform(input, line_width);
+    let mut lines = text.split(NEWLINE_STR).filter(|line| !line.is_empty());
+
+    let n_lines = lines.clone().count();
+    let (height, n_taller) = (n_lines.div_ceil(n), n_lines % n);
+    let blank = SPACE_STR.repeat(line_width as usize);
+    (0..n)
+        .map(|idx| {
+            let n_taken = match n_taller == 0 || idx < n_taller {
+                true => height,
+                false => height - 1,
+            };
+            let mut column: Vec<String> = lines.by_ref().take(n_taken).map(String::from).collect();
+            column.resize(height, blank.clone());
+            column
+        })
+        .collect()
+}
 
-        result += &tail[..to_append_len];
+/// Line `width` chars wide with `left` at the start, `right` at the end
+/// and `fill` between them, e.g. `Total ........ 42`. There is at least
+/// one `fill` char, so `right` is cut to `width - 1` chars.
+///
+/// When both parts do not fit, `left` is wrapped into left-aligned lines
+/// padded with spaces, and only the last line carries `right`. When the
+/// last line of `left` leaves no room either, `right` gets a line of its own.
+///
+/// Panics when `width` is 0.
+pub fn two_part_line(left: &str, right: &str, width: u32, fill: char) -> String {
+    assert!(width > 0, "line width must be greater than 0");
+    let line_width = width as usize;
+    let right: String = right.chars().take(line_width - 1).collect();
+    let room = line_width - right.chars().count();
 
-        if available_chars < line_width {
-            result += &SPACE_STR.repeat(line_width - available_chars);
+    let mut options = TransformOptions::new(width);
+    options.align = Alignment::Left;
+    let mut result = transform_with_options(left, &options);
+
+    // Last line goes without its padding, when there is room for a fill char
+    let last_start = result.rfind(NEWLINE_STR).map_or(0, |idx| idx + 1);
+    let last_width = result[last_start..].trim_end().chars().count();
+    let n_fill = match last_width < room {
+        true => {
+            result.truncate(last_start + result[last_start..].trim_end().len());
+            room - last_width
+        }
+        false => {
+            if !result.is_empty() {
+                result += NEWLINE_STR;
+            }
+            room
         }
+    };
+    result.extend(core::iter::repeat_n(fill, n_fill));
+    result += &right;
+    result
+}
 
-        elapsed += to_append_len;
-        need_newline = true;
+/// Same as [`transform`], but line `i` is `widths[i]` chars wide, and
+/// lines after the last of `widths` are `rest_width` chars wide. Every
+/// line is padded or justified to its own width.
+///
+/// Panics when a width used for a line is 0, see [`try_transform_shaped`].
+pub fn transform_shaped(input: &str, widths: &[u32], rest_width: u32) -> String {
+    let width_fn = |idx: usize| Ok(widths.get(idx).copied().unwrap_or(rest_width));
+    match shape(input, width_fn) {
+        Ok(result) => result,
+        Err(_) => unreachable!("Widths are not checked"),
     }
+}
 
-    result
+/// Same as [`transform_shaped`], but fails when any of `widths` or
+/// `rest_width` is 0 or above [`TransformOptions::DEFAULT_MAX_WIDTH`].
+pub fn try_transform_shaped(
+    input: &str,
+    widths: &[u32],
+    rest_width: u32,
+) -> Result<String, TransformError> {
+    for &width in widths.iter().chain([&rest_width]) {
+        check_width(width, TransformOptions::DEFAULT_MAX_WIDTH)?;
+    }
+    Ok(transform_shaped(input, widths, rest_width))
 }
 
-fn fit_strs<'a>(
-    tokens: &mut Peekable<std::str::SplitWhitespace<'a>>,
-    max_line_width: usize,
-) -> FitResult<'a> {
-    const ONE_SPACE: usize = 1;
+/// Same as [`transform_shaped`], but line `i` is `width_fn(i)` chars wide.
+///
+/// `width_fn` is called as lines are laid out, once for every line and
+/// once more to find out that no lines are left, so widths may depend on
+/// state the caller updates meanwhile. Fails when a width used for a line
+/// is 0 or above [`TransformOptions::DEFAULT_MAX_WIDTH`].
+pub fn transform_shaped_with(
+    input: &str,
+    width_fn: impl Fn(usize) -> u32,
+) -> Result<String, TransformError> {
+    shape(input, |idx| {
+        let width = width_fn(idx);
+        check_width(width, TransformOptions::DEFAULT_MAX_WIDTH)?;
+        Ok(width)
+    })
+}
 
-    let mut list = LinkedList::<&str>::new();
-    let mut total_len = 0;
-    let mut chk_len = 0;
+/// Lays out lines of `input`, each as wide as `width_fn` returns for its
+/// index. An error is returned only for a width of a line that exists.
+fn shape(
+    input: &str,
+    width_fn: impl Fn(usize) -> Result<u32, TransformError>,
+) -> Result<String, TransformError> {
+    // Width of every line is set below
+    let options = TransformOptions::new(1);
+    let mut lines = Lines::new(input, &options, 1);
 
-    while let Some(s) = tokens.next_if(|s| chk_len + s.chars().count() <= max_line_width) {
-        let chars_count = s.chars().count();
-        total_len += chars_count;
+    let mut result = String::new();
+    for idx in 0.. {
+        let line_width = width_fn(idx);
+        // Any valid width tells whether there is a line left
+        lines.line_width = line_width.as_ref().map_or(1, |&width| width as usize);
+        let Some(plan) = lines.next_plan() else {
+            break;
+        };
+        line_width?;
 
-        // Assuming there will space before next word
-        chk_len += chars_count + ONE_SPACE;
+        if idx > 0 {
+            result += NEWLINE_STR;
+        }
+        plan.push(&mut result);
+    }
+    Ok(result)
+}
+
+/// Number of lines in the output of [`transform`], i.e.
+/// `transform(input, line_width).lines().count()`, computed without
+/// building the output.
+pub fn line_count(input: &str, line_width: u32) -> usize {
+    line_count_with_options(input, &TransformOptions::new(line_width))
+}
+
+/// Same as [`line_count`], but configured by `options` like
+/// [`transform_with_options`]. Lines of `options.page_separator` are
+/// counted as well.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn line_count_with_options(input: &str, options: &TransformOptions) -> usize {
+    let options = &*width::in_units(options);
+    let line_width = options.line_width as usize;
+    let n_lines = match &options.number_lines {
+        Some(style) => numbered_layout(input, options, line_width, style).1,
+        None => count_lines(input, options, line_width, None).0,
+    };
+    let n_lines = n_lines.max(options.min_lines.unwrap_or(0));
 
-        list.push_back(s);
+    match options.lines_per_page {
+        // Every separator adds the lines it ends
+        Some(lines_per_page) if n_lines > 0 => {
+            let n_pages = n_lines.div_ceil(lines_per_page.max(1));
+            n_lines + (n_pages - 1) * options.page_separator.matches(NEWLINE_STR).count()
+        }
+        _ => n_lines,
     }
+}
 
-    FitResult { list, total_len }
+/// Width of the widest word of `input`, in chars, or 0 when it has no
+/// words. Wrapping at this width or wider never splits a word.
+///
+/// Words are split at any whitespace, no-break spaces included, exactly
+/// as [`transform`] splits them.
+pub fn minimum_width(input: &str) -> usize {
+    let measure = measure(input);
+    tokens(input).map(|token| measure(token).width).max().unwrap_or(0)
 }
 
-struct FitResult<'a> {
-    list: LinkedList<&'a str>,
-    total_len: usize,
+/// Same as [`minimum_width`], but for wrapping with `options`: the line
+/// number gutter of `options.number_lines` is added to the width of the
+/// widest word. `options.line_width` is ignored.
+///
+/// Wrapping at exactly this width never splits a word, so every word of
+/// `input` appears in the output whole.
+pub fn minimum_width_with(input: &str, options: &TransformOptions) -> usize {
+    let word_width = minimum_width(input);
+    let Some(style) = options.number_lines.as_ref().filter(|_| word_width > 0) else {
+        return word_width;
+    };
+
+    // Gutter is never narrower than the first number, so the search for
+    // the narrowest line that fits the widest word starts there
+    let separator_len = style.separator.chars().count();
+    let mut line_width = word_width + count_digits(style.start) + separator_len;
+    while numbered_layout(input, options, line_width, style).0 < word_width {
+        line_width += 1;
+    }
+    line_width
 }
 
-fn gaps(n_tokens: usize, total_len: usize, line_width: usize) -> GapInfo {
-    if n_tokens == 0 {
-        return GapInfo {
-            body_gaps_size: 0,
-            tail_gap_size: 0,
-        };
-    } else if n_tokens == 1 {
-        return GapInfo {
-            body_gaps_size: 0,
-            tail_gap_size: line_width - total_len,
+/// Narrowest line width at which [`transform`] of `input` takes at most
+/// `max_lines` lines, or `None` when `max_lines` is 0 and `input` has words.
+///
+/// Input without words fits at any width and gets 0. Otherwise the width
+/// is at most the length of all words joined by single spaces, which
+/// always fits into a single line.
+pub fn width_for_lines(input: &str, max_lines: usize) -> Option<usize> {
+    let measure = measure(input);
+    let (n_words, words_width) = tokens(input).fold((0, 0), |(count, width), token| {
+        (count + 1, width + measure(token).width)
+    });
+    if n_words == 0 {
+        return Some(0);
+    }
+    if max_lines == 0 {
+        return None;
+    }
+
+    // Line count never grows with the width, so the narrowest width that
+    // fits is found by bisection
+    let mut fits = n_words - 1 + words_width;
+    let mut narrower = 0;
+    while fits - narrower > 1 {
+        let width = narrower + (fits - narrower) / 2;
+        if line_count(input, width as u32) <= max_lines {
+            fits = width;
+        } else {
+            narrower = width;
+        }
+    }
+    Some(fits)
+}
+
+/// Byte offsets into `input` at which the lines of [`transform`] of
+/// `input` begin, i.e. where the first word of every line starts. Parts
+/// of a split word begin inside the word, always at a char boundary.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn break_offsets(input: &str, line_width: u32) -> Vec<usize> {
+    let options = TransformOptions::new(line_width);
+    let mut lines = Lines::new(input, &options, line_width as usize);
+    core::iter::from_fn(|| lines.next_plan())
+        .map(|plan| plan.text.as_ptr() as usize - input.as_ptr() as usize)
+        .collect()
+}
+
+/// Narrowest line width up to `max_width` at which [`transform`] of
+/// `input` takes no more lines than at `max_width`. Wrapping at it leaves
+/// the least padding. Input without words keeps `max_width`.
+///
+/// Panics when `max_width` is 0 and `input` is not blank.
+pub fn shrink_to_fit(input: &str, max_width: u32) -> u32 {
+    let n_lines = line_count(input, max_width);
+    width_for_lines(input, n_lines)
+        .filter(|&width| width > 0)
+        .map_or(max_width, |width| width as u32)
+}
+
+/// Same as [`transform`], but wraps at [`shrink_to_fit`] of `max_width`.
+pub fn transform_shrunk(input: &str, max_width: u32) -> String {
+    transform(input, shrink_to_fit(input, max_width))
+}
+
+/// Whether [`transform`] of `input` at `line_width` takes at most
+/// `max_lines` lines without splitting any word.
+///
+/// Stops at the first line over the budget or the first word wider than
+/// a line, so the rest of `input` is never looked at.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn fits(input: &str, line_width: u32, max_lines: usize) -> bool {
+    fits_by(input, line_width, max_lines, false)
+}
+
+/// Same as [`fits`], but words wider than a line are split like
+/// [`transform`] splits them and their parts count as lines.
+pub fn fits_splitting(input: &str, line_width: u32, max_lines: usize) -> bool {
+    fits_by(input, line_width, max_lines, true)
+}
+
+fn fits_by(input: &str, line_width: u32, max_lines: usize, split: bool) -> bool {
+    let options = TransformOptions::new(line_width);
+    let mut lines = Lines::new(input, &options, line_width as usize);
+
+    // First part of a split word leaves the rest behind, which takes
+    // whole lines except for its last part
+    let units = core::iter::from_fn(|| {
+        lines.next_plan()?;
+        let is_split = !lines.unfitted.text.is_empty();
+        Some((1 + lines.skip_unfitted(), is_split))
+    });
+    within_lines(units, max_lines, split)
+}
+
+/// Whether lines taken by output units sum to at most `max_lines`,
+/// without any unit that splits a word unless `split` is set. Stops at
+/// the first unit that fails.
+fn within_lines(
+    mut units: impl Iterator<Item = (usize, bool)>,
+    max_lines: usize,
+    split: bool,
+) -> bool {
+    let mut n_lines = 0;
+    units.all(|(unit_lines, is_split)| {
+        n_lines += unit_lines;
+        n_lines <= max_lines && (split || !is_split)
+    })
+}
+
+/// Width left for text next to the line number gutter and the number of
+/// output lines, found with the same search as in `number_lines`.
+fn numbered_layout(
+    input: &str,
+    options: &TransformOptions,
+    line_width: usize,
+    style: &NumberStyle,
+) -> (usize, usize) {
+    let separator_len = style.separator.chars().count();
+
+    let mut digits = count_digits(style.start);
+    loop {
+        let text_width = line_width.saturating_sub(digits + separator_len).max(1);
+        let text_options = without_gutter(options, digits + separator_len);
+        let (n_lines, n_numbered) = count_lines(input, &text_options, text_width, Some(style));
+        let required = count_digits(style.start + n_numbered.saturating_sub(1));
+        if required <= digits {
+            return (text_width, n_lines);
+        }
+        digits = required;
+    }
+}
+
+/// Counts lines of adjusted body text, and how many of them get a number
+/// when `style` is given.
+fn count_lines(
+    input: &str,
+    options: &TransformOptions,
+    line_width: usize,
+    style: Option<&NumberStyle>,
+) -> (usize, usize) {
+    let input = &*cjk::spaced(input, options);
+    let glued = is_glued(options)
+        .then(|| Glued::new(input, options, line_width))
+        .flatten();
+    let input = glued.as_ref().map_or(input, |glued| glued.text.as_str());
+
+    let mut n_lines = 0;
+    let mut n_numbered = 0;
+    let mut prev_empty = true;
+
+    let max_lines = options.max_lines.unwrap_or(usize::MAX);
+    let mut lines = Lines::new(input, options, line_width);
+    while n_lines < max_lines {
+        let Some(plan) = lines.next_plan() else {
+            break;
         };
+        let is_empty = plan.len() == 0;
+        let continuation = style.is_some_and(|style| style.number_continuation_lines);
+        if !is_empty && (continuation || prev_empty) {
+            n_numbered += 1;
+        }
+        prev_empty = is_empty;
+        n_lines += 1;
+
+        // Rest of a split word takes whole lines, except maybe the last
+        let n_parts = lines.skip_unfitted().min(max_lines - n_lines);
+        n_lines += n_parts;
+        if continuation {
+            n_numbered += n_parts;
+        }
     }
 
-    let n_gaps = n_tokens - 1;
-    let free_space = line_width - total_len;
-    let remainder = free_space % (n_gaps);
+    (n_lines, n_numbered)
+}
 
-    let div = if n_gaps > 1 && remainder > 0 {
-        n_gaps - 1
-    } else {
-        n_gaps
+/// Whether some spaces of the input neither break nor stretch.
+fn is_glued(options: &TransformOptions) -> bool {
+    options.french_spacing || !options.atomic_delimiters.is_empty()
+}
+
+fn adjust(
+    input: &str,
+    options: &TransformOptions,
+    line_width: usize,
+    limit: Option<usize>,
+) -> Result<String, TransformError> {
+    let input = &*cjk::spaced(input, options);
+    let mut result = None;
+    if is_glued(options) {
+        if let Some(glued) = Glued::new(input, options, line_width) {
+            let adjusted = adjust_words(&glued.text, options, line_width, limit)?;
+            result = Some(glued.restore(&adjusted, options.french_narrow_spaces));
+        }
+    }
+    let result = match result {
+        Some(result) => result,
+        None => adjust_words(input, options, line_width, limit)?,
     };
 
-    let max_gap = (free_space - remainder) / div;
+    match &options.markup {
+        Some(markup) if markup.reopen_on_wrap => {
+            let result = markup.reopen_spans(&result);
+            check_output_size(result.len(), limit)?;
+            Ok(result)
+        }
+        _ => Ok(result),
+    }
+}
 
-    let last_gap = if remainder > 0 { remainder } else { max_gap };
+/// Same as [`adjust`], but every word of `input` is wrapped as it is.
+fn adjust_words(
+    input: &str,
+    options: &TransformOptions,
+    line_width: usize,
+    limit: Option<usize>,
+) -> Result<String, TransformError> {
+    // Lines are planned first, so the output is never written past the
+    // limit and gets its exact size up front
+    let mut plans = Vec::with_capacity(input.len() / line_width.max(1) + 1);
+    let mut len = 0;
+    let max_lines = options.max_lines.unwrap_or(usize::MAX);
+    let mut lines = Lines::new(input, options, line_width);
+    while plans.len() < max_lines {
+        let Some(plan) = lines.next_plan() else {
+            break;
+        };
+        if !plans.is_empty() {
+            len += NEWLINE_STR.len();
+        }
+        len += plan.len();
+        check_output_size(len, limit)?;
+        plans.push(plan);
+    }
 
-    GapInfo {
-        body_gaps_size: max_gap,
-        tail_gap_size: last_gap,
+    // Last line kept is rebuilt to end with the ellipsis
+    let last_line = match &options.truncation {
+        Truncation::Ellipsis(ellipsis) if plans.len() == max_lines => {
+            let cut = lines.next_plan().is_some();
+            plans.pop_if(|_| cut).map(|plan| {
+                let line = ellipsized(&plan, ellipsis, options);
+                len = len - plan.len() + line.len();
+                line
+            })
+        }
+        _ => None,
+    };
+    check_output_size(len, limit)?;
+
+    let mut result = String::with_capacity(len);
+    for (idx, plan) in plans.iter().enumerate() {
+        if idx > 0 {
+            result += NEWLINE_STR;
+        }
+        plan.push(&mut result);
+    }
+    if let Some(line) = last_line {
+        if !plans.is_empty() {
+            result += NEWLINE_STR;
+        }
+        result += &line;
     }
+
+    debug_assert_eq!(result.len(), len);
+    Ok(result)
 }
 
-struct GapInfo {
-    body_gaps_size: usize,
-    tail_gap_size: usize,
+/// Produces adjusted lines of the input one by one.
+pub(crate) struct Lines<'a> {
+    input: &'a str,
+    options: &'a TransformOptions,
+    /// Width of the next line, may change between lines
+    line_width: usize,
+    paragraphs: Paragraphs<'a>,
+    /// Either [`Word::new`] or [`Word::ascii`], chosen once for the input
+    measure: fn(&'a str) -> Word<'a>,
+    tokens: Words<'a>,
+    /// Rest of a word that does not fit even a whole line
+    unfitted: Word<'a>,
+    has_lines: bool,
+    /// Width of the first line of every paragraph, when it differs
+    initial_width: Option<usize>,
+    /// Whether no line of the current paragraph is planned yet
+    paragraph_start: bool,
+    /// Line matched by `skip_line` that comes after an empty line
+    verbatim: Option<&'a str>,
+    /// Words of the whole input, measured up front
+    prepared: Option<&'a [Word<'a>]>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::transform;
+impl<'a> Lines<'a> {
+    /// Lays out `input` in lines of `line_width`, in half units with
+    /// [`WidthMode::HalfWidthUnits`].
+    pub(crate) fn new(input: &'a str, options: &'a TransformOptions, line_width: usize) -> Self {
+        let measure: fn(&'a str) -> Word<'a> = match options.width_mode {
+            WidthMode::Chars => measure(input),
+            WidthMode::HalfWidthUnits => Word::half_units,
+        };
+        Lines::with_measure(input, options, line_width, measure)
+    }
 
-    #[test]
-    fn split_test() {
-        let test_cases = [
-            ("consectetur", 4, "cons\necte\ntur "),
-            ("Привет", 12, "Привет      "),
-            ("Поддержка кодировки utf-8 в коде", 8, "Поддержк\nа       \nкодировк\nи       \nutf-8  в\nкоде    "),
-            ("Съешь ещё этих мягких французских булок, да выпей чаю", 12, "Съешь    ещё\nэтих  мягких\nфранцузских \nбулок,    да\nвыпей    чаю"),
-            ("🤩 привет  💨 hello", 1, "🤩\nп\nр\nи\nв\nе\nт\n💨\nh\ne\nl\nl\no"),
-            ("🤩 привет  💨 hello", 3, "🤩  \nпри\nвет\n💨  \nhel\nlo "),
-        ];
+    /// Same as [`Lines::new`], but words are measured by `measure`.
+    fn with_measure(
+        input: &'a str,
+        options: &'a TransformOptions,
+        line_width: usize,
+        measure: fn(&'a str) -> Word<'a>,
+    ) -> Self {
+        Lines {
+            input,
+            options,
+            line_width,
+            paragraphs: Paragraphs::new(input, options.preserve_paragraphs)
+                .with_skip_line(options.skip_line.as_ref()),
+            measure,
+            tokens: words("", measure, options),
+            unfitted: Word::new(""),
+            has_lines: false,
+            initial_width: options.initial_width.map(|width| width as usize),
+            paragraph_start: true,
+            verbatim: None,
+            prepared: None,
+        }
+    }
 
-        for &(input, line_width, expected) in &test_cases {
-            println!("input: '{}'", input);
-            assert_eq!(transform(input, line_width), expected);
+    /// Same as [`Lines::with_measure`], but the input is a single
+    /// paragraph of `words`, measured by `measure` already.
+    pub(crate) fn prepared(
+        input: &'a str,
+        words: &'a [Word<'a>],
+        options: &'a TransformOptions,
+        line_width: usize,
+        measure: fn(&'a str) -> Word<'a>,
+    ) -> Self {
+        let mut lines = Lines::with_measure(input, options, line_width, measure);
+        lines.prepared = Some(words);
+        lines
+    }
+
+    /// Width of the next line.
+    fn width(&self) -> usize {
+        match self.initial_width {
+            Some(width) if self.paragraph_start => width,
+            _ => self.line_width,
         }
     }
 
-    #[test]
-    fn equal_length_lines() {
-        let test_cases = [
-            ("Бык тупогуб, тупогубенький бычок, у быка губа тупа.", 5),
-            ("Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.", 18),
-            ("Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.", 6),
-            ("Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.", 1),
-            ("Тpидцaть тpи коpaбля лaвиpовaли, лaвиpовaли, лавировали, дa не \tвылaвиpовaли.", 4),
-            ("У переп\tелa и перепелки\t\t\t пять  \t\tперепелят    .", 3),
-        ];
+    /// Appends the next line, without line ending, to `result`.
+    /// Returns `false` when there are no lines left.
+    #[cfg(any(feature = "std", test))]
+    pub(crate) fn next_line(&mut self, result: &mut String) -> bool {
+        match self.next_plan() {
+            Some(plan) => {
+                plan.push(result);
+                true
+            }
+            None => false,
+        }
+    }
 
-        for (input, line_width) in test_cases {
-            let result = transform(input, line_width);
-            println!("input: '{}'", input);
-            for line in result.lines() {
-                assert_eq!(line.chars().count() as u32, line_width);
+    /// Skips parts of a manually split word that take whole lines and
+    /// returns their number. The last part is left to start the next line.
+    fn skip_unfitted(&mut self) -> usize {
+        if self.unfitted.width <= self.line_width {
+            return 0;
+        }
+
+        let plain = self.options.break_after_chars.is_empty()
+            && self.options.break_hint.is_none()
+            && !self.unfitted.text.contains(DASHES)
+            && !self.unfitted.text.contains([NO_BREAK_HYPHEN, WORD_JOINER]);
+        if !plain || self.options.width_mode != WidthMode::Chars {
+            // Parts may be narrower than a line
+            let mut n_parts = 0;
+            while self.unfitted.width > self.line_width {
+                let (part, rest) = self.split(self.unfitted, self.line_width);
+                // Rest of such a part is planned as a whole line
+                if part.width == 0 {
+                    break;
+                }
+                self.unfitted = rest;
+                n_parts += 1;
+            }
+            return n_parts;
+        }
+
+        let n_parts = (self.unfitted.width - 1) / self.line_width;
+        let (_, last) = self.split(self.unfitted, n_parts * self.line_width);
+        self.unfitted = last;
+        n_parts
+    }
+
+    /// Measures `word` like its tokens are measured.
+    fn word(&self, word: &'a str) -> Word<'a> {
+        measure_marked(word, self.measure, self.options)
+    }
+
+    /// Splits `unfitted` like [`split_word`] does.
+    fn split(&self, unfitted: Word<'a>, line_width: usize) -> (Word<'a>, Word<'a>) {
+        split_word(unfitted, line_width, self.options, self.measure)
+    }
+
+    /// Lays out the next line without writing it.
+    pub(crate) fn next_plan(&mut self) -> Option<LinePlan<'a>> {
+        let plan = self.plan_line()?;
+        self.paragraph_start &= plan.len() == 0;
+        Some(plan)
+    }
+
+    /// Drops words of `orphan_words` from the end of `fit_result`, so they
+    /// do not end the line. The first word is always kept.
+    fn drop_orphans(&self, fit_result: &mut FitResult<'a>) {
+        let Some(orphans) = &self.options.orphan_words else {
+            return;
+        };
+        let lowercase = |word: &'a str| word.chars().flat_map(char::to_lowercase);
+        let is_orphan = |word: &'a str| {
+            orphans.iter().any(|orphan| match self.options.orphan_words_ignore_case {
+                true => orphan.chars().flat_map(char::to_lowercase).eq(lowercase(word)),
+                false => orphan == word,
+            })
+        };
+
+        while fit_result.n_words > 1 && fit_result.last_word().is_some_and(is_orphan) {
+            self.drop_last_word(fit_result);
+        }
+    }
+
+    /// Drops the last word of `fit_result` when it would leave the last
+    /// line of the paragraph with a single word, but the line keeps two.
+    fn drop_for_last_line(&self, fit_result: &mut FitResult<'a>) {
+        let mut rest = self.tokens.clone();
+        let (Some(last), None) = (rest.next(), rest.next()) else {
+            return;
+        };
+        let Some(word) = fit_result.last_word().filter(|_| fit_result.n_words > 2) else {
+            return;
+        };
+        if self.word(word).width + 1 + last.width <= self.line_width {
+            self.drop_last_word(fit_result);
+        }
+    }
+
+    fn drop_last_word(&self, fit_result: &mut FitResult<'a>) {
+        let text = fit_result.text;
+        let Some(word) = fit_result.last_word() else {
+            return;
+        };
+        let mut text = text[..text.len() - word.len()].trim_end();
+        // Tokens of hints alone before the word are no words either
+        let hint = hint::break_hint(self.options);
+        while let Some(token) = text.rsplit(char::is_whitespace).next() {
+            if token.is_empty() || !hint::is_only_hints(token, hint) {
+                break;
             }
+            text = text[..text.len() - token.len()].trim_end();
         }
+        fit_result.text = text;
+        fit_result.n_words -= 1;
+        fit_result.total_len -= self.word(word).width;
+        fit_result.total_bytes -= word.len();
     }
 
+    fn plan_line(&mut self) -> Option<LinePlan<'a>> {
+        if self.unfitted.width > self.width() {
+            let (part, rest) = match self.split(self.unfitted, self.width()) {
+                // Part without width would never leave the rest behind
+                (part, _) if part.width == 0 => (self.unfitted, Word::new("")),
+                split => split,
+            };
+            self.unfitted = rest;
+
+            // Part takes the whole line, so there is nothing to pad
+            let policy = LinePolicy::new(self.options, false);
+            return Some(LinePlan::new(&FitResult::word(part), self.width(), policy));
+        }
 
+        if self.unfitted.text.is_empty() {
+            if let Some(line) = self.verbatim.take() {
+                return Some(LinePlan::verbatim(line, self.line_width));
+            }
+
+            while self.tokens.peek().is_none() {
+                let Some(block) = self.paragraphs.next_block() else {
+                    if !self.options.blank_line_for_empty || self.has_lines {
+                        return None;
+                    }
+
+                    // Input without words still gives a line, padded
+                    // like a last line
+                    self.has_lines = true;
+                    let mut blank = LinePlan::empty(self.width());
+                    if self.options.pad_last_line {
+                        blank.trailing = self.width();
+                    }
+                    return Some(blank);
+                };
+                let separated = self.has_lines && block.separated;
+                self.paragraph_start = true;
+
+                if block.verbatim {
+                    self.has_lines = true;
+                    if separated {
+                        self.verbatim = Some(block.text);
+                        return Some(LinePlan::empty(self.line_width));
+                    }
+                    return Some(LinePlan::verbatim(block.text, self.line_width));
+                }
+
+                self.tokens = match self.prepared.take() {
+                    Some(words) => Measured::Prepared(words.iter().copied()).peekable(),
+                    None => words(block.text, self.measure, self.options),
+                };
+                // Empty line between paragraphs
+                if separated && self.tokens.peek().is_some() {
+                    return Some(LinePlan::empty(self.line_width));
+                }
+            }
+            self.has_lines = true;
+        }
+
+        // Last part of a split word starts the line like any other word,
+        // so adjusting the output again splits the word the same way
+        let line_width = self.width();
+        let last_part = core::mem::replace(&mut self.unfitted, Word::new(""));
+        let look_back = self.options.orphan_words.is_some() || self.options.avoid_orphan_last_line;
+        let tokens = look_back.then(|| self.tokens.clone());
+        let mut fit_result = fit_strs(last_part, &mut self.tokens, line_width, self.input);
+
+        if fit_result.n_words == 0 {
+            let word = *self
+                .tokens
+                .peek()
+                .expect("Value is already peeked, but results in None");
+
+            // Word that only fits lines after the shorter first one
+            // starts on the second line
+            if word.width <= self.line_width {
+                let blank = FitResult::word(Word {
+                    text: &word.text[..0],
+                    width: 0,
+                });
+                let policy = LinePolicy::new(self.options, false);
+                return Some(LinePlan::new(&blank, line_width, policy));
+            }
+
+            // Case when even single word does not fit to required line length.
+            // We should at least split it manually.
+            // Taking it out of tokens prevents stucking on large unconsumed word
+            self.unfitted = word;
+            self.tokens.next();
+
+            return self.plan_line();
+        }
+
+        if let Some(tokens) = tokens.filter(|_| self.tokens.peek().is_some()) {
+            let n_words = fit_result.n_words;
+            self.drop_orphans(&mut fit_result);
+            if self.options.avoid_orphan_last_line && fit_result.n_words == n_words {
+                self.drop_for_last_line(&mut fit_result);
+            }
+            if fit_result.n_words < n_words {
+                // Dropped words are taken again for the next line
+                self.tokens = tokens;
+                let n_taken = fit_result.n_words - usize::from(!last_part.text.is_empty());
+                for _ in 0..n_taken {
+                    self.tokens.next();
+                }
+            }
+        }
+
+        self.fill_with_part(&mut fit_result, line_width);
+        let is_last = self.tokens.peek().is_none() && self.unfitted.text.is_empty();
+        let mut policy = LinePolicy::new(self.options, is_last);
+        policy.hanging = self.hanging(&fit_result, policy.align);
+        Some(LinePlan::new(&fit_result, line_width, policy))
+    }
+
+    /// Adds the first part of the next word to `fit_result`, when the word
+    /// is split anyway and the part it would start the next line with,
+    /// e.g. one that ends after a dash, fits the rest of the line. The rest
+    /// of the word starts the next line, so adjusting the output again
+    /// gives the same lines.
+    fn fill_with_part(&mut self, fit_result: &mut FitResult<'a>, line_width: usize) {
+        let Some(&word) = self.tokens.peek().filter(|word| word.width > self.line_width) else {
+            return;
+        };
+        let room = line_width.saturating_sub(fit_result.total_len + fit_result.n_words);
+        let (part, rest) = self.split(word, self.line_width);
+        if part.width > room {
+            return;
+        }
+
+        self.tokens.next();
+        self.unfitted = rest;
+        let start = fit_result.text.as_ptr() as usize - self.input.as_ptr() as usize;
+        let end = part.text.as_ptr() as usize - self.input.as_ptr() as usize + part.text.len();
+        fit_result.text = &self.input[start..end];
+        fit_result.n_words += 1;
+        fit_result.total_len += part.width;
+        fit_result.total_bytes += part.text.len();
+    }
+
+    /// Width of the char at the end of `fit_result` that hangs past the
+    /// line with `hang_punctuation`, 0 when nothing hangs.
+    fn hanging(&self, fit_result: &FitResult<'a>, align: Alignment) -> usize {
+        let at_edge = match align {
+            Alignment::Justify => fit_result.n_words > 1,
+            Alignment::Right => true,
+            Alignment::Left | Alignment::Center => false,
+        };
+        if !self.options.hang_punctuation || !at_edge {
+            return 0;
+        }
+        match fit_result.text.char_indices().next_back() {
+            Some((idx, chr)) if self.options.hanging_punctuation.contains(&chr) => {
+                self.word(&fit_result.text[idx..]).width
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// Layout of a single output line, computed before it is written.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LinePlan<'a> {
+    /// Words of the line, with any whitespace between them
+    text: &'a str,
+    n_words: usize,
+    /// Length of the words in bytes, without whitespace
+    words_bytes: usize,
+    /// Width the line is laid out for, in chars
+    line_width: usize,
+    leading: usize,
+    /// Width of every gap except the last one
+    gap_widths_base: usize,
+    /// Width of the last gap, which takes what is left after the others
+    remainder: usize,
+    trailing: usize,
+    /// Left out of the words when they are written, tokens of it alone
+    /// in `text` are not words of the line
+    break_hint: Option<&'a str>,
+    /// Length of the break hints in the words
+    hint_bytes: usize,
+}
+
+impl<'a> LinePlan<'a> {
+    /// Lays out words of `fit_result` according to `policy`.
+    pub(crate) fn new(
+        fit_result: &FitResult<'a>,
+        line_width: usize,
+        policy: LinePolicy<'a>,
+    ) -> Self {
+        let n_gaps = fit_result.n_words.saturating_sub(1);
+        let total_len = fit_result.total_len - policy.hanging;
+        let mut plan = LinePlan {
+            text: fit_result.text,
+            n_words: fit_result.n_words,
+            words_bytes: fit_result.total_bytes,
+            line_width,
+            leading: 0,
+            gap_widths_base: 1,
+            remainder: 1,
+            trailing: 0,
+            break_hint: policy.break_hint,
+            hint_bytes: 0,
+        };
+        if let Some(hint) = policy.break_hint {
+            plan.hint_bytes = tokens(plan.text)
+                .filter(|token| !hint::is_only_hints(token, Some(hint)))
+                .map(|word| word.matches(hint).count() * hint.len())
+                .sum();
+        }
+
+        if policy.align == Alignment::Justify && n_gaps > 0 {
+            let gaps_info = gaps(fit_result.n_words, total_len, line_width);
+            plan.gap_widths_base = gaps_info.body_gaps_size;
+            plan.remainder = gaps_info.tail_gap_size;
+            return plan;
+        }
+
+        // Words are separated by single spaces, a single justified word is
+        // followed by padding
+        let free_space = line_width.saturating_sub(total_len + n_gaps);
+        plan.leading = match policy.align {
+            Alignment::Right => free_space,
+            Alignment::Center => free_space / 2,
+            Alignment::Left | Alignment::Justify => 0,
+        };
+        if policy.trailing_padding {
+            plan.trailing = free_space - plan.leading;
+        }
+        plan
+    }
+
+    /// Line without words, e.g. between paragraphs.
+    fn empty(line_width: usize) -> Self {
+        LinePlan {
+            text: "",
+            n_words: 0,
+            words_bytes: 0,
+            line_width,
+            leading: 0,
+            gap_widths_base: 0,
+            remainder: 0,
+            trailing: 0,
+            break_hint: None,
+            hint_bytes: 0,
+        }
+    }
+
+    /// Line copied from the input as it is, whatever its width.
+    fn verbatim(line: &'a str, line_width: usize) -> Self {
+        LinePlan {
+            text: line,
+            n_words: 1,
+            words_bytes: line.len(),
+            ..LinePlan::empty(line_width)
+        }
+    }
+
+    /// Length of the line in bytes.
+    pub(crate) fn len(&self) -> usize {
+        let gaps = match self.n_words {
+            0 | 1 => 0,
+            n_words => self.gap_widths_base * (n_words - 2) + self.remainder,
+        };
+        self.leading + self.words_bytes - self.hint_bytes + gaps + self.trailing
+    }
+
+    /// Spaces added to fill the line, i.e. all spaces except a single
+    /// one between adjacent words.
+    pub(crate) fn padding(&self) -> usize {
+        let gaps = match self.n_words {
+            0 | 1 => 0,
+            n_words => self.gap_widths_base * (n_words - 2) + self.remainder - (n_words - 1),
+        };
+        self.leading + gaps + self.trailing
+    }
+
+    /// Appends the line, without line ending, to `result`.
+    pub(crate) fn push(&self, result: &mut String) {
+        self.for_each_part(|part| match part {
+            LinePart::Spaces(count) => push_spaces(result, count),
+            LinePart::Word(word) => match self.break_hint {
+                Some(hint) => result.extend(word.split(hint)),
+                None => *result += word,
+            },
+        });
+    }
+
+    /// Calls `f` with every part of the line in order.
+    pub(crate) fn for_each_part(&self, mut f: impl FnMut(LinePart<'a>)) {
+        f(LinePart::Spaces(self.leading));
+        if self.n_words <= 1 {
+            f(LinePart::Word(self.text));
+        } else if self.text.len() == self.words_bytes + self.n_words - 1 {
+            // Every separator is a single ASCII whitespace byte, which is
+            // cheaper to look for than whitespace chars
+            let mut rest = self.text;
+            let words = core::iter::from_fn(|| {
+                if rest.is_empty() {
+                    return None;
+                }
+                let end = rest
+                    .bytes()
+                    .position(|byte| matches!(byte, b'\t'..=b'\r' | b' '))
+                    .unwrap_or(rest.len());
+                let word = &rest[..end];
+                rest = rest.get(end + 1..).unwrap_or("");
+                Some(word)
+            });
+            self.for_each_gapped(words, &mut f);
+        } else {
+            let words = tokens(self.text);
+            let words = words.filter(|token| !hint::is_only_hints(token, self.break_hint));
+            self.for_each_gapped(words, &mut f);
+        }
+        f(LinePart::Spaces(self.trailing));
+    }
+
+    fn for_each_gapped(
+        &self,
+        words: impl Iterator<Item = &'a str>,
+        f: &mut impl FnMut(LinePart<'a>),
+    ) {
+        let n_gaps = self.n_words - 1;
+        for (idx, word) in words.enumerate() {
+            if idx > 0 {
+                // Remainder goes to the last gap
+                let gap = if idx == n_gaps {
+                    self.remainder
+                } else {
+                    self.gap_widths_base
+                };
+                f(LinePart::Spaces(gap));
+            }
+            f(LinePart::Word(word));
+        }
+    }
+}
+
+/// Piece of an output line.
+pub(crate) enum LinePart<'a> {
+    Spaces(usize),
+    /// Word or a part of a split word, borrowed from the input
+    Word(&'a str),
+}
+
+/// Appends `count` spaces without building a temporary string.
+fn push_spaces(result: &mut String, count: usize) {
+    result.extend(core::iter::repeat_n(SPACE_STR, count));
+}
+
+/// Paragraphs of the input separated by whitespace-only lines, found
+/// one at a time.
+struct Paragraphs<'a> {
+    /// Text after the last returned paragraph
+    rest: &'a str,
+    /// When `false`, the whole text is a single paragraph
+    split: bool,
+    /// Lines copied as they are, when splitting
+    skip_line: Option<&'a LineFilter>,
+}
+
+/// Paragraph, or a line matched by `skip_line` without its line break.
+struct Block<'a> {
+    text: &'a str,
+    verbatim: bool,
+    /// Whether a whitespace-only line comes before the block
+    separated: bool,
+}
+
+impl<'a> Paragraphs<'a> {
+    fn new(input: &'a str, split: bool) -> Self {
+        Paragraphs {
+            rest: input,
+            split,
+            skip_line: None,
+        }
+    }
+
+    fn with_skip_line(mut self, skip_line: Option<&'a LineFilter>) -> Self {
+        self.skip_line = skip_line;
+        self
+    }
+
+    fn next_block(&mut self) -> Option<Block<'a>> {
+        if !self.split {
+            let text = core::mem::take(&mut self.rest);
+            return (!text.is_empty()).then_some(Block {
+                text,
+                verbatim: false,
+                separated: false,
+            });
+        }
+
+        let mut start = None;
+        let mut separated = false;
+        let mut offset = 0;
+        for line in self.rest.split_inclusive('\n') {
+            let text = line.strip_suffix('\n').unwrap_or(line);
+            let text = text.strip_suffix('\r').unwrap_or(text);
+            let skipped = self.skip_line.is_some_and(|filter| filter.matches(text));
+
+            if skipped || line.trim().is_empty() {
+                if let Some(start) = start {
+                    let paragraph = &self.rest[start..offset];
+                    self.rest = &self.rest[offset..];
+                    return Some(Block {
+                        text: paragraph,
+                        verbatim: false,
+                        separated,
+                    });
+                }
+                if skipped {
+                    self.rest = &self.rest[offset + line.len()..];
+                    return Some(Block {
+                        text,
+                        verbatim: true,
+                        separated,
+                    });
+                }
+                separated = true;
+            } else if start.is_none() {
+                start = Some(offset);
+            }
+            offset += line.len();
+        }
+
+        let rest = core::mem::take(&mut self.rest);
+        start.map(|start| Block {
+            text: &rest[start..],
+            verbatim: false,
+            separated,
+        })
+    }
+}
+
+impl<'a> Iterator for Paragraphs<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        Some(self.next_block()?.text)
+    }
+}
+
+/// How a single output line is laid out.
+#[derive(Clone, Copy)]
+pub(crate) struct LinePolicy<'a> {
+    align: Alignment,
+    trailing_padding: bool,
+    /// Width at the end of the line that goes past it
+    hanging: usize,
+    /// `break_hint` of the options, tokens of which alone are left out
+    break_hint: Option<&'a str>,
+}
+
+impl<'a> LinePolicy<'a> {
+    pub(crate) fn new(options: &'a TransformOptions, is_last: bool) -> Self {
+        if is_last && !options.pad_last_line {
+            let align = match options.align {
+                Alignment::Justify => Alignment::Left,
+                align => align,
+            };
+            LinePolicy {
+                align,
+                trailing_padding: false,
+                hanging: 0,
+                break_hint: hint::break_hint(options),
+            }
+        } else {
+            LinePolicy {
+                align: options.align,
+                trailing_padding: true,
+                hanging: 0,
+                break_hint: hint::break_hint(options),
+            }
+        }
+    }
+}
+
+fn paginate(text: &str, lines_per_page: usize) -> Vec<String> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+
+    let n_lines = text.bytes().filter(|&byte| byte == b'\n').count() + 1;
+    let mut lines = Vec::with_capacity(n_lines);
+    lines.extend(text.split(NEWLINE_STR));
+    lines
+        .chunks(lines_per_page.max(1))
+        .map(|page| page.join(NEWLINE_STR))
+        .collect()
+}
+
+fn number_lines(
+    input: &str,
+    options: &TransformOptions,
+    style: &NumberStyle,
+    adjust: AdjustFn,
+    limit: Option<usize>,
+) -> Result<String, TransformError> {
+    let line_width = options.line_width as usize;
+    let separator_len = style.separator.chars().count();
+
+    // Gutter width depends on the line count, which in turn depends on
+    // the width left for text. Widen the gutter until the largest number fits.
+    let mut digits = count_digits(style.start);
+    let body = loop {
+        let text_width = line_width.saturating_sub(digits + separator_len).max(1);
+        let text_options = without_gutter(options, digits + separator_len);
+        let body = adjust(input, &text_options, text_width, limit)?;
+
+        let n_numbered = numbered_lines(&body, style)
+            .iter()
+            .filter(|(_, numbered)| *numbered)
+            .count();
+        let required = count_digits(style.start + n_numbered.saturating_sub(1));
+        if required <= digits {
+            break body;
+        }
+        digits = required;
+    };
+
+    let lines = numbered_lines(&body, style);
+    let gutter_len = digits + style.separator.len();
+    let mut result = String::with_capacity(body.len() + lines.len() * gutter_len);
+    let mut number = style.start;
+    for (idx, (line, numbered)) in lines.into_iter().enumerate() {
+        if idx > 0 {
+            result += NEWLINE_STR;
+        }
+
+        // Blank lines between paragraphs stay blank
+        if line.is_empty() {
+            continue;
+        }
+
+        if numbered {
+            result += &format!("{number:>digits$}");
+            number += 1;
+        } else {
+            push_spaces(&mut result, digits);
+        }
+        result += &style.separator;
+        result += line;
+    }
+
+    Ok(result)
+}
+
+/// Options for text next to a gutter `gutter_width` chars wide, which
+/// takes the same part of the first line as of any other.
+fn without_gutter(options: &TransformOptions, gutter_width: usize) -> Cow<'_, TransformOptions> {
+    match options.initial_width {
+        Some(initial_width) => {
+            let mut text_options = options.clone();
+            let text_width = (initial_width as usize).saturating_sub(gutter_width).max(1);
+            text_options.initial_width = Some(text_width as u32);
+            Cow::Owned(text_options)
+        }
+        None => Cow::Borrowed(options),
+    }
+}
+
+/// Pairs every line of `body` with whether it gets a number.
+fn numbered_lines<'a>(body: &'a str, style: &NumberStyle) -> Vec<(&'a str, bool)> {
+    let n_lines = body.bytes().filter(|&byte| byte == b'\n').count() + 1;
+    let mut lines = Vec::with_capacity(n_lines);
+
+    let mut prev_empty = true;
+    for line in body.split(NEWLINE_STR) {
+        let numbered = !line.is_empty() && (style.number_continuation_lines || prev_empty);
+        prev_empty = line.is_empty();
+        lines.push((line, numbered));
+    }
+    lines
+}
+
+fn count_digits(mut number: usize) -> usize {
+    let mut digits = 1;
+    while number >= 10 {
+        number /= 10;
+        digits += 1;
+    }
+    digits
+}
+
+/// Whitespace-separated token together with its width in chars.
+///
+/// The width is measured once, when the token is split off the input.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Word<'a> {
+    pub(crate) text: &'a str,
+    pub(crate) width: usize,
+}
+
+impl<'a> Word<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        Word {
+            text,
+            width: text.chars().count(),
+        }
+    }
+
+    /// Same as [`Word::new`] for text known to be ASCII, where every
+    /// byte is a char.
+    fn ascii(text: &'a str) -> Self {
+        Word {
+            text,
+            width: text.len(),
+        }
+    }
+
+    fn is_ascii(&self) -> bool {
+        self.width == self.text.len()
+    }
+}
+
+type Words<'a> = Peekable<Measured<'a>>;
+
+fn words<'a>(
+    text: &'a str,
+    measure: fn(&'a str) -> Word<'a>,
+    options: &'a TransformOptions,
+) -> Words<'a> {
+    Measured::Tokens {
+        tokens: tokens(text),
+        measure,
+        options,
+    }
+    .peekable()
+}
+
+#[derive(Clone)]
+enum Measured<'a> {
+    /// Tokens measured by `measure`, without the width of their markers
+    /// and break hints. Tokens of break hints alone are left out
+    Tokens {
+        tokens: Tokens<'a>,
+        measure: fn(&'a str) -> Word<'a>,
+        options: &'a TransformOptions,
+    },
+    /// Words measured up front
+    Prepared(core::iter::Copied<core::slice::Iter<'a, Word<'a>>>),
+}
+
+impl<'a> Iterator for Measured<'a> {
+    type Item = Word<'a>;
+
+    fn next(&mut self) -> Option<Word<'a>> {
+        match self {
+            Measured::Tokens {
+                tokens,
+                measure,
+                options,
+            } => {
+                let hint = hint::break_hint(options);
+                let token = tokens.find(|token| !hint::is_only_hints(token, hint))?;
+                Some(measure_marked(token, *measure, options))
+            }
+            Measured::Prepared(words) => words.next(),
+        }
+    }
+}
+
+fn measure_marked<'a>(
+    text: &'a str,
+    measure: fn(&'a str) -> Word<'a>,
+    options: &TransformOptions,
+) -> Word<'a> {
+    let mut word = measure(text);
+    if let Some(markup) = &options.markup {
+        word.width -= markup.markers_width(text);
+    }
+    if let Some(hint) = hint::break_hint(options) {
+        word.width -= text.matches(hint).map(|hint| measure(hint).width).sum::<usize>();
+    }
+    word
+}
+
+/// Picks the cheapest way to measure words of `input`.
+fn measure(input: &str) -> for<'a> fn(&'a str) -> Word<'a> {
+    // Closures work for any lifetime of the text, unlike the methods
+    if input.is_ascii() {
+        |text| Word::ascii(text)
+    } else {
+        |text| Word::new(text)
+    }
+}
+
+/// Splits `unfitted` like [`split_manually`], but markers of `markup`
+/// and `break_hint` take no width, and the part ends after the last
+/// `break_hint` in it, or else after the last of `break_after_chars` or
+/// [`DASHES`] in it, if any. The part never ends with
+/// [`NO_BREAK_HYPHEN`] or right before [`WORD_JOINER`], unless it
+/// cannot end anywhere else. Parts are measured by `measure`.
+pub(crate) fn split_word<'a>(
+    unfitted: Word<'a>,
+    line_width: usize,
+    options: &TransformOptions,
+    measure: fn(&'a str) -> Word<'a>,
+) -> (Word<'a>, Word<'a>) {
+    let (part, rest) = split_chars(unfitted, line_width, options);
+    if let Some(end) = break_end(unfitted, part, options) {
+        return split_at(unfitted, end, options, measure);
+    }
+
+    let is_breakable = |end: usize| is_breakable(unfitted.text, end);
+    if is_breakable(part.text.len()) {
+        return (part, rest);
+    }
+    // Step back to the nearest place a word may be split at
+    let ends = char_ends(part.text, options.markup.as_ref(), hint::break_hint(options));
+    let end = ends
+        .map(|(end, _)| end)
+        .filter(|&end| end < part.text.len() && is_breakable(end))
+        .last();
+    match end {
+        Some(end) => split_at(unfitted, end, options, measure),
+        None => (part, rest),
+    }
+}
+
+/// Splits `unfitted` after as many chars as fit `line_width`, in the
+/// units of `width_mode`, where markers of `markup` and `break_hint` take
+/// no width. With `break_hint`, the part ends after the last one in it.
+fn split_chars<'a>(
+    unfitted: Word<'a>,
+    line_width: usize,
+    options: &TransformOptions,
+) -> (Word<'a>, Word<'a>) {
+    let half_units = options.width_mode == WidthMode::HalfWidthUnits;
+    match (hint::break_hint(options), &options.markup) {
+        (Some(hint), markup) => {
+            hint::split(unfitted, line_width, hint, markup.as_ref(), half_units)
+        }
+        (None, Some(markup)) => markup.split(unfitted, line_width, half_units),
+        (None, None) if half_units => width::split_half_units(unfitted, line_width),
+        (None, None) => split_manually(unfitted, line_width),
+    }
+}
+
+/// End of `part` of `unfitted` when it ends after a `break_hint`, or else
+/// after the last of `break_after_chars` or [`DASHES`] in it, if any.
+fn break_end(unfitted: Word<'_>, part: Word<'_>, options: &TransformOptions) -> Option<usize> {
+    let hint = hint::break_hint(options);
+    if hint.is_some_and(|hint| part.text.ends_with(hint)) {
+        return Some(part.text.len());
+    }
+
+    let break_chars = &options.break_after_chars;
+    char_ends(part.text, options.markup.as_ref(), hint)
+        .filter(|&(end, chr)| {
+            (break_chars.contains(&chr) || DASHES.contains(&chr))
+                && is_breakable(unfitted.text, end)
+        })
+        .last()
+        .map(|(end, _)| end)
+}
+
+/// Whether `text` may be split at `end`, i.e. not after
+/// [`NO_BREAK_HYPHEN`] or before [`WORD_JOINER`].
+fn is_breakable(text: &str, end: usize) -> bool {
+    let (part, rest) = text.split_at(end);
+    !part.ends_with(NO_BREAK_HYPHEN) && !rest.starts_with(WORD_JOINER)
+}
+
+/// Splits `unfitted` at `end`, where close markers right after it stay
+/// with the part.
+fn split_at<'a>(
+    unfitted: Word<'a>,
+    mut end: usize,
+    options: &TransformOptions,
+    measure: fn(&'a str) -> Word<'a>,
+) -> (Word<'a>, Word<'a>) {
+    let markup = options.markup.as_ref();
+    let closing = |end: usize| markup.and_then(|markup| markup.marker_len(&unfitted.text[end..]));
+    while let Some((len, true)) = closing(end) {
+        end += len;
+    }
+    let (part, rest) = unfitted.text.split_at(end);
+    let part = measure_marked(part, measure, options);
+    let rest = Word {
+        text: rest,
+        width: unfitted.width - part.width,
+    };
+    (part, rest)
+}
+
+/// Ends of the chars of `text`, with the chars, leaving out markers of
+/// `markup` and `hint`, which are never split.
+fn char_ends<'t>(
+    text: &'t str,
+    markup: Option<&'t MarkupConfig>,
+    hint: Option<&'t str>,
+) -> impl Iterator<Item = (usize, char)> + 't {
+    let mut idx = 0;
+    core::iter::from_fn(move || loop {
+        let rest = &text[idx..];
+        let chr = rest.chars().next()?;
+        if let Some(hint) = hint.filter(|hint| rest.starts_with(hint)) {
+            idx += hint.len();
+            continue;
+        }
+        if let Some((len, _)) = markup.and_then(|markup| markup.marker_len(rest)) {
+            idx += len;
+            continue;
+        }
+        idx += chr.len_utf8();
+        return Some((idx, chr));
+    })
+}
+
+/// Splits `unfitted` into the first part that fits into a line and the rest.
+pub(crate) fn split_manually(unfitted: Word<'_>, line_width: usize) -> (Word<'_>, Word<'_>) {
+    assert!(line_width > 0, "line width must be greater than 0");
+
+    // line_width is upper limit for characters counting
+    let (part, rest) = if unfitted.is_ascii() {
+        unfitted.text.split_at(line_width.min(unfitted.text.len()))
+    } else {
+        match unfitted.text.char_indices().nth(line_width) {
+            Some((idx, _)) => unfitted.text.split_at(idx),
+            None => (unfitted.text, ""),
+        }
+    };
+    let part_width = unfitted.width.min(line_width);
+
+    let part = Word {
+        text: part,
+        width: part_width,
+    };
+    let rest = Word {
+        text: rest,
+        width: unfitted.width - part_width,
+    };
+    (part, rest)
+}
+
+/// Takes words that fit into a line from `tokens`, which are split off `input`.
+/// The line starts with `first`, which is either empty or known to fit.
+fn fit_strs<'a>(
+    first: Word<'a>,
+    tokens: &mut Words<'a>,
+    max_line_width: usize,
+    input: &'a str,
+) -> FitResult<'a> {
+    const ONE_SPACE: usize = 1;
+
+    let mut n_words = 0;
+    let mut total_len = 0;
+    let mut total_bytes = 0;
+    let mut chk_len = 0;
+    let mut span = None;
+
+    let mut first = Some(first).filter(|word| !word.text.is_empty());
+    while let Some(word) = first
+        .take()
+        .or_else(|| tokens.next_if(|word| chk_len + word.width <= max_line_width))
+    {
+        n_words += 1;
+        total_len += word.width;
+        total_bytes += word.text.len();
+
+        // Assuming there will space before next word
+        chk_len += word.width + ONE_SPACE;
+
+        let start = word.text.as_ptr() as usize - input.as_ptr() as usize;
+        let end = start + word.text.len();
+        span = Some(span.map_or((start, end), |(first, _)| (first, end)));
+    }
+
+    FitResult {
+        text: span.map_or("", |(start, end)| &input[start..end]),
+        n_words,
+        total_len,
+        total_bytes,
+    }
+}
+
+/// Words that fit into a line.
+pub(crate) struct FitResult<'a> {
+    /// Text from the start of the first word to the end of the last one
+    pub(crate) text: &'a str,
+    pub(crate) n_words: usize,
+    /// Width of the words in chars
+    pub(crate) total_len: usize,
+    /// Length of the words in bytes
+    pub(crate) total_bytes: usize,
+}
+
+impl<'a> FitResult<'a> {
+    fn last_word(&self) -> Option<&'a str> {
+        self.text.split_whitespace().next_back()
+    }
+
+    pub(crate) fn word(word: Word<'a>) -> Self {
+        FitResult {
+            text: word.text,
+            n_words: usize::from(!word.text.is_empty()),
+            total_len: word.width,
+            total_bytes: word.text.len(),
+        }
+    }
+}
+
+fn gaps(n_tokens: usize, total_len: usize, line_width: usize) -> GapInfo {
+    if n_tokens == 0 {
+        return GapInfo {
+            body_gaps_size: 0,
+            tail_gap_size: 0,
+        };
+    } else if n_tokens == 1 {
+        return GapInfo {
+            body_gaps_size: 0,
+            tail_gap_size: line_width - total_len,
+        };
+    }
+
+    let n_gaps = n_tokens - 1;
+    let free_space = line_width - total_len;
+    let remainder = free_space % (n_gaps);
+
+    let div = if n_gaps > 1 && remainder > 0 {
+        n_gaps - 1
+    } else {
+        n_gaps
+    };
+
+    let max_gap = (free_space - remainder) / div;
+
+    // Whatever the body gaps leave goes to the last one
+    let last_gap = free_space - max_gap * (n_gaps - 1);
+
+    GapInfo {
+        body_gaps_size: max_gap,
+        tail_gap_size: last_gap,
+    }
+}
+
+struct GapInfo {
+    body_gaps_size: usize,
+    tail_gap_size: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use crate::verify::verify_invariants;
+
+    use super::{
+        adjust, break_offsets, fits, fits_splitting, line_count, line_count_with_options, minimum_width,
+        minimum_width_with, shrink_to_fit, transform, transform_cow, transform_pages,
+        transform_shaped, transform_shaped_with, transform_shrunk, transform_with_options,
+        try_transform, two_part_line, wrap_columns,
+        try_transform_shaped, try_transform_with_options,
+        width_for_lines, within_lines, Alignment, BlockAlignment, LineEnding, LineFilter, Lines, NumberStyle, TransformError,
+        TransformOptions, VerticalAlignment, Word,
+    };
+
+    #[test]
+    fn split_test() {
+        let test_cases = [
+            ("consectetur", 4, "cons\necte\ntur "),
+            ("Привет", 12, "Привет      "),
+            ("Поддержка кодировки utf-8 в коде", 8, "Поддержк\nа       \nкодировк\nи  utf-8\nв   коде"),
+            ("Съешь ещё этих мягких французских булок, да выпей чаю", 12, "Съешь    ещё\nэтих  мягких\nфранцузских \nбулок,    да\nвыпей    чаю"),
+            ("🤩 привет  💨 hello", 1, "🤩\nп\nр\nи\nв\nе\nт\n💨\nh\ne\nl\nl\no"),
+            ("🤩 привет  💨 hello", 3, "🤩  \nпри\nвет\n💨  \nhel\nlo "),
+        ];
+
+        for &(input, line_width, expected) in &test_cases {
+            println!("input: '{}'", input);
+            assert_eq!(transform(input, line_width), expected);
+        }
+    }
+
+    #[test]
+    fn equal_length_lines() {
+        let test_cases = [
+            ("Бык тупогуб, тупогубенький бычок, у быка губа тупа.", 5),
+            ("Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.", 18),
+            ("Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.", 6),
+            ("Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.", 1),
+            ("Тpидцaть тpи коpaбля лaвиpовaли, лaвиpовaли, лавировали, дa не \tвылaвиpовaли.", 4),
+            ("У переп\tелa и перепелки\t\t\t пять  \t\tперепелят    .", 3),
+        ];
+
+        for (input, line_width) in test_cases {
+            let result = transform(input, line_width);
+            println!("input: '{}'", input);
+            for line in result.lines() {
+                assert_eq!(line.chars().count() as u32, line_width);
+            }
+
+            let options = TransformOptions::new(line_width);
+            assert_eq!(verify_invariants(input, &options, &result), Ok(()));
+        }
+    }
+
+    #[test]
+    fn large_input() {
+        let words = ["Съешь", "ещё", "этих", "мягких", "французских", "булок,", "да", "выпей", "чаю", "a"];
+        let input = (0..100_000).map(|idx| words[idx * 7 % words.len()]).collect::<Vec<_>>().join(" ");
+
+        for line_width in [11, 40, 80] {
+            let result = transform(&input, line_width);
+            for line in result.lines() {
+                assert_eq!(line.chars().count() as u32, line_width);
+            }
+            assert!(result.split_whitespace().eq(input.split_whitespace()));
+        }
+    }
+
+    #[test]
+    fn preallocated_output() {
+        let words = ["Съешь", "ещё", "этих", "мягких", "французских", "булок,", "да", "выпей", "чаю", "a"];
+        let mut input = String::new();
+        for idx in 0.. {
+            if input.len() >= 1 << 20 {
+                break;
+            }
+            input += words[idx * 7 % words.len()];
+            input += " ";
+        }
+
+        for line_width in [5, 11, 80] {
+            let (result, counts) = crate::alloc_counter::count(|| transform(&input, line_width));
+            let reallocs = counts.large_reallocs;
+            assert!(reallocs <= 1, "{reallocs} reallocations for width {line_width}");
+
+            let mut options = TransformOptions::new(line_width);
+            options.number_lines = Some(NumberStyle::default());
+            let (_, counts) = crate::alloc_counter::count(|| transform_with_options(&input, &options));
+            let reallocs = counts.large_reallocs;
+            // Body is adjusted once per tried gutter width
+            assert!(reallocs <= 2, "{reallocs} reallocations for numbered width {line_width}");
+
+            let (pages, counts) = crate::alloc_counter::count(|| transform_pages(&input, line_width, 60));
+            let reallocs = counts.large_reallocs;
+            assert!(reallocs <= 1, "{reallocs} reallocations for pages of width {line_width}");
+            assert_eq!(pages.concat().len() + pages.len() - 1, result.len());
+        }
+    }
+
+    #[test]
+    fn padding_does_not_allocate() {
+        let input = "a bb c ".repeat(2_000);
+        for align in [Alignment::Justify, Alignment::Center] {
+            let mut options = TransformOptions::new(40_000);
+            options.align = align;
+            let (_, counts) = crate::alloc_counter::count(|| transform_with_options(&input, &options));
+            // Output, paragraph and word lists, growth of the word list
+            assert!(counts.allocs <= 4, "{} allocations for {align:?}", counts.allocs);
+        }
+    }
+
+    #[test]
+    fn ascii_fast_path() {
+        fn adjust_measured(input: &str, options: &TransformOptions, ascii: bool) -> Vec<String> {
+            let measure = if ascii { Word::ascii } else { Word::new };
+            let mut lines = Lines::with_measure(input, options, options.line_width as usize, measure);
+            let mut result = Vec::new();
+            let mut line = String::new();
+            while lines.next_line(&mut line) {
+                result.push(core::mem::take(&mut line));
+            }
+            result
+        }
+
+        const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz,.!?  \t\n\n\x0b";
+        let mut state = 7u32;
+        for case in 0..300 {
+            // Tiny linear congruential generator for input chars
+            let mut next = || {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as usize
+            };
+            let len = next() % 120;
+            let input: String = (0..len).map(|_| ALPHABET[next() % ALPHABET.len()] as char).collect();
+
+            let mut options = TransformOptions::new(1 + case % 17);
+            options.align = [Alignment::Left, Alignment::Right, Alignment::Center, Alignment::Justify][case as usize % 4];
+            options.pad_last_line = case % 3 != 0;
+            options.preserve_paragraphs = case % 5 == 0;
+
+            assert!(input.is_ascii());
+            assert_eq!(adjust_measured(&input, &options, true), adjust_measured(&input, &options, false), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn cow() {
+        let mut options = TransformOptions::new(11);
+        let cases = [
+            ("", true),
+            ("hello world", true),
+            ("hello", false),
+            ("hello world ", false),
+            (" hello world", false),
+            ("hello  world", false),
+            ("hello\tworld", false),
+            ("hello world!", false),
+        ];
+        for (input, borrowed) in cases {
+            let result = transform_cow(input, &options);
+            assert_eq!(matches!(result, Cow::Borrowed(_)), borrowed, "{input:?}");
+            assert_eq!(result, transform_with_options(input, &options));
+        }
+
+        options.pad_last_line = false;
+        let cases = [
+            ("hello", Alignment::Left, true),
+            ("hello big", Alignment::Justify, true),
+            ("hello ", Alignment::Left, false),
+            ("hello", Alignment::Right, false),
+            ("hello", Alignment::Center, false),
+            ("hello world", Alignment::Center, true),
+            ("hello wide world", Alignment::Left, false),
+        ];
+        for (input, align, borrowed) in cases {
+            options.align = align;
+            let result = transform_cow(input, &options);
+            assert_eq!(matches!(result, Cow::Borrowed(_)), borrowed, "{input:?} {align:?}");
+            assert_eq!(result, transform_with_options(input, &options));
+        }
+
+        options.number_lines = Some(NumberStyle::default());
+        assert!(matches!(transform_cow("hello", &options), Cow::Owned(_)));
+    }
+
+    #[test]
+    fn justified_gaps_fill_line() {
+        // Whatever the body gaps leave goes to the last gap
+        assert_eq!(transform("ab cd ef gh", 18), "ab    cd    ef  gh");
+        assert_eq!(transform("ab cd ef gh ij", 21), "ab  cd  ef  gh     ij");
+    }
+
+    #[test]
+    fn planned_size() {
+        let input = "Вез корабль карамель, наскочил корабль на мель,\n\nматросы две недели \
+                     карамель на мели ели. Тридцатитрёхбуквенный 🤩 hello\n \n";
+        for align in [Alignment::Left, Alignment::Right, Alignment::Center, Alignment::Justify] {
+            for line_width in [1, 3, 7, 12, 100] {
+                let mut options = TransformOptions::new(line_width);
+                options.align = align;
+                options.preserve_paragraphs = line_width % 2 == 1;
+                options.pad_last_line = line_width > 5;
+
+                let mut lines = Lines::new(input, &options, line_width as usize);
+                while let Some(plan) = lines.next_plan() {
+                    let mut line = String::new();
+                    plan.push(&mut line);
+                    assert_eq!(line.len(), plan.len(), "{line:?}");
+                }
+
+                let result = adjust(input, &options, line_width as usize, None).unwrap();
+                assert_eq!(result.capacity(), result.len());
+            }
+        }
+    }
+
+    #[test]
+    fn numbered_lines() {
+        let input = "one two three four five six seven eight nine ten eleven twelve";
+        let mut options = TransformOptions::new(11);
+        options.number_lines = Some(NumberStyle::default());
+
+        let result = transform_with_options(input, &options);
+        let lines: Vec<&str> = result.lines().collect();
+
+        assert_eq!(lines.len(), 12);
+        assert_eq!(lines[0], " 1 │ one   ");
+        assert_eq!(lines[8], " 9 │ nine  ");
+        assert_eq!(lines[9], "10 │ ten   ");
+        assert_eq!(lines[11], "12 │ twelve");
+        for line in lines {
+            assert_eq!(line.chars().count(), 11);
+        }
+    }
+
+    #[test]
+    fn numbered_first_line_only() {
+        let mut options = TransformOptions::new(10);
+        options.number_lines = Some(NumberStyle {
+            start: 7,
+            separator: String::from(": "),
+            number_continuation_lines: false,
+        });
+
+        let result = transform_with_options("wrapped paragraph text", &options);
+        assert_eq!(result, "7: wrapped\n : paragra\n : ph text");
+    }
+
+    #[test]
+    fn pages() {
+        let input = "Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.";
+        let expected = transform(input, 10);
+
+        let pages = transform_pages(input, 10, 3);
+        assert_eq!(expected.lines().count(), 11);
+        assert_eq!(pages.len(), 4);
+        for page in &pages[..3] {
+            assert_eq!(page.lines().count(), 3);
+        }
+        assert_eq!(pages[3].lines().count(), 2);
+        assert_eq!(pages.join("\n"), expected);
+
+        let mut options = TransformOptions::new(10);
+        options.lines_per_page = Some(3);
+        let paged = transform_with_options(input, &options);
+        assert_eq!(paged.matches('\u{0C}').count(), 3);
+        assert!(!paged.ends_with('\u{0C}'));
+        assert_eq!(paged.replace("\u{0C}\n", ""), expected);
+    }
+
+    #[test]
+    fn alignment() {
+        let input = "Съешь ещё этих мягких французских булок";
+        let test_cases = [
+            (Alignment::Left, "Съешь ещё   \nэтих мягких \nфранцузских \nбулок       "),
+            (Alignment::Right, "   Съешь ещё\n этих мягких\n французских\n       булок"),
+            (Alignment::Center, " Съешь ещё  \nэтих мягких \nфранцузских \n   булок    "),
+            (Alignment::Justify, "Съешь    ещё\nэтих  мягких\nфранцузских \nбулок       "),
+        ];
+
+        for (align, expected) in test_cases {
+            let mut options = TransformOptions::new(12);
+            options.align = align;
+            assert_eq!(transform_with_options(input, &options), expected);
+        }
+    }
+
+    #[test]
+    fn unpadded_last_line() {
+        let mut options = TransformOptions::new(12);
+        options.pad_last_line = false;
+        assert_eq!(
+            transform_with_options("Съешь ещё этих мягких булок, да", &options),
+            "Съешь    ещё\nэтих  мягких\nбулок, да"
+        );
+
+        options.line_width = 4;
+        assert_eq!(transform_with_options("consectetur", &options), "cons\necte\ntur");
+
+        options.line_width = 12;
+        options.align = Alignment::Right;
+        assert_eq!(transform_with_options("булок, да", &options), "   булок, да");
+    }
+
+    #[test]
+    fn paragraphs_and_line_endings() {
+        let input = "first paragraph\n  \t\nsecond\nparagraph\n\n\n";
+        let mut options = TransformOptions::new(10);
+        options.preserve_paragraphs = true;
+        assert_eq!(
+            transform_with_options(input, &options),
+            "first     \nparagraph \n\nsecond    \nparagraph "
+        );
+
+        options.line_ending = LineEnding::CrLf;
+        assert_eq!(
+            transform_with_options(input, &options),
+            "first     \r\nparagraph \r\n\r\nsecond    \r\nparagraph "
+        );
+
+        assert_eq!(transform(input, 10), "first     \nparagraph \nsecond    \nparagraph ");
+    }
+
+    #[test]
+    fn skipped_lines() {
+        let input = "Run the tests\nwith cargo:\n    $ cargo test\n    ok\nand read\n\nthe    \
+                     output  of\n    $ cargo  bench\n\n    done\n";
+        let mut options = TransformOptions::new(12);
+        options.preserve_paragraphs = true;
+        options.skip_line = Some(LineFilter::new(|line| line.starts_with("    ")));
+        assert_eq!(
+            transform_with_options(input, &options),
+            "Run      the\ntests   with\ncargo:      \n    $ cargo test\n    ok\nand     read\n\n\
+             the   output\nof          \n    $ cargo  bench\n\n    done"
+        );
+
+        // Lines keep their line breaks out of the filter
+        let expected = transform_with_options(input, &options).replace('\n', "\r\n");
+        options.line_ending = LineEnding::CrLf;
+        let crlf_input = input.replace('\n', "\r\n");
+        assert_eq!(transform_with_options(&crlf_input, &options), expected);
+
+        // Filter only applies to paragraphs
+        options.line_ending = LineEnding::Lf;
+        options.preserve_paragraphs = false;
+        assert_eq!(
+            transform_with_options(input, &options),
+            transform_with_options(input, &TransformOptions::new(12))
+        );
+    }
+
+    #[test]
+    fn orphan_words() {
+        let input = "Он гулял в лесу и к ночи вернулся. В лесу";
+        let mut options = TransformOptions::new(10);
+        assert_eq!(
+            transform_with_options(input, &options),
+            "Он гулял в\nлесу  и  к\nночи      \nвернулся. \nВ     лесу"
+        );
+
+        options.orphan_words = Some(vec![String::from("в"), String::from("и"), String::from("к")]);
+        let output = transform_with_options(input, &options);
+        assert_eq!(output, "Он   гулял\nв     лесу\nи  к  ночи\nвернулся. \nВ     лесу");
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        assert_eq!(line_count_with_options(input, &options), 5);
+
+        // Words in a row move together, the first word of a line stays
+        options.line_width = 11;
+        options.align = Alignment::Left;
+        assert_eq!(
+            transform_with_options("лесу и к ночи", &options),
+            "лесу       \nи к ночи   "
+        );
+        assert_eq!(transform_with_options("и к ночи", &options), "и к ночи   ");
+        options.line_width = 3;
+        assert_eq!(transform_with_options("и к ночи", &options), "и  \nк  \nноч\nи  ");
+
+        options.line_width = 9;
+        assert_eq!(
+            transform_with_options("вернулся. В лесу", &options),
+            "вернулся.\nВ лесу   "
+        );
+        options.line_width = 11;
+        assert_eq!(
+            transform_with_options("вернулся. В лесу", &options),
+            "вернулся. В\nлесу       "
+        );
+        options.orphan_words_ignore_case = true;
+        assert_eq!(
+            transform_with_options("вернулся. В лесу", &options),
+            "вернулся.  \nВ лесу     "
+        );
+
+        // Tokens of break hints alone before an orphan go with it
+        let mut options = TransformOptions::new(6);
+        options.break_hint = Some(String::from("%%"));
+        options.orphan_words = Some(vec![String::from("да")]);
+        for input in ["bb %% да cc", "bb\n\n%% да\ncc"] {
+            let output = transform_with_options(input, &options);
+            assert_eq!(output, "bb    \nда  cc");
+            assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+            assert_eq!(line_count_with_options(input, &options), 2);
+            let limited = TransformOptions {
+                max_output_bytes: Some(output.len()),
+                ..options.clone()
+            };
+            assert_eq!(try_transform_with_options(input, &limited), Ok(output));
+        }
+    }
+
+    #[test]
+    fn orphan_last_line() {
+        let input = "Съешь же ещё этих мягких французских булок, да выпей чаю";
+        let mut options = TransformOptions::new(15);
+        assert_eq!(
+            transform_with_options(input, &options),
+            "Съешь    же ещё\nэтих     мягких\nфранцузских    \nбулок, да выпей\nчаю            "
+        );
+
+        options.avoid_orphan_last_line = true;
+        let output = transform_with_options(input, &options);
+        assert_eq!(
+            output,
+            "Съешь    же ещё\nэтих     мягких\nфранцузских    \nбулок,       да\nвыпей       чаю"
+        );
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        assert_eq!(line_count_with_options(input, &options), 5);
+
+        // Line before would be left with a single word, or has one already
+        for (input, line_width) in [("да выпей чаю", 9), ("французских чаю", 12)] {
+            options.line_width = line_width;
+            let mut plain = options.clone();
+            plain.avoid_orphan_last_line = false;
+            assert_eq!(
+                transform_with_options(input, &options),
+                transform_with_options(input, &plain)
+            );
+        }
+    }
+
+    #[test]
+    fn break_after_chars() {
+        let input = "call very_long_function_name_with_many_parts now";
+        let mut options = TransformOptions::new(12);
+        options.break_after_chars = vec!['_', '/'];
+        let output = transform_with_options(input, &options);
+        assert_eq!(
+            output,
+            "call        \nvery_long_  \nfunction_   \nname_with_  \nmany_parts  \nnow         "
+        );
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        assert_eq!(line_count_with_options(input, &options), 6);
+
+        let input = "see /usr/local/share/doc/line_adjustment/README.md";
+        assert_eq!(
+            transform_with_options(input, &options),
+            "see         \n/usr/local/ \nshare/doc/  \nline_       \nadjustment/ \nREADME.md   "
+        );
+
+        // Split after as many chars as fit without such chars
+        assert_eq!(
+            transform_with_options("abcdefghijklmnop_q", &options),
+            "abcdefghijkl\nmnop_q      "
+        );
+    }
+
+    #[test]
+    fn dashes() {
+        let input = "rock\u{2014}paper\u{2014}scissors, co\u{2011}operate";
+        let options = TransformOptions::new(12);
+        let output = transform_with_options(input, &options);
+        assert_eq!(
+            output,
+            "rock\u{2014}paper\u{2014} \nscissors,   \nco\u{2011}operate  "
+        );
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        assert_eq!(line_count_with_options(input, &options), 3);
+
+        // Part before a dash fills the line before it
+        let input = "ab cd\u{2014}efghijk";
+        let output = transform(input, 6);
+        assert_eq!(output, "ab cd\u{2014}\nefghij\nk     ");
+        assert_eq!(transform(&output, 6), output);
+        assert_eq!(line_count(input, 6), 3);
+
+        // En dash too, but not the hyphens that must not end a line
+        let options = TransformOptions::new(5);
+        assert_eq!(
+            transform_with_options("1939\u{2013}1945", &options),
+            "1939\u{2013}\n1945 "
+        );
+        let mut options = TransformOptions::new(3);
+        options.break_after_chars = vec!['\u{2010}', '\u{2011}'];
+        let output = transform_with_options("co\u{2011}operate", &options);
+        assert_eq!(output, "co \n\u{2011}op\nera\nte ");
+        options.line_width = 2;
+        let input = "x\u{2010}\u{2060}ray";
+        let output = transform_with_options(input, &options);
+        assert_eq!(output, "x \n\u{2010}\u{2060}\nra\ny ");
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        assert_eq!(line_count_with_options(input, &options), 4);
+
+        // ASCII hyphen is split after only with break_after_chars
+        let options = TransformOptions::new(6);
+        assert_eq!(transform_with_options("well-known", &options), "well-k\nnown  ");
+        let mut options = TransformOptions::new(6);
+        options.break_after_chars = vec!['-'];
+        assert_eq!(transform_with_options("well-known", &options), "well- \nknown ");
+    }
+
+    #[test]
+    fn break_hints() {
+        let input = "see foo%%bar%%bazqux end";
+        let mut options = TransformOptions::new(12);
+        options.align = Alignment::Left;
+        options.break_hint = Some(String::from("%%"));
+        let cases = [
+            (12, "see         \nfoobarbazqux\nend         "),
+            (9, "see      \nfoobar   \nbazqux   \nend      "),
+            (4, "see \nfoo \nbar \nbazq\nux  \nend "),
+        ];
+        for (line_width, expected) in cases {
+            options.line_width = line_width;
+            let output = transform_with_options(input, &options);
+            assert_eq!(output, expected);
+            assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+            assert_eq!(line_count_with_options(input, &options), output.lines().count());
+        }
+
+        // Hints of words that fit take no width
+        options.line_width = 16;
+        assert_eq!(transform_with_options(input, &options), "see foobarbazqux\nend             ");
+        options.break_hint = None;
+        assert_eq!(
+            transform_with_options(input, &options),
+            "see             \nfoo%%bar%%bazqux\nend             "
+        );
+
+        // Tokens of hints alone are no words
+        options.break_hint = Some(String::from("%%"));
+        for (input, line_width, expected) in [(":\n%%", 1, ":"), ("ab %% %%%% cd", 5, "ab cd")] {
+            options.line_width = line_width;
+            let output = transform_with_options(input, &options);
+            assert_eq!(output, expected);
+            assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+            assert_eq!(line_count_with_options(input, &options), 1);
+        }
+
+        // Lines copied as they are keep their hints
+        options.line_width = 6;
+        options.preserve_paragraphs = true;
+        options.skip_line = Some(LineFilter::new(|line| line.starts_with('#')));
+        let input = "# keep %% this\nfoo%%bar%%baz";
+        let output = transform_with_options(input, &options);
+        assert_eq!(output, "# keep %% this\nfoobar\nbaz   ");
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+    }
+
+    #[test]
+    fn hanging_punctuation() {
+        let input = "Вез корабль карамель, наскочил корабль на мель, матросы две недели \
+                     карамель на мели ели.";
+        let mut options = TransformOptions::new(22);
+        options.hang_punctuation = true;
+        let output = transform_with_options(input, &options);
+        assert_eq!(
+            output,
+            "Вез  корабль  карамель,\n\
+             наскочил    корабль на\n\
+             мель,      матросы две\n\
+             недели   карамель   на\n\
+             мели               ели."
+        );
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        let unhung = transform_with_options(input, &TransformOptions::new(22));
+        assert_eq!(output.lines().nth(1), unhung.lines().nth(1));
+
+        options.align = Alignment::Right;
+        let output = transform_with_options(input, &options);
+        assert!(output.starts_with("  Вез корабль карамель,\n   наскочил корабль на\n"));
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+
+        // Ragged right edge has nothing to hang
+        options.align = Alignment::Left;
+        let mut unhung = options.clone();
+        unhung.hang_punctuation = false;
+        assert_eq!(
+            transform_with_options(input, &options),
+            transform_with_options(input, &unhung)
+        );
+
+        options.align = Alignment::Justify;
+        options.hanging_punctuation = vec!['.'];
+        let output = transform_with_options(input, &options);
+        assert!(output.starts_with("Вез  корабль карамель,\n"));
+        assert!(output.ends_with("\nмели               ели."));
+    }
+
+    #[test]
+    fn field() {
+        let input = "Съешь ещё этих мягких французских булок, да выпей чаю";
+        let mut options = TransformOptions::new(12);
+        options.field_width = Some(20);
+        options.block_align = BlockAlignment::Center;
+        let output = transform_with_options(input, &options);
+        assert_eq!(
+            output,
+            "    Съешь    ещё    \n    этих  мягких    \n    французских     \n    \
+             булок,    да    \n    выпей    чаю    "
+        );
+        assert!(output.lines().all(|line| line.chars().count() == 20));
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+
+        // Block moves as a whole, and last lines get no padding after them
+        options.align = Alignment::Right;
+        options.block_align = BlockAlignment::Right;
+        options.pad_last_line = false;
+        options.preserve_paragraphs = true;
+        let output = transform_with_options("этих мягких\n\nда выпей чаю", &options);
+        assert_eq!(output, "         этих мягких\n\n        да выпей чаю");
+
+        options.field_width = Some(11);
+        assert_eq!(
+            try_transform_with_options(input, &options),
+            Err(TransformError::FieldTooNarrow {
+                field_width: 11,
+                line_width: 12
+            })
+        );
+        options.initial_width = Some(14);
+        options.field_width = Some(13);
+        assert_eq!(
+            try_transform_with_options(input, &options),
+            Err(TransformError::FieldTooNarrow {
+                field_width: 13,
+                line_width: 14
+            })
+        );
+        options.field_width = Some(TransformOptions::DEFAULT_MAX_WIDTH + 1);
+        assert!(matches!(
+            try_transform_with_options(input, &options),
+            Err(TransformError::WidthTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn zero_width() {
+        assert_eq!(try_transform("word", 0), Err(TransformError::ZeroWidth));
+        assert_eq!(try_transform("word", 5), Ok(String::from("word ")));
+    }
+
+    #[test]
+    fn huge_width() {
+        let error = TransformError::WidthTooLarge {
+            width: u32::MAX,
+            max_width: TransformOptions::DEFAULT_MAX_WIDTH,
+        };
+        assert_eq!(try_transform("a", u32::MAX), Err(error));
+
+        let mut options = TransformOptions::new(1000);
+        options.max_width = 999;
+        assert!(matches!(
+            try_transform_with_options("a", &options),
+            Err(TransformError::WidthTooLarge { width: 1000, max_width: 999 })
+        ));
+
+        options.max_width = 1000;
+        assert_eq!(try_transform_with_options("a", &options).map(|result| result.len()), Ok(1000));
+    }
+
+    #[test]
+    fn output_size_cap() {
+        let input = "tiny words ".repeat(50);
+        let mut options = TransformOptions::new(100_000);
+        options.align = Alignment::Left;
+        let result = try_transform_with_options(&input, &options);
+        assert_eq!(result.map(|result| result.len()), Ok(100_000));
+
+        // Stopped right after the first line instead of adjusting all of them
+        options.max_output_bytes = Some(1024);
+        options.max_width = 100_000;
+        assert_eq!(
+            try_transform_with_options(&input, &options),
+            Err(TransformError::OutputTooLarge { produced: 100_000, limit: 1024 })
+        );
+
+        // Parts of a split word are checked one by one
+        options.line_width = 1000;
+        assert_eq!(
+            try_transform_with_options(&"x".repeat(5000), &options),
+            Err(TransformError::OutputTooLarge { produced: 2001, limit: 1024 })
+        );
+
+        // Final output is checked as well
+        options.line_width = 10;
+        options.number_lines = Some(NumberStyle::default());
+        let numbered = transform_with_options(&input, &options);
+        options.max_output_bytes = Some(numbered.len());
+        assert_eq!(try_transform_with_options(&input, &options), Ok(numbered.clone()));
+        options.max_output_bytes = Some(numbered.len() - 1);
+        assert!(try_transform_with_options(&input, &options).is_err());
+    }
+
+    #[test]
+    fn counted_lines() {
+        let inputs = [
+            "",
+            " \n\t ",
+            "consectetur",
+            "Поддержка кодировки utf-8 в коде",
+            "Съешь ещё этих мягких французских булок, да выпей чаю",
+            "🤩 привет  💨 hello",
+            "Бык тупогуб, тупогубенький бычок, у быка губа тупа.",
+            "Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.",
+            "Тpидцaть тpи коpaбля лaвиpовaли, лaвиpовaли, лавировали, дa не \tвылaвиpовaли.",
+            "У переп\tела и перепелки\t\t\t пять  \t\tперепелят    .",
+            "First paragraph.\n\nSecond\n  \nThird one, the longest of all, Тридцатитрёхбуквенный",
+            "ab cd\u{2014}efghijk rock\u{2014}paper\u{2014}scissors, 1939\u{2013}1945 \u{2014} x",
+        ];
+
+        for input in inputs {
+            for line_width in 1..=40 {
+                let expected = transform(input, line_width).lines().count();
+                assert_eq!(line_count(input, line_width), expected);
+
+                let mut options = TransformOptions::new(line_width);
+                options.preserve_paragraphs = true;
+                options.line_ending = LineEnding::CrLf;
+                options.lines_per_page = Some(line_width as usize % 4);
+                options.page_separator = String::from("\n---\n");
+                options.number_lines = Some(NumberStyle {
+                    start: 7,
+                    number_continuation_lines: line_width % 2 == 0,
+                    ..NumberStyle::default()
+                });
+
+                let expected = transform_with_options(input, &options).lines().count();
+                println!("input: '{}', width: {}", input, line_width);
+                assert_eq!(line_count_with_options(input, &options), expected);
+
+                // Output may end with an empty line between paragraphs
+                options.max_lines = Some(line_width as usize % 7);
+                options.min_lines = Some(line_width as usize % 5);
+                let result = transform_with_options(input, &options);
+                let expected = match result.is_empty() {
+                    true => 0,
+                    false => result.matches('\n').count() + 1,
+                };
+                assert_eq!(line_count_with_options(input, &options), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn minimum_widths() {
+        assert_eq!(minimum_width(""), 0);
+        assert_eq!(minimum_width(" \n\t "), 0);
+        assert_eq!(minimum_width("Съешь ещё этих мягких французских булок"), 11);
+        // No-break space separates words like any other whitespace
+        assert_eq!(minimum_width("100\u{A0}km away"), 4);
+
+        let inputs = [
+            "consectetur",
+            "🤩 привет  💨 hello",
+            "100\u{A0}km away, Тридцатитрёхбуквенный",
+            "Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.",
+        ];
+        for input in inputs {
+            let words: Vec<&str> = input.split_whitespace().collect();
+
+            let mut options = TransformOptions::new(0);
+            options.line_width = minimum_width(input) as u32;
+            let result = transform_with_options(input, &options);
+            assert_eq!(result.split_whitespace().collect::<Vec<_>>(), words);
+
+            // Numbers are part of whitespace separated output words
+            options.number_lines = Some(NumberStyle {
+                start: 9,
+                separator: String::from(" "),
+                ..NumberStyle::default()
+            });
+            options.line_width = minimum_width_with(input, &options) as u32;
+            let result = transform_with_options(input, &options);
+            let text: Vec<&str> = result
+                .lines()
+                .flat_map(|line| line.split_whitespace().skip(1))
+                .collect();
+            assert_eq!(text, words);
+
+            // Any narrower line splits the widest word
+            options.line_width -= 1;
+            let result = transform_with_options(input, &options);
+            let text: Vec<&str> = result
+                .lines()
+                .flat_map(|line| line.split_whitespace().skip(1))
+                .collect();
+            assert_ne!(text, words);
+        }
+    }
+
+    #[test]
+    fn widths_for_lines() {
+        assert_eq!(width_for_lines("", 0), Some(0));
+        assert_eq!(width_for_lines(" \n ", 3), Some(0));
+        assert_eq!(width_for_lines("word", 0), None);
+        assert_eq!(width_for_lines("  Съешь\tещё  этих ", 1), Some(14));
+        // Parts of a split word take whole lines
+        assert_eq!(width_for_lines("a Тридцатитрёхбуквенный", 3), Some(11));
+
+        let inputs = [
+            "consectetur",
+            "🤩 привет  💨 hello",
+            "Съешь ещё этих мягких французских булок, да выпей чаю",
+            "Бык тупогуб, тупогубенький бычок, у быка губа тупа.",
+            "Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.",
+        ];
+        for input in inputs {
+            for max_lines in 1..=20 {
+                let expected = (1..).find(|&width| line_count(input, width) <= max_lines);
+                let expected = expected.map(|width| width as usize);
+                println!("input: '{}', lines: {}", input, max_lines);
+                assert_eq!(width_for_lines(input, max_lines), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn fitting() {
+        let inputs = [
+            "",
+            "consectetur",
+            "🤩 привет  💨 hello",
+            "Съешь ещё этих мягких французских булок, да выпей чаю",
+            "Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.",
+        ];
+        for input in inputs {
+            for line_width in 1..=20 {
+                let n_lines = line_count(input, line_width);
+                let split = minimum_width(input) > line_width as usize;
+                for max_lines in 0..=20 {
+                    println!("input: '{}', width: {}, lines: {}", input, line_width, max_lines);
+                    assert_eq!(fits_splitting(input, line_width, max_lines), n_lines <= max_lines);
+                    assert_eq!(fits(input, line_width, max_lines), n_lines <= max_lines && !split);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fitting_stops_early() {
+        let units = || {
+            let units = [(1, false), (1, false), (1, false), (3, true), (1, false)];
+            units.into_iter().chain(core::iter::from_fn(|| -> Option<(usize, bool)> {
+                panic!("Advanced past the first failing line")
+            }))
+        };
+
+        assert!(!within_lines(units(), 2, true));
+        assert!(!within_lines(units(), 5, true));
+        assert!(!within_lines(units(), 100, false));
+        assert!(!within_lines(units(), 6, true));
+    }
+
+    #[test]
+    fn shrunk_width() {
+        let input = "Съешь ещё этих мягких французских булок, да выпей чаю";
+        assert_eq!(line_count(input, 40), 2);
+        assert_eq!(shrink_to_fit(input, 40), 31);
+        assert_eq!(transform_shrunk(input, 40), transform(input, 31));
+        assert_eq!(line_count(input, 30), 3);
+
+        // Split word keeps its parts
+        assert_eq!(shrink_to_fit("consectetur", 4), 4);
+        assert_eq!(shrink_to_fit("consectetur adipiscing", 100), 22);
+        assert_eq!(shrink_to_fit(" \n ", 30), 30);
+
+        let inputs = [
+            "🤩 привет  💨 hello",
+            "Бык тупогуб, тупогубенький бычок, у быка губа тупа.",
+            "Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.",
+        ];
+        for input in inputs {
+            for max_width in 1..=40 {
+                let width = shrink_to_fit(input, max_width);
+                assert!(width <= max_width);
+                assert_eq!(line_count(input, width), line_count(input, max_width));
+                if width > 1 {
+                    assert!(line_count(input, width - 1) > line_count(input, max_width));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn line_offsets() {
+        assert_eq!(break_offsets(" \n ", 5), Vec::<usize>::new());
+        assert_eq!(break_offsets("  consectetur", 4), [2, 6, 10]);
+        assert_eq!(break_offsets("🤩 привет  💨 hello", 3), [0, 5, 11, 19, 24, 27]);
+
+        let test_cases = [
+            ("consectetur", 4),
+            ("Привет", 12),
+            ("Поддержка кодировки utf-8 в коде", 8),
+            ("Съешь ещё этих мягких французских булок, да выпей чаю", 12),
+            ("🤩 привет  💨 hello", 1),
+            ("🤩 привет  💨 hello", 3),
+            ("У переп\tела и перепелки\t\t\t пять  \t\tперепелят    .", 3),
+        ];
+
+        for (input, line_width) in test_cases {
+            let result = transform(input, line_width);
+            let offsets = break_offsets(input, line_width);
+            assert_eq!(offsets.len(), result.lines().count());
+
+            for (offset, line) in offsets.into_iter().zip(result.lines()) {
+                let first_word = line.split_whitespace().next().unwrap();
+                assert!(input[offset..].starts_with(first_word), "{input:?} at {offset}");
+            }
+        }
+    }
+
+    #[test]
+    fn idempotent() {
+        let inputs = [
+            "consectetur",
+            "Поддержка кодировки utf-8 в коде",
+            "Съешь ещё этих мягких французских булок, да выпей чаю",
+            "🤩 привет  💨 hello",
+            "Бык тупогуб, тупогубенький бычок, у быка губа тупа.",
+            "Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.",
+            "У переп\tела и перепелки\t\t\t пять  \t\tперепелят    .",
+            "First paragraph.\n\nSecond\n  \nThird one, the longest of all, Тридцатитрёхбуквенный",
+            "ab cd\u{2014}efghijk rock\u{2014}paper\u{2014}scissors, 1939\u{2013}1945 \u{2014} x",
+        ];
+
+        for input in inputs {
+            for line_width in 1..=40 {
+                let result = transform(input, line_width);
+                println!("input: '{}', width: {}", input, line_width);
+                assert_eq!(transform(&result, line_width), result);
+
+                for align in [Alignment::Left, Alignment::Center, Alignment::Right] {
+                    let mut options = TransformOptions::new(line_width);
+                    options.align = align;
+                    options.pad_last_line = line_width % 2 == 0;
+                    options.preserve_paragraphs = true;
+                    options.line_ending = LineEnding::CrLf;
+
+                    let result = transform_with_options(input, &options);
+                    assert_eq!(transform_with_options(&result, &options), result);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shaped() {
+        let input = "Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели. \
+                     Тридцать три корабля лавировали, лавировали, да не вылавировали.";
+        let result = transform_shaped(input, &[20, 20], 40);
+        let expected = [
+            "Вез          корабль",
+            "карамель,   наскочил",
+            "корабль  на  мель,  матросы  две  недели",
+            "карамель  на  мели  ели.  Тридцать   три",
+            "корабля лавировали, лавировали, да    не",
+            "вылавировали.                           ",
+        ];
+        assert_eq!(result, expected.join("\n"));
+
+        let widths: Vec<usize> = result.lines().map(|line| line.chars().count()).collect();
+        assert_eq!(widths, [20, 20, 40, 40, 40, 40]);
+
+        // Split word continues at the next width
+        assert_eq!(transform_shaped("consectetur", &[4, 2], 3), "cons\nec\ntet\nur ");
+
+        assert_eq!(try_transform_shaped(input, &[20, 0], 40), Err(TransformError::ZeroWidth));
+        assert_eq!(try_transform_shaped(input, &[20], 0), Err(TransformError::ZeroWidth));
+        assert!(try_transform_shaped(input, &[u32::MAX], 40).is_err());
+        assert_eq!(try_transform_shaped(input, &[20, 20], 40), Ok(result));
+    }
+
+    #[test]
+    fn shaped_with() {
+        let input = "Вез корабль карамель, наскочил корабль на мель, матросы две недели \
+                     карамель на мели ели. Тpидцaть тpи коpaбля лaвиpовaли, лaвиpовaли";
+        let width_fn = |idx: usize| if idx.is_multiple_of(2) { 10 } else { 20 };
+        let result = transform_shaped_with(input, width_fn).unwrap();
+        for (idx, line) in result.lines().enumerate() {
+            assert_eq!(line.chars().count() as u32, width_fn(idx));
+        }
+        assert_eq!(result.lines().count(), 11);
+
+        let widths: Vec<u32> = (0..11).map(width_fn).collect();
+        assert_eq!(result, transform_shaped(input, &widths, 10));
+
+        // Only widths of existing lines are checked
+        let width_fn = |idx: usize| if idx < 2 { 20 } else { 0 };
+        let result = transform_shaped_with("Тридцать три", width_fn);
+        assert_eq!(result, Ok(transform("Тридцать три", 20)));
+        let result = transform_shaped_with(input, width_fn);
+        assert_eq!(result, Err(TransformError::ZeroWidth));
+        assert!(transform_shaped_with(input, |_| u32::MAX).is_err());
+        assert_eq!(transform_shaped_with("", |_| 0), Ok(String::new()));
+
+        // Split word continues at the next width
+        let result = transform_shaped_with("consectetur", |idx| idx as u32 + 2);
+        assert_eq!(result.unwrap(), "co\nnse\nctet\nur   ");
+    }
+
+    #[test]
+    fn initial_width() {
+        let mut options = TransformOptions::new(12);
+        options.initial_width = Some(8);
+        let result = transform_with_options("Съешь ещё этих мягких булок", &options);
+        assert_eq!(result, "Съешь   \nещё     этих\nмягких булок");
+
+        // Word fits later lines, but not the first one
+        let result = transform_with_options("французских булок", &options);
+        assert_eq!(result, "        \nфранцузских \nбулок       ");
+
+        // First line of every paragraph
+        options.preserve_paragraphs = true;
+        let input = "Съешь ещё\n\nэтих мягких французских\n\nбулок";
+        let expected = "Съешь   \nещё         \n\nэтих    \nмягких      \nфранцузских \n\nбулок   ";
+        assert_eq!(transform_with_options(input, &options), expected);
+        assert_eq!(line_count_with_options(input, &options), expected.lines().count());
+
+        // Gutter takes part of the first line too
+        options.number_lines = Some(NumberStyle {
+            separator: String::from(" "),
+            ..NumberStyle::default()
+        });
+        let result = transform_with_options("Съешь ещё этих мягких булок", &options);
+        assert_eq!(result, "1 Съешь \n2 ещё   этих\n3 мягких    \n4 булок     ");
+
+        options.initial_width = Some(0);
+        assert_eq!(try_transform_with_options("text", &options), Err(TransformError::ZeroWidth));
+    }
+
+    #[test]
+    fn min_lines() {
+        let blank = " ".repeat(12);
+        let text = ["Вез  корабль", "карамель,   "];
+        let mut options = TransformOptions::new(12);
+        options.min_lines = Some(5);
+
+        let cases = [
+            (VerticalAlignment::Top, 0),
+            (VerticalAlignment::Middle, 1),
+            (VerticalAlignment::Bottom, 3),
+        ];
+        for (vertical_align, n_above) in cases {
+            options.vertical_align = vertical_align;
+            let mut expected = [blank.as_str(); 5];
+            expected[n_above..n_above + 2].copy_from_slice(&text);
+
+            let result = transform_with_options("Вез корабль карамель,", &options);
+            assert_eq!(result, expected.join("\n"));
+            assert_eq!(line_count_with_options("Вез корабль карамель,", &options), 5);
+        }
+
+        // Blank lines are empty when last lines are not padded
+        options.pad_last_line = false;
+        options.vertical_align = VerticalAlignment::Middle;
+        assert_eq!(transform_with_options("Вез", &options), "\n\nВез\n\n");
+        assert_eq!(transform_with_options("", &options), "\n\n\n\n");
+
+        // Text as long as the block is left as is
+        options.min_lines = Some(2);
+        let result = transform_with_options("Вез корабль карамель,", &options);
+        assert_eq!(result, "Вез  корабль\nкарамель,");
+        assert!(matches!(transform_cow("Вез", &options), Cow::Owned(_)));
+    }
+
+    #[test]
+    fn margins() {
+        let input = "Вез корабль карамель, наскочил корабль на мель, матросы две недели";
+        let mut options = TransformOptions::new(12);
+        options.margin_left = 3;
+        options.margin_right = 2;
+
+        // Column of the first and of the last char that is not a space
+        let columns = |text: &str| -> Vec<(usize, usize)> {
+            let columns = text.lines().map(|line| {
+                let chars: Vec<char> = line.chars().collect();
+                let first = chars.iter().position(|&chr| chr != ' ').unwrap();
+                let last = chars.iter().rposition(|&chr| chr != ' ').unwrap();
+                assert_eq!(chars.len(), 17);
+                (first, last)
+            });
+            columns.collect()
+        };
+
+        let result = transform_with_options(input, &options);
+        let expected = [(3, 14), (3, 11), (3, 10), (3, 14), (3, 7), (3, 14), (3, 8)];
+        assert_eq!(columns(&result), expected);
+        assert_eq!(result.lines().next(), Some("   Вез  корабль  "));
+
+        options.align = Alignment::Right;
+        let result = transform_with_options(input, &options);
+        let expected = [(4, 14), (6, 14), (7, 14), (5, 14), (10, 14), (4, 14), (9, 14)];
+        assert_eq!(columns(&result), expected);
+
+        // Gutter goes inside the margins
+        options.number_lines = Some(NumberStyle {
+            separator: String::from("|"),
+            ..NumberStyle::default()
+        });
+        let result = transform_with_options(input, &options);
+        assert_eq!(result.lines().next(), Some("   1|       Вез  "));
+
+        // Without padding, the last line ends with its last word
+        let mut options = TransformOptions::new(12);
+        options.margin_left = 1;
+        options.margin_right = 1;
+        options.pad_last_line = false;
+        options.preserve_paragraphs = true;
+        let result = transform_with_options("Вез корабль карамель,\n\nнаскочил", &options);
+        assert_eq!(result, " Вез  корабль \n карамель,\n\n наскочил");
+
+        options.max_width = 13;
+        let result = try_transform_with_options("Вез", &options);
+        let expected = TransformError::WidthTooLarge {
+            width: 14,
+            max_width: 13,
+        };
+        assert_eq!(result, Err(expected));
+        options.margin_right = 0;
+        assert_eq!(try_transform_with_options("Вез", &options), Ok(String::from(" Вез")));
+    }
+
+    #[test]
+    fn two_parts() {
+        assert_eq!(two_part_line("Total", "42", 16, '.'), "Total.........42");
+        assert_eq!(two_part_line("File.rs", "3 KB", 16, ' '), "File.rs     3 KB");
+        assert_eq!(two_part_line("", "42", 4, '-'), "--42");
+        assert_eq!(two_part_line("Total", "", 8, '.'), "Total...");
+
+        // Width is counted in chars
+        let result = two_part_line("Итого", "4 Кб", 12, '·');
+        assert_eq!(result, "Итого···4 Кб");
+        assert_eq!(result.chars().count(), 12);
+
+        // Only the last line carries the right part
+        let result = two_part_line("Вез корабль карамель, наскочил", "42", 16, '.');
+        assert_eq!(result, "Вез корабль     \nкарамель,       \nнаскочил......42");
+        let result = two_part_line("Вез корабль карамель", "42", 12, '.');
+        assert_eq!(result, "Вез корабль \nкарамель..42");
+        let result = two_part_line("Вез корабль", "42", 11, '.');
+        assert_eq!(result, "Вез корабль\n.........42");
+
+        // Right part leaves room for a single fill char
+        assert_eq!(two_part_line("Total", "1234567", 4, '.'), "Tota\nl   \n.123");
+    }
+
+    #[test]
+    fn columns() {
+        let input = "Вез корабль карамель, наскочил корабль на мель, матросы две недели \
+                     карамель на мели ели.";
+        let lines: Vec<String> = transform(input, 12).lines().map(String::from).collect();
+        assert_eq!(lines.len(), 9);
+        let blank = " ".repeat(12);
+
+        let columns = wrap_columns(input, 12, 2);
+        let mut second = lines[5..].to_vec();
+        second.push(blank.clone());
+        assert_eq!(columns, [lines[..5].to_vec(), second]);
+
+        let columns = wrap_columns(input, 12, 5);
+        let mut expected: Vec<Vec<String>> = lines.chunks(2).map(<[String]>::to_vec).collect();
+        expected[4].push(blank.clone());
+        assert_eq!(columns, expected);
+
+        // Balanced rather than filling the first columns up
+        let heights = |columns: Vec<Vec<String>>| -> Vec<usize> {
+            let blank = &blank;
+            let heights = columns.iter().map(|column| column.iter().filter(|line| *line != blank));
+            heights.map(Iterator::count).collect()
+        };
+        assert_eq!(heights(wrap_columns(input, 12, 4)), [3, 2, 2, 2]);
+        assert_eq!(heights(wrap_columns(input, 12, 12)), [1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0]);
+        assert!(wrap_columns(input, 12, 12).iter().all(|column| column.len() == 1));
+
+        assert_eq!(wrap_columns(input, 12, 1), wrap_columns(input, 12, 0));
+        assert_eq!(wrap_columns(input, 12, 1), [lines]);
+        assert_eq!(wrap_columns("", 10, 2), [Vec::<String>::new(), Vec::new()]);
+    }
+
+    #[test]
+    fn blank_line_for_empty() {
+        let mut options = TransformOptions::new(10);
+        for input in ["", "   ", "\t\t", " \n\t \n"] {
+            options.blank_line_for_empty = false;
+            assert_eq!(transform_with_options(input, &options), "");
+            assert_eq!(line_count_with_options(input, &options), 0);
+
+            options.blank_line_for_empty = true;
+            assert_eq!(transform_with_options(input, &options), " ".repeat(10));
+            assert_eq!(line_count_with_options(input, &options), 1);
+            assert!(matches!(transform_cow(input, &options), Cow::Owned(_)));
+        }
+
+        // Only input without words gets the line
+        assert_eq!(transform_with_options(" Вез ", &options), "Вез       ");
+
+        options.preserve_paragraphs = true;
+        options.align = Alignment::Center;
+        options.initial_width = Some(4);
+        assert_eq!(transform_with_options("\n \n", &options), "    ");
+
+        options.pad_last_line = false;
+        assert_eq!(transform_with_options(" ", &options), "");
+        assert_eq!(line_count_with_options(" ", &options), 1);
+    }
+
+    #[test]
+    fn final_newline() {
+        let mut options = TransformOptions::new(4);
+        options.final_newline = true;
+        assert_eq!(transform_with_options("consectetur", &options), "cons\necte\ntur \n");
+        assert_eq!(transform_with_options("", &options), "");
+        assert_eq!(transform_with_options(" \t ", &options), "");
+        assert!(matches!(transform_cow("cons", &options), Cow::Owned(_)));
+
+        options.line_ending = LineEnding::CrLf;
+        options.blank_line_for_empty = true;
+        assert_eq!(transform_with_options(" \t ", &options), "    \r\n");
+        assert_eq!(transform_with_options("cons ecte", &options), "cons\r\necte\r\n");
+
+        // Blank line that is empty is no output either
+        options.pad_last_line = false;
+        assert_eq!(transform_with_options("", &options), "");
+    }
+
+    #[test]
+    fn try_transform_never_panics() {
+        // Same check as the fuzz target, over pseudo-random inputs
+        let alphabet: Vec<char> = "ab \t\n\r\u{a0}\u{2003}й🤩\u{301}\u{200b}\u{0}\u{85}".chars().collect();
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as usize
+        };
+
+        for _ in 0..5_000 {
+            let len = next() % 30;
+            let input: String = (0..len).map(|_| alphabet[next() % alphabet.len()]).collect();
+            let line_width = match next() % 8 {
+                0 => [0, u32::MAX, TransformOptions::DEFAULT_MAX_WIDTH + 1][next() % 3],
+                _ => (next() % 12) as u32,
+            };
+
+            match try_transform(&input, line_width) {
+                Ok(output) => {
+                    for line in output.lines() {
+                        assert_eq!(line.chars().count() as u32, line_width, "{input:?}");
+                    }
+                }
+                Err(TransformError::ZeroWidth) => assert_eq!(line_width, 0),
+                Err(err) => assert!(line_width > TransformOptions::DEFAULT_MAX_WIDTH, "{err}"),
+            }
+        }
+    }
+
+    #[test]
+    fn wide_chars_take_a_column() {
+        // Every char counts as one column, so even a line of one fits any char
+        assert_eq!(try_transform("漢", 1), Ok(String::from("漢")));
+        assert_eq!(try_transform("漢字 🤩🤩", 1), Ok(String::from("漢\n字\n🤩\n🤩")));
+        assert_eq!(line_count("漢字 🤩🤩", 1), 4);
+    }
 }
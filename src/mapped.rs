@@ -0,0 +1,283 @@
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+
+use crate::{LinePart, Lines, TransformOptions, NEWLINE_STR};
+
+/// Same as [`crate::transform`], but also returns where every output
+/// position comes from in `input`.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn transform_mapped(input: &str, line_width: u32) -> (String, OffsetMap<'_>) {
+    let options = TransformOptions::new(line_width);
+    let mut map = OffsetMap {
+        input,
+        line_width: line_width as usize,
+        words: Vec::new(),
+        line_starts: Vec::new(),
+    };
+
+    let mut result = String::new();
+    let mut lines = Lines::new(input, &options, line_width as usize);
+    while let Some(plan) = lines.next_plan() {
+        if !map.line_starts.is_empty() {
+            result += NEWLINE_STR;
+        }
+        map.line_starts.push(map.words.len());
+
+        let mut col = 0;
+        let mut output_len = 0;
+        plan.for_each_part(|part| match part {
+            LinePart::Spaces(count) => {
+                col += count;
+                output_len += count;
+            }
+            LinePart::Word(word) => {
+                let width = word.chars().count();
+                map.words.push(MappedWord {
+                    col,
+                    width,
+                    offset: word.as_ptr() as usize - input.as_ptr() as usize,
+                    len: word.len(),
+                    output_offset: result.len() + output_len,
+                });
+                col += width;
+                output_len += word.len();
+            }
+        });
+        plan.push(&mut result);
+    }
+
+    (result, map)
+}
+
+/// Same as [`crate::transform`], but also translates every byte range of
+/// `spans` in `input` into byte ranges of the output, e.g. to highlight
+/// the same text after wrapping.
+///
+/// A span gives a range for every word, or part of a split word, it
+/// covers, in output order. Whitespace of the input is either collapsed
+/// or replaced by padding, so it is left out of the ranges, and a span
+/// of whitespace alone gives no ranges. Ranges are translated byte by
+/// byte, so spans at char boundaries give ranges at char boundaries.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn transform_with_spans(
+    input: &str,
+    line_width: u32,
+    spans: &[Range<usize>],
+) -> (String, Vec<Vec<Range<usize>>>) {
+    let (result, map) = transform_mapped(input, line_width);
+    let ranges = spans.iter().map(|span| map.output_ranges(span)).collect();
+    (result, ranges)
+}
+
+/// Maps positions in the output of [`transform_mapped`] back to byte
+/// offsets in its input.
+#[derive(Debug, Clone)]
+pub struct OffsetMap<'a> {
+    input: &'a str,
+    line_width: usize,
+    /// Words of all lines in output order
+    words: Vec<MappedWord>,
+    /// Index of the first word of every line
+    line_starts: Vec<usize>,
+}
+
+/// Word or a part of a split word, placed in an output line.
+#[derive(Debug, Clone, Copy)]
+struct MappedWord {
+    /// Column of the first char
+    col: usize,
+    /// Width in chars
+    width: usize,
+    /// Byte offset in the input
+    offset: usize,
+    /// Length in bytes
+    len: usize,
+    /// Byte offset in the output
+    output_offset: usize,
+}
+
+impl OffsetMap<'_> {
+    /// Byte offset in the input of the char at `col` of output `line`,
+    /// both counted from 0 and `col` in chars.
+    ///
+    /// Padding and gaps between words map to the end of the preceding
+    /// word. Returns `None` for a position outside of the output.
+    pub fn to_input(&self, line: usize, col: usize) -> Option<usize> {
+        let start = *self.line_starts.get(line)?;
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.words.len());
+        let words = &self.words[start..end];
+
+        if col >= self.line_width {
+            return None;
+        }
+
+        // Every line starts with a word
+        let word = words[..words.partition_point(|word| word.col <= col)].last()?;
+        if col >= word.col + word.width {
+            return Some(word.offset + word.len);
+        }
+
+        let text = &self.input[word.offset..word.offset + word.len];
+        let (idx, _) = text
+            .char_indices()
+            .nth(col - word.col)
+            .expect("Column is within the word");
+        Some(word.offset + idx)
+    }
+
+    /// Byte ranges of the output that come from `span` of the input, see
+    /// [`transform_with_spans`].
+    fn output_ranges(&self, span: &Range<usize>) -> Vec<Range<usize>> {
+        let first = self
+            .words
+            .partition_point(|word| word.offset + word.len <= span.start);
+        self.words[first..]
+            .iter()
+            .take_while(|word| word.offset < span.end)
+            .map(|word| {
+                let start = span.start.max(word.offset) - word.offset;
+                let end = span.end.min(word.offset + word.len) - word.offset;
+                word.output_offset + start..word.output_offset + end
+            })
+            .filter(|range| !range.is_empty())
+            .collect()
+    }
+
+    /// Line and column, in chars, of the output char that comes from
+    /// `input_offset`, which is a byte offset in the input.
+    ///
+    /// Returns `None` for an offset into whitespace, which is collapsed
+    /// or replaced by padding, and for one that is not at a char boundary.
+    pub fn to_output(&self, input_offset: usize) -> Option<(usize, usize)> {
+        // Words are in the same order as in the input
+        let idx = self
+            .words
+            .partition_point(|word| word.offset <= input_offset)
+            .checked_sub(1)?;
+        let word = self.words[idx];
+        if input_offset >= word.offset + word.len {
+            return None;
+        }
+
+        let text = &self.input[word.offset..word.offset + word.len];
+        let prefix = text.get(..input_offset - word.offset)?;
+        let line = self.line_starts.partition_point(|&start| start <= idx) - 1;
+        Some((line, word.col + prefix.chars().count()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{transform_mapped, transform_with_spans};
+    use crate::transform;
+
+    #[test]
+    fn same_output() {
+        for line_width in [1, 3, 7, 12, 30] {
+            let input = "Съешь ещё  этих мягких\tфранцузских булок, 🤩 Тридцатитрёхбуквенный";
+            let (result, _) = transform_mapped(input, line_width);
+            assert_eq!(result, transform(input, line_width));
+        }
+    }
+
+    #[test]
+    fn positions() {
+        let input = "Съешь  ещё\nэтих\nconsectetur_adipiscing";
+        let (result, map) = transform_mapped(input, 12);
+        assert_eq!(
+            result,
+            "Съешь    ещё\nэтих        \nconsectetur_\nadipiscing  "
+        );
+
+        // Inside words, counted in chars
+        assert_eq!(map.to_input(0, 0), Some(0));
+        assert_eq!(map.to_input(0, 2), Some(4));
+        assert_eq!(map.to_input(0, 10), Some(14));
+        assert_eq!(map.to_input(1, 2), Some(23));
+
+        // Justification gap maps to the end of the word before it
+        assert_eq!(map.to_input(0, 5), Some(10));
+        assert_eq!(map.to_input(0, 8), Some(10));
+
+        // Split word continues on the next line
+        assert_eq!(map.to_input(2, 11), Some(39));
+        assert_eq!(map.to_input(3, 0), Some(40));
+
+        // Trailing padding
+        assert_eq!(map.to_input(1, 6), Some(27));
+        assert_eq!(map.to_input(3, 10), Some(50));
+        assert_eq!(map.to_input(3, 11), Some(50));
+
+        // Outside of the output
+        assert_eq!(map.to_input(3, 12), None);
+        assert_eq!(map.to_input(4, 0), None);
+        assert_eq!(transform_mapped(" ", 12).1.to_input(0, 0), None);
+    }
+
+    #[test]
+    fn output_positions() {
+        let (_, map) = transform_mapped("Съешь  ещё\nэтих\nconsectetur_adipiscing", 12);
+        assert_eq!(map.to_output(0), Some((0, 0)));
+        assert_eq!(map.to_output(12), Some((0, 9)));
+        assert_eq!(map.to_output(39), Some((2, 11)));
+        assert_eq!(map.to_output(40), Some((3, 0)));
+
+        // Whitespace and offsets inside a char
+        assert_eq!(map.to_output(10), None);
+        assert_eq!(map.to_output(18), None);
+        assert_eq!(map.to_output(1), None);
+        assert_eq!(map.to_output(50), None);
+
+        let inputs = [
+            "Поддержка кодировки utf-8 в коде",
+            "Съешь ещё этих мягких французских булок, да выпей чаю",
+            "Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.",
+            "У переп\tела и перепелки\t\t\t пять  \t\tперепелят    .",
+        ];
+        for input in inputs {
+            for line_width in [1, 3, 8, 12, 18] {
+                let (result, map) = transform_mapped(input, line_width);
+                for (line, text) in result.lines().enumerate() {
+                    for (col, chr) in text.chars().enumerate() {
+                        if chr == ' ' {
+                            continue;
+                        }
+                        let offset = map.to_input(line, col).unwrap();
+                        assert!(input[offset..].starts_with(chr));
+                        assert_eq!(map.to_output(offset), Some((line, col)));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spans() {
+        let input = "Съешь  ещё\nэтих\nconsectetur_adipiscing";
+        // "Съешь    ещё\nэтих        \nconsectetur_\nadipiscing  "
+        let spans = [0..4, 12..25, 34..45, 10..12, 40..40, 48..60];
+        let (result, ranges) = transform_with_spans(input, 12, &spans);
+        assert_eq!(result, transform_mapped(input, 12).0);
+        let texts: Vec<Vec<&str>> = ranges
+            .iter()
+            .map(|ranges| ranges.iter().map(|range| &result[range.clone()]).collect())
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                vec!["Съ"],
+                vec!["ещё", "эти"],
+                vec!["tetur_", "adipi"],
+                vec![],
+                vec![],
+                vec!["ng"],
+            ]
+        );
+    }
+}
@@ -0,0 +1,394 @@
+//! Markers of spans that the renderer of the output consumes.
+
+use alloc::string::String;
+
+use alloc::vec::Vec;
+
+use crate::{width::char_units, MarkupConfig, Word, NEWLINE_STR};
+
+/// Starts every OSC 8 sequence.
+const LINK_START: &str = "\u{1b}]8;";
+/// Ends a link, with either terminator.
+const LINK_CLOSE: &str = "\u{1b}]8;;\u{1b}\\";
+const LINK_CLOSE_BEL: &str = "\u{1b}]8;;\u{7}";
+
+impl MarkupConfig {
+    /// Length of the marker `text` starts with, if any.
+    fn marker_at(&self, text: &str) -> Option<(usize, Marker)> {
+        if self.hyperlinks && text.starts_with(LINK_START) {
+            return link_at(text);
+        }
+        if !self.open.is_empty() && text.starts_with(self.open.as_str()) {
+            Some((self.open.len(), Marker::Open))
+        } else if !self.close.is_empty() && text.starts_with(self.close.as_str()) {
+            Some((self.close.len(), Marker::Close))
+        } else {
+            None
+        }
+    }
+
+    /// Length of the marker `text` starts with, if any, and whether it
+    /// ends a span.
+    pub(crate) fn marker_len(&self, text: &str) -> Option<(usize, bool)> {
+        let (len, marker) = self.marker_at(text)?;
+        Some((len, matches!(marker, Marker::Close | Marker::LinkClose)))
+    }
+
+    /// Calls `f` with every marker of `text` and its offset.
+    fn for_each_marker(&self, text: &str, mut f: impl FnMut(usize, usize, Marker)) {
+        let mut idx = 0;
+        while let Some(chr) = text[idx..].chars().next() {
+            match self.marker_at(&text[idx..]) {
+                Some((len, marker)) => {
+                    f(idx, len, marker);
+                    idx += len;
+                }
+                None => idx += chr.len_utf8(),
+            }
+        }
+    }
+
+    /// Width taken by markers of `text`, which the output does not show.
+    pub(crate) fn markers_width(&self, text: &str) -> usize {
+        let mut width = 0;
+        self.for_each_marker(text, |idx, len, _| {
+            width += text[idx..idx + len].chars().count();
+        });
+        width
+    }
+
+    /// `text` without its markers.
+    #[cfg(any(test, feature = "test-util"))]
+    pub(crate) fn strip_markers(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut end = 0;
+        self.for_each_marker(text, |idx, len, _| {
+            result += &text[end..idx];
+            end = idx + len;
+        });
+        result + &text[end..]
+    }
+
+    /// Same as [`crate::split_manually`], but markers take no width, and
+    /// widths are in half units when `half_units` is set. Close markers
+    /// right after the part stay with it, open markers go with the rest.
+    /// The part gets at least one char.
+    pub(crate) fn split<'a>(
+        &self,
+        unfitted: Word<'a>,
+        line_width: usize,
+        half_units: bool,
+    ) -> (Word<'a>, Word<'a>) {
+        assert!(line_width > 0, "line width must be greater than 0");
+
+        let text = unfitted.text;
+        let mut idx = 0;
+        let mut width = 0;
+        // End of the part, before open markers that may go with the rest
+        let mut end = 0;
+        while let Some(chr) = text[idx..].chars().next() {
+            match self.marker_at(&text[idx..]) {
+                Some((len, Marker::Close | Marker::LinkClose)) if idx == end => {
+                    idx += len;
+                    end = idx;
+                }
+                Some((len, _)) => idx += len,
+                None => {
+                    let units = if half_units { char_units(chr) } else { 1 };
+                    if width + units > line_width && width > 0 {
+                        break;
+                    }
+                    idx += chr.len_utf8();
+                    width += units;
+                    end = idx;
+                }
+            }
+        }
+        if idx == text.len() {
+            end = idx;
+        }
+
+        let (part, rest) = text.split_at(end);
+        let part = Word { text: part, width };
+        let rest = Word {
+            text: rest,
+            width: unfitted.width - width,
+        };
+        (part, rest)
+    }
+
+    /// Closes spans open at the end of every line of `text` and opens them
+    /// again on the next line with text. Markers go next to the words,
+    /// padding stays out of the spans. Spans are not opened again after a
+    /// blank line, so paragraphs come out the same wrapped on their own.
+    pub(crate) fn reopen_spans(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        // Markers that open spans still open, with whether they are links
+        let mut open: Vec<(&str, bool)> = Vec::new();
+        for (idx, line) in text.split(NEWLINE_STR).enumerate() {
+            if idx > 0 {
+                result += NEWLINE_STR;
+            }
+            let words = line.trim();
+            if words.is_empty() {
+                result += line;
+                open.clear();
+                continue;
+            }
+
+            let start = line.len() - line.trim_start().len();
+            result += &line[..start];
+            result.extend(open.iter().map(|&(marker, _)| marker));
+            result += words;
+            self.for_each_marker(words, |idx, len, marker| {
+                let is_link = match marker {
+                    Marker::Open => return open.push((&words[idx..idx + len], false)),
+                    Marker::LinkOpen => return open.push((&words[idx..idx + len], true)),
+                    Marker::Close => false,
+                    Marker::LinkClose => true,
+                };
+                // Links and other spans may overlap
+                if let Some(idx) = open.iter().rposition(|&(_, link)| link == is_link) {
+                    open.remove(idx);
+                }
+            });
+            result.extend(open.iter().rev().map(|&(marker, is_link)| match is_link {
+                true if marker.ends_with('\u{7}') => LINK_CLOSE_BEL,
+                true => LINK_CLOSE,
+                false => self.close.as_str(),
+            }));
+            result += &line[start + words.len()..];
+        }
+        result
+    }
+}
+
+/// OSC 8 sequence `text` starts with and its length, if it is complete.
+fn link_at(text: &str) -> Option<(usize, Marker)> {
+    let rest = &text[LINK_START.len()..];
+    let (end, terminator) = rest
+        .char_indices()
+        .find(|&(idx, chr)| chr == '\u{7}' || rest[idx..].starts_with("\u{1b}\\"))?;
+    let len = LINK_START.len() + end + terminator.len_utf8() + usize::from(terminator != '\u{7}');
+    let (_, uri) = rest[..end].split_once(';')?;
+    match uri.is_empty() {
+        true => Some((len, Marker::LinkClose)),
+        false => Some((len, Marker::LinkOpen)),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Marker {
+    Open,
+    Close,
+    LinkOpen,
+    LinkClose,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        transform_with_options, try_transform_with_options, verify::verify_invariants, Alignment,
+        Document, MarkupConfig, TransformOptions,
+    };
+
+    fn options(line_width: u32, reopen_on_wrap: bool) -> TransformOptions {
+        let mut options = TransformOptions::new(line_width);
+        options.markup = Some(MarkupConfig {
+            open: String::from("\u{1}"),
+            close: String::from("\u{2}"),
+            reopen_on_wrap,
+            hyperlinks: false,
+        });
+        options
+    }
+
+    fn visible_width(line: &str) -> usize {
+        line.chars()
+            .filter(|&chr| chr != '\u{1}' && chr != '\u{2}')
+            .count()
+    }
+
+    #[test]
+    fn markers_take_no_width() {
+        let input = "Съешь \u{1}ещё этих мягких\u{2} французских булок";
+        let options = options(12, false);
+        let output = transform_with_options(input, &options);
+        assert_eq!(
+            output,
+            "Съешь    \u{1}ещё\n\
+             этих  мягких\u{2}\n\
+             французских \n\
+             булок       "
+        );
+        assert!(output.lines().all(|line| visible_width(line) == 12));
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+
+        let options = self::options(12, true);
+        let output = transform_with_options(input, &options);
+        assert_eq!(
+            output,
+            "Съешь    \u{1}ещё\u{2}\n\
+             \u{1}этих  мягких\u{2}\n\
+             французских \n\
+             булок       "
+        );
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+    }
+
+    #[test]
+    fn hyperlinks() {
+        let mut options = TransformOptions::new(12);
+        options.markup = Some(MarkupConfig {
+            open: String::new(),
+            close: String::new(),
+            reopen_on_wrap: true,
+            hyperlinks: true,
+        });
+        let open = "\u{1b}]8;;https://example.com/булки\u{1b}\\";
+        let close = "\u{1b}]8;;\u{1b}\\";
+        let input = format!("Съешь {open}ещё этих мягких{close} булок");
+        let output = transform_with_options(&input, &options);
+        assert_eq!(
+            output,
+            format!("Съешь    {open}ещё{close}\n{open}этих  мягких{close}\nбулок       ")
+        );
+        for line in output.lines() {
+            assert_eq!(line.matches(open).count(), line.matches(close).count());
+            assert!(line.find(open) <= line.find(close));
+        }
+        assert_eq!(verify_invariants(&input, &options, &output), Ok(()));
+
+        // Parts of a split link, ended by BEL
+        let open = "\u{1b}]8;id=1;https://example.com\u{7}";
+        let close = "\u{1b}]8;;\u{7}";
+        let input = format!("see {open}Тридцатитрёхбуквенный{close} word");
+        assert_eq!(
+            transform_with_options(&input, &options),
+            format!(
+                "see         \n{open}Тридцатитрёх{close}\n{open}буквенный{close}   \nword        "
+            )
+        );
+
+        // Otherwise escape sequences are text, split like any other
+        options.markup.as_mut().unwrap().hyperlinks = false;
+        let output = transform_with_options(&input, &options);
+        assert!(!output.contains(open));
+    }
+
+    #[test]
+    fn links_with_break_chars() {
+        let mut options = TransformOptions::new(1);
+        options.markup = Some(MarkupConfig {
+            open: String::new(),
+            close: String::new(),
+            reopen_on_wrap: false,
+            hyperlinks: true,
+        });
+        options.break_after_chars = vec!['/'];
+        options.max_output_bytes = Some(1 << 12);
+
+        // Slashes of the URI are no places to split at
+        let input = "\u{1b}]8;;/\u{1b}\\ea";
+        let output = try_transform_with_options(input, &options).unwrap();
+        assert_eq!(output, "\u{1b}]8;;/\u{1b}\\e\na");
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+
+        let open = "\u{1b}]8;;https://example.com/docs\u{1b}\\";
+        let close = "\u{1b}]8;;\u{1b}\\";
+        let input = format!("see {open}the docs{close} now");
+        for reopen_on_wrap in [false, true] {
+            options.markup.as_mut().unwrap().reopen_on_wrap = reopen_on_wrap;
+            let output = try_transform_with_options(&input, &options).unwrap();
+            assert_eq!(output.lines().count(), 13);
+            assert_eq!(verify_invariants(&input, &options, &output), Ok(()));
+        }
+        options.line_width = 4;
+        assert_eq!(
+            try_transform_with_options(&input, &options).unwrap(),
+            format!("see \n{open}the{close} \n{open}docs{close}\nnow ")
+        );
+    }
+
+    #[test]
+    fn field() {
+        let mut options = options(4, false);
+        options.field_width = Some(6);
+        let input = "\u{1}ab\u{2} c";
+        let output = transform_with_options(input, &options);
+        assert_eq!(output, "\u{1}ab\u{2} c  ");
+        assert!(output.lines().all(|line| visible_width(line) == 6));
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+    }
+
+    #[test]
+    fn split_words() {
+        let mut options = options(4, false);
+        options.align = Alignment::Left;
+        let input = "ab\u{1}cd\u{2}ef \u{1}\u{1}ghijk\u{2}\u{2}";
+        assert_eq!(
+            transform_with_options(input, &options),
+            "ab\u{1}cd\u{2}\nef  \n\u{1}\u{1}ghij\nk\u{2}\u{2}   "
+        );
+
+        options.markup.as_mut().unwrap().reopen_on_wrap = true;
+        assert_eq!(
+            transform_with_options(input, &options),
+            "ab\u{1}cd\u{2}\nef  \n\u{1}\u{1}ghij\u{2}\u{2}\n\u{1}\u{1}k\u{2}\u{2}   "
+        );
+    }
+
+    #[test]
+    fn spans_end_with_paragraph() {
+        let mut options = options(6, true);
+        options.preserve_paragraphs = true;
+        let input = "ab \u{1}cd ef\n\ngh ij\u{2} kl";
+        let output = transform_with_options(input, &options);
+        assert_eq!(
+            output,
+            "ab  \u{1}cd\u{2}\n\u{1}ef\u{2}    \n\ngh  ij\u{2}\nkl    "
+        );
+
+        let mut document = Document::with_options(options.clone());
+        assert_eq!(document.update(input), output);
+        #[cfg(feature = "parallel")]
+        assert_eq!(crate::transform_par(input, &options), output);
+    }
+
+    #[test]
+    fn break_after_chars() {
+        let mut options = TransformOptions::new(2);
+        options.markup = Some(MarkupConfig {
+            open: String::from("<b>"),
+            close: String::from("</b>"),
+            reopen_on_wrap: false,
+            hyperlinks: false,
+        });
+        options.break_after_chars = vec!['/'];
+
+        // Markers are never split after their chars
+        for (input, line_width) in [
+            ("<b>abcdef</b>gh", 2),
+            ("a</b>bcdef", 1),
+            ("<b>ab/cdef</b>/gh", 3),
+        ] {
+            options.line_width = line_width;
+            let output = transform_with_options(input, &options);
+            assert_eq!(
+                verify_invariants(input, &options, &output),
+                Ok(()),
+                "{output:?}"
+            );
+        }
+        options.line_width = 2;
+        assert_eq!(
+            transform_with_options("<b>abcdef</b>gh", &options),
+            "<b>ab\ncd\nef</b>\ngh"
+        );
+        options.line_width = 3;
+        assert_eq!(
+            transform_with_options("<b>ab/cdef</b>/gh", &options),
+            "<b>ab/\ncde\nf</b>/ \ngh "
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::{fmt, str::FromStr};
+
+/// Settings for [`crate::transform_with_options`].
+///
+/// Construct with [`TransformOptions::new`] and adjust the public fields as needed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransformOptions {
+    /// Width of every output line, in chars. Includes any gutter
+    /// produced by `number_lines`.
+    pub line_width: u32,
+    /// Unit of `line_width` and of every other width, and of the width of
+    /// words.
+    pub width_mode: WidthMode,
+    /// When set, the first line of every paragraph is this many chars
+    /// wide instead of `line_width`, e.g. to leave room for a label.
+    /// Includes the gutter like `line_width` does.
+    ///
+    /// A word that fits `line_width` but not the first line starts on the
+    /// second line, leaving the first one blank. Checked by
+    /// [`crate::try_transform_with_options`] like `line_width`.
+    pub initial_width: Option<u32>,
+    /// When set, lines are padded with spaces to this many chars, and the
+    /// block of lines is placed within the field by `block_align`. Lines
+    /// keep their own alignment within `line_width`, as every line gets
+    /// the same padding before it. Like margins, empty lines stay empty and last
+    /// lines of paragraphs get no padding after them without
+    /// `pad_last_line`.
+    ///
+    /// A field narrower than `line_width` or `initial_width` adds no
+    /// padding before the lines, and
+    /// [`crate::try_transform_with_options`] rejects it.
+    pub field_width: Option<u32>,
+    /// Placement of the block of lines within `field_width`.
+    pub block_align: BlockAlignment,
+    /// Spaces written before every line, outside of `line_width`, of the
+    /// line number gutter and of `field_width`.
+    pub margin_left: usize,
+    /// Spaces written after every line, outside of `line_width`. Empty
+    /// lines get no margins, and last lines of paragraphs get no right
+    /// margin without `pad_last_line`, so no line ends with whitespace
+    /// that is not padding.
+    pub margin_right: usize,
+    /// How words are placed within a line.
+    pub align: Alignment,
+    /// When `false`, the last line of a paragraph is neither padded
+    /// nor justified: words are separated by single spaces.
+    pub pad_last_line: bool,
+    /// When `true`, input without words, empty or whitespace-only, gives a
+    /// single blank line of `line_width` spaces instead of no lines. The
+    /// line is padded like a last line, so it is empty without
+    /// `pad_last_line`.
+    pub blank_line_for_empty: bool,
+    /// When `true`, whitespace-only lines separate paragraphs. Every
+    /// paragraph is adjusted on its own and followed by an empty line.
+    pub preserve_paragraphs: bool,
+    /// With `preserve_paragraphs`, input lines matching this filter are
+    /// copied to the output as they are, without their line breaks. They
+    /// are neither wrapped nor padded, and end the paragraph before them.
+    /// Ignored without `preserve_paragraphs`.
+    pub skip_line: Option<LineFilter>,
+    /// When `true`, whitespace before `;`, `:`, `!`, `?` and `»` and after
+    /// `«` is kept as a single space that neither breaks nor stretches, so
+    /// these marks never start a line. Whitespace that ends a paragraph
+    /// still breaks, and words joined this way are split like any other
+    /// word when they do not fit a whole line.
+    pub french_spacing: bool,
+    /// With `french_spacing`, such spaces are written as U+202F narrow
+    /// no-break spaces.
+    pub french_narrow_spaces: bool,
+    /// Pairs of opening and closing delimiters, e.g. `('(', ')')` or
+    /// `('"', '"')`, of phrases that are not broken across lines. A phrase
+    /// is wrapped as a single word together with the words it touches,
+    /// unless it is wider than `line_width` or has a paragraph break in it,
+    /// then it is broken as usual. Phrases do not nest: a phrase ends at
+    /// the first closing delimiter after its start.
+    pub atomic_delimiters: Vec<(char, char)>,
+    /// When `true`, a space is added between a CJK char, i.e. a Han
+    /// ideograph or kana, and a Latin letter or digit right next to it,
+    /// e.g. `使用Rust编写` is wrapped as `使用 Rust 编写`. Such spaces
+    /// stretch and break like any other. Chars that already have
+    /// whitespace between them, CJK punctuation and lines matched by
+    /// `skip_line` are left as they are.
+    pub cjk_latin_spacing: bool,
+    /// Chars a word wider than a line is split after, when there is one
+    /// that fits the line, e.g. `_` or `/`. The char stays at the end of
+    /// the line and the line is padded as usual. Otherwise a word is split
+    /// after as many chars as fit. Such a word is split after en and em
+    /// dashes too, but never after U+2011 non-breaking hyphen or before
+    /// U+2060 word joiner, even when they are listed here.
+    pub break_after_chars: Vec<char>,
+    /// When set, occurrences of this marker in words, e.g. `<wbr>`, are
+    /// left out of the output and take no width. A word wider than a line
+    /// is split right after the last one that fits the line, if any,
+    /// instead of after as many chars as fit. Lines matched by `skip_line`
+    /// keep theirs.
+    pub break_hint: Option<String>,
+    /// When `true`, a line aligned to the right edge, i.e. justified with
+    /// more than one word or right-aligned, that ends with one of
+    /// `hanging_punctuation` has it past `line_width`. The rest of the line
+    /// is laid out as if the char took no width, so such lines are wider
+    /// by the char.
+    pub hang_punctuation: bool,
+    /// Chars that hang with `hang_punctuation`. Defaults to
+    /// [`TransformOptions::DEFAULT_HANGING_PUNCTUATION`].
+    pub hanging_punctuation: Vec<char>,
+    /// When set, these words do not end a line that is followed by
+    /// another line of the paragraph, e.g. one-letter prepositions. They
+    /// start the next line instead, except for the first word of a line.
+    /// Words are whole whitespace-separated tokens, punctuation included.
+    pub orphan_words: Option<Vec<String>>,
+    /// When `true`, `orphan_words` match regardless of case.
+    pub orphan_words_ignore_case: bool,
+    /// When `true`, a last line of a paragraph that would hold a single
+    /// word gets the last word of the line before it too, when both fit
+    /// and the line before keeps at least two words. Other lines stay
+    /// the same.
+    pub avoid_orphan_last_line: bool,
+    /// When set, markers of inline spans, e.g. escape sequences a
+    /// renderer consumes, take no width and stay with the text they mark.
+    pub markup: Option<MarkupConfig>,
+    /// Written between output lines.
+    pub line_ending: LineEnding,
+    /// When `true`, `line_ending` is written after the last line as well.
+    /// Output without lines stays empty.
+    pub final_newline: bool,
+    /// When set, every output line is prefixed with a line number gutter.
+    pub number_lines: Option<NumberStyle>,
+    /// When set, output is split into pages of at most this many lines.
+    pub lines_per_page: Option<usize>,
+    /// Written between pages. Defaults to a form feed on its own line.
+    pub page_separator: String,
+    /// When set, text beyond this many lines is dropped, as `truncation`
+    /// says. Counts lines of text only, without page separators, and
+    /// includes empty lines between paragraphs.
+    pub max_lines: Option<usize>,
+    /// How the last line kept by `max_lines` shows that text is cut.
+    pub truncation: Truncation,
+    /// When set, blank lines are added until there are this many lines of
+    /// text. Blank lines are `line_width` spaces, or empty without
+    /// `pad_last_line`.
+    pub min_lines: Option<usize>,
+    /// Where text goes among the blank lines added by `min_lines`.
+    pub vertical_align: VerticalAlignment,
+    /// Largest `line_width` accepted by [`crate::try_transform_with_options`],
+    /// and largest `line_width` together with the margins.
+    /// Every line is padded to the full width, so a huge width with tiny
+    /// input still allocates gigabytes of spaces.
+    pub max_width: u32,
+    /// When set, [`crate::try_transform_with_options`] fails as soon as the
+    /// output grows beyond this many bytes.
+    pub max_output_bytes: Option<usize>,
+}
+
+impl TransformOptions {
+    /// Default of [`TransformOptions::max_width`].
+    pub const DEFAULT_MAX_WIDTH: u32 = 1 << 20;
+
+    /// Default of [`TransformOptions::hanging_punctuation`]: commas,
+    /// periods, colons, semicolons, hyphens and closing quotation marks.
+    pub const DEFAULT_HANGING_PUNCTUATION: &'static [char] =
+        &[',', '.', ':', ';', '-', '\'', '"', '’', '”', '»'];
+
+    pub fn new(line_width: u32) -> Self {
+        TransformOptions {
+            line_width,
+            width_mode: WidthMode::Chars,
+            initial_width: None,
+            field_width: None,
+            block_align: BlockAlignment::Left,
+            margin_left: 0,
+            margin_right: 0,
+            align: Alignment::Justify,
+            pad_last_line: true,
+            blank_line_for_empty: false,
+            preserve_paragraphs: false,
+            skip_line: None,
+            french_spacing: false,
+            french_narrow_spaces: false,
+            atomic_delimiters: Vec::new(),
+            cjk_latin_spacing: false,
+            break_after_chars: Vec::new(),
+            break_hint: None,
+            hang_punctuation: false,
+            hanging_punctuation: Vec::from(TransformOptions::DEFAULT_HANGING_PUNCTUATION),
+            orphan_words: None,
+            orphan_words_ignore_case: false,
+            avoid_orphan_last_line: false,
+            markup: None,
+            line_ending: LineEnding::Lf,
+            final_newline: false,
+            number_lines: None,
+            lines_per_page: None,
+            page_separator: String::from("\u{0C}\n"),
+            max_lines: None,
+            truncation: Truncation::Clip,
+            min_lines: None,
+            vertical_align: VerticalAlignment::Top,
+            max_width: TransformOptions::DEFAULT_MAX_WIDTH,
+            max_output_bytes: None,
+        }
+    }
+}
+
+/// Describes the line number gutter.
+///
+/// Numbers are right-aligned to the width of the largest number printed,
+/// followed by `separator`. The gutter is taken from the line width, so
+/// output lines stay exactly `line_width` chars long.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberStyle {
+    /// Number of the first line.
+    pub start: usize,
+    /// Written between the number and the text.
+    pub separator: String,
+    /// When `false`, only the first line of a paragraph gets a number.
+    /// Continuation lines get spaces in place of the number.
+    pub number_continuation_lines: bool,
+}
+
+impl Default for NumberStyle {
+    fn default() -> Self {
+        NumberStyle {
+            start: 1,
+            separator: String::from(" │ "),
+            number_continuation_lines: true,
+        }
+    }
+}
+
+/// How widths are counted, see [`TransformOptions::width_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WidthMode {
+    /// Every char takes a column.
+    #[default]
+    Chars,
+    /// Widths are in full-width units, as in Japanese layout: CJK chars,
+    /// kana, hangul, fullwidth forms and emoji take a unit, other chars
+    /// half of it. Lines are laid out in half units, so gaps and padding
+    /// are spaces of half a unit each. Margins are in units too, and the
+    /// gutter of `number_lines` takes half a unit per char.
+    HalfWidthUnits,
+}
+
+/// Placement of words within a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Alignment {
+    /// Single spaces between words, padding after the last word.
+    Left,
+    /// Single spaces between words, padding before the first word.
+    Right,
+    /// Single spaces between words, padding split around them.
+    Center,
+    /// Gaps between words are stretched to fill the whole line.
+    #[default]
+    Justify,
+}
+
+impl FromStr for Alignment {
+    type Err = ParseAlignmentError;
+
+    /// Parses `left`, `right`, `center` or `justify`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => Ok(Alignment::Left),
+            "right" => Ok(Alignment::Right),
+            "center" => Ok(Alignment::Center),
+            "justify" => Ok(Alignment::Justify),
+            other => Err(ParseAlignmentError(other.to_string())),
+        }
+    }
+}
+
+/// Returned when a string does not name an [`Alignment`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAlignmentError(String);
+
+impl fmt::Display for ParseAlignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown alignment '{}'", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseAlignmentError {}
+
+/// Placement of the block of lines within
+/// [`TransformOptions::field_width`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlockAlignment {
+    /// Field padding goes after the lines.
+    #[default]
+    Left,
+    /// Field padding is split around the lines, the extra space goes after them.
+    Center,
+    /// Field padding goes before the lines.
+    Right,
+}
+
+/// Predicate over input lines, see [`TransformOptions::skip_line`].
+///
+/// Filters are only equal to their own clones.
+#[derive(Clone)]
+pub struct LineFilter(Arc<dyn Fn(&str) -> bool + Send + Sync>);
+
+impl LineFilter {
+    pub fn new(filter: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        LineFilter(Arc::new(filter))
+    }
+
+    /// Whether `line`, given without its line break, matches.
+    pub fn matches(&self, line: &str) -> bool {
+        (self.0)(line)
+    }
+}
+
+impl fmt::Debug for LineFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LineFilter(..)")
+    }
+}
+
+impl PartialEq for LineFilter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for LineFilter {}
+
+/// Markers of inline spans, see [`TransformOptions::markup`].
+///
+/// Markers are matched as they are, and must not contain whitespace, so
+/// they are always part of a word. A marker at the start or the end of a
+/// word marks the whole word, and a span of several words may end on a
+/// later line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkupConfig {
+    /// Starts a span.
+    pub open: String,
+    /// Ends a span. When it is the same as `open`, every marker starts a
+    /// span.
+    pub close: String,
+    /// When `true`, spans open at the end of a line are closed there and
+    /// opened again on the next line, so every line can be shown on its
+    /// own. Spans still open at the end of a paragraph are closed there
+    /// for good. Otherwise lines are left as they are and spans cross line
+    /// breaks.
+    pub reopen_on_wrap: bool,
+    /// When `true`, OSC 8 hyperlinks, i.e. `ESC ] 8 ; params ; URI ST`
+    /// where `ST` is `ESC \` or BEL, are markers too: one with a URI
+    /// starts a link, one without ends it. A link opened again on the next
+    /// line, with `reopen_on_wrap`, gets the same URI and params.
+    pub hyperlinks: bool,
+}
+
+/// Marks text cut by [`TransformOptions::max_lines`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Truncation {
+    /// Lines after the last one kept are dropped.
+    #[default]
+    Clip,
+    /// The last line kept ends with this text, when text is cut. Words,
+    /// and then chars of the last word, are dropped from the end of the
+    /// line until the text fits, and the line is padded as usual.
+    Ellipsis(String),
+}
+
+/// Placement of text among the blank lines added by
+/// [`TransformOptions::min_lines`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerticalAlignment {
+    /// Blank lines go below the text.
+    #[default]
+    Top,
+    /// Blank lines are split around the text, the extra one goes below.
+    Middle,
+    /// Blank lines go above the text.
+    Bottom,
+}
+
+/// Separator between output lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
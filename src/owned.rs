@@ -0,0 +1,257 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    transform_with_options, LineEnding, LinePart, Lines, TransformOptions, WidthMode, NEWLINE_STR,
+};
+
+/// Same as [`crate::transform`], but takes ownership of `input` and writes
+/// the output into its allocation when possible.
+pub fn transform_owned(input: String, line_width: u32) -> String {
+    transform_owned_with_options(input, &TransformOptions::new(line_width))
+}
+
+/// Same as [`crate::transform_with_options`], but takes ownership of `input`
+/// and writes the output into its allocation when possible.
+///
+/// The allocation is reused when no word of the output starts after the
+/// same word in `input`, so words can be moved towards the start of the
+/// buffer one by one. That is common for text that loses more whitespace
+/// than it gains padding, e.g. left-aligned text without `pad_last_line`.
+/// Otherwise, or with `number_lines`, `lines_per_page`, `max_lines`,
+/// `min_lines`, `field_width`, margins, `french_spacing`,
+/// `atomic_delimiters`, `cjk_latin_spacing`, `markup`, `break_hint`,
+/// `width_mode` other than `Chars` or a line ending other than `Lf`, a new
+/// string is allocated.
+pub fn transform_owned_with_options(input: String, options: &TransformOptions) -> String {
+    let plain = options.number_lines.is_none()
+        && options.lines_per_page.is_none()
+        && options.max_lines.is_none()
+        && options.min_lines.is_none()
+        && options.field_width.is_none()
+        && options.margin_left == 0
+        && options.margin_right == 0
+        && !options.french_spacing
+        && options.atomic_delimiters.is_empty()
+        && !options.cjk_latin_spacing
+        && options.width_mode == WidthMode::Chars
+        && options.markup.is_none()
+        && options.break_hint.is_none()
+        && options.line_ending == LineEnding::Lf;
+
+    let Some((ops, len)) = plain.then(|| plan_in_place(&input, options)).flatten() else {
+        return transform_with_options(&input, options);
+    };
+    let mut result = write_in_place(input, &ops, len);
+    if options.final_newline && !result.is_empty() {
+        result += NEWLINE_STR;
+    }
+    result
+}
+
+/// Step of moving the output into the input buffer.
+#[derive(Debug, PartialEq, Eq)]
+enum Op {
+    /// Moves `len` bytes at `src` of the input to the end of the output
+    Copy { src: usize, len: usize },
+    /// Appends `len` copies of `byte` to the output
+    Fill { byte: u8, len: usize },
+}
+
+/// Lists steps that build the output of `input` in place together with
+/// its length, or returns `None` when the output would overwrite input
+/// that is not moved yet.
+fn plan_in_place(input: &str, options: &TransformOptions) -> Option<(Vec<Op>, usize)> {
+    let mut ops = Vec::new();
+    let mut len = 0;
+    let mut fits = true;
+    let mut has_lines = false;
+
+    let mut lines = Lines::new(input, options, options.line_width as usize);
+    while let Some(plan) = lines.next_plan() {
+        if has_lines {
+            push_fill(&mut ops, NEWLINE_STR.as_bytes()[0], NEWLINE_STR.len());
+            len += NEWLINE_STR.len();
+        }
+        has_lines = true;
+
+        plan.for_each_part(|part| match part {
+            LinePart::Spaces(count) => {
+                push_fill(&mut ops, b' ', count);
+                len += count;
+            }
+            LinePart::Word(word) if !word.is_empty() => {
+                let src = word.as_ptr() as usize - input.as_ptr() as usize;
+                fits &= len <= src;
+                push_copy(&mut ops, input.as_bytes(), src, word.len());
+                len += word.len();
+            }
+            LinePart::Word(_) => {}
+        });
+
+        if !fits {
+            return None;
+        }
+    }
+
+    Some((ops, len))
+}
+
+fn push_fill(ops: &mut Vec<Op>, byte: u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+
+    match ops.last_mut() {
+        Some(Op::Fill {
+            byte: last,
+            len: last_len,
+        }) if *last == byte => *last_len += len,
+        _ => ops.push(Op::Fill { byte, len }),
+    }
+}
+
+/// Adds a copy of `len` bytes at `src` of `input`. When the fills since
+/// the previous copy write the same bytes that are between both words in
+/// `input`, they are merged into a single copy.
+fn push_copy(ops: &mut Vec<Op>, input: &[u8], src: usize, len: usize) {
+    if len == 0 {
+        return;
+    }
+
+    let Some(copy_idx) = ops.iter().rposition(|op| matches!(op, Op::Copy { .. })) else {
+        ops.push(Op::Copy { src, len });
+        return;
+    };
+    let Op::Copy {
+        src: prev_src,
+        len: prev_len,
+    } = ops[copy_idx]
+    else {
+        unreachable!("Found by the position above");
+    };
+
+    let mut between = input[prev_src + prev_len..src].iter();
+    let same = ops[copy_idx + 1..].iter().all(|op| match op {
+        Op::Fill { byte, len } => {
+            between.by_ref().take(*len).filter(|b| *b == byte).count() == *len
+        }
+        Op::Copy { .. } => unreachable!("Position of the last copy"),
+    }) && between.next().is_none();
+
+    if same {
+        ops.truncate(copy_idx + 1);
+        ops[copy_idx] = Op::Copy {
+            src: prev_src,
+            len: src + len - prev_src,
+        };
+    } else {
+        ops.push(Op::Copy { src, len });
+    }
+}
+
+fn write_in_place(input: String, ops: &[Op], len: usize) -> String {
+    let mut bytes = input.into_bytes();
+    if bytes.len() < len {
+        // Only padding after the last word may go past the input
+        bytes.resize(len, b' ');
+    }
+
+    // Every copy moves bytes towards the start and no write reaches
+    // input bytes that are still to be copied
+    let mut end = 0;
+    for op in ops {
+        match *op {
+            Op::Copy { src, len } => bytes.copy_within(src..src + len, end),
+            Op::Fill { byte, len } => bytes[end..end + len].fill(byte),
+        }
+        end += match *op {
+            Op::Copy { len, .. } | Op::Fill { len, .. } => len,
+        };
+    }
+    bytes.truncate(len);
+
+    String::from_utf8(bytes).expect("Whole words and ASCII bytes are written")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plan_in_place, transform_owned, transform_owned_with_options, Op};
+    use crate::{transform, transform_with_options, Alignment, NumberStyle, TransformOptions};
+
+    const INPUT: &str = "Вез  корабль карамель,\n  наскочил корабль на мель,   матросы две недели \
+                         карамель на мели ели.\n\nТридцатитрёхбуквенный 🤩 hello";
+
+    #[test]
+    fn matches_transform() {
+        for align in [
+            Alignment::Left,
+            Alignment::Right,
+            Alignment::Center,
+            Alignment::Justify,
+        ] {
+            for line_width in [1, 3, 7, 12, 30, 100] {
+                let mut options = TransformOptions::new(line_width);
+                options.align = align;
+                options.pad_last_line = line_width % 2 == 0;
+                options.preserve_paragraphs = line_width > 10;
+                options.final_newline = line_width % 3 == 0;
+
+                let result = transform_owned_with_options(INPUT.to_string(), &options);
+                assert_eq!(result, transform_with_options(INPUT, &options));
+            }
+        }
+
+        let mut options = TransformOptions::new(10);
+        options.number_lines = Some(NumberStyle::default());
+        let result = transform_owned_with_options(INPUT.to_string(), &options);
+        assert_eq!(result, transform_with_options(INPUT, &options));
+    }
+
+    #[test]
+    fn reuses_allocation() {
+        let mut options = TransformOptions::new(20);
+        options.align = Alignment::Left;
+        options.pad_last_line = false;
+
+        let input = String::from("Short   words\t\tmoving  left\n\n  to fill the   lines");
+        let ptr = input.as_ptr();
+        let result = transform_owned_with_options(input, &options);
+        assert_eq!(result, "Short words moving  \nleft to fill the    \nlines");
+        assert_eq!(result.as_ptr(), ptr);
+
+        // Justified gaps fit into the whitespace they replace
+        let input = String::from("one   two    three four");
+        let ptr = input.as_ptr();
+        let result = transform_owned(input, 20);
+        assert_eq!(result, transform("one   two    three four", 20));
+        assert_eq!(result.as_ptr(), ptr);
+
+        // Padding of the very first line moves words past their source
+        let input = String::from("Short words");
+        let ptr = input.as_ptr();
+        let result = transform_owned(input, 20);
+        assert_eq!(result, "Short          words");
+        assert_ne!(result.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn merges_copies() {
+        let mut options = TransformOptions::new(8);
+        options.align = Alignment::Left;
+        options.pad_last_line = false;
+
+        // Padding and newline of the first line are already in the input
+        let (ops, len) = plan_in_place("one two \nthree     four", &options).unwrap();
+        let expected = [
+            Op::Copy { src: 0, len: 14 },
+            Op::Fill { byte: b' ', len: 3 },
+            Op::Fill {
+                byte: b'\n',
+                len: 1,
+            },
+            Op::Copy { src: 19, len: 4 },
+        ];
+        assert_eq!(ops, expected);
+        assert_eq!(len, 22);
+    }
+}
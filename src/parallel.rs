@@ -0,0 +1,116 @@
+use rayon::prelude::*;
+
+use crate::{adjust, transform_by, Paragraphs, TransformError, TransformOptions};
+
+/// Same as [`crate::transform_with_options`], but paragraphs are adjusted
+/// on the rayon thread pool. Output is identical to the sequential version.
+///
+/// Paragraphs are only independent when `options.preserve_paragraphs` is
+/// set and neither `options.max_lines` nor `options.skip_line` are.
+/// Otherwise the input is adjusted on the calling thread.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn transform_par(input: &str, options: &TransformOptions) -> String {
+    transform_by(input, options, adjust_par, None).expect("Output size is not limited")
+}
+
+fn adjust_par(
+    input: &str,
+    options: &TransformOptions,
+    line_width: usize,
+    limit: Option<usize>,
+) -> Result<String, TransformError> {
+    let independent = options.max_lines.is_none() && options.skip_line.is_none();
+    if !options.preserve_paragraphs || !independent {
+        return adjust(input, options, line_width, limit);
+    }
+
+    let paragraphs: Vec<&str> = Paragraphs::new(input, true).collect();
+    if paragraphs.is_empty() {
+        // Blank input may still give a blank line
+        return adjust(input, options, line_width, limit);
+    }
+    let mut adjusted: Vec<String> = paragraphs
+        .par_iter()
+        .map(|paragraph| adjust(paragraph, options, line_width, limit))
+        .collect::<Result<_, _>>()?;
+    // Paragraphs of break hints alone come out empty, like they do there
+    adjusted.retain(|paragraph| !paragraph.is_empty());
+    Ok(adjusted.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transform_par;
+    use crate::{transform_with_options, Alignment, NumberStyle, TransformOptions};
+
+    #[test]
+    fn same_as_sequential() {
+        let words = [
+            "Съешь",
+            "ещё",
+            "этих",
+            "мягких",
+            "французских",
+            "булок,",
+            "да",
+            "выпей",
+            "чаю",
+        ];
+        let mut input = String::new();
+        for idx in 0..2_000 {
+            input += words[idx % words.len()];
+            input += match idx % 13 {
+                0 => "\n\n",
+                5 => "\n \t\n\n",
+                _ => " ",
+            };
+        }
+
+        for line_width in [1, 7, 40] {
+            let mut options = TransformOptions::new(line_width);
+            options.preserve_paragraphs = true;
+            assert_eq!(
+                transform_par(&input, &options),
+                transform_with_options(&input, &options)
+            );
+
+            options.align = Alignment::Center;
+            options.pad_last_line = false;
+            options.number_lines = Some(NumberStyle::default());
+            options.lines_per_page = Some(30);
+            assert_eq!(
+                transform_par(&input, &options),
+                transform_with_options(&input, &options)
+            );
+
+            options.max_lines = Some(40);
+            assert_eq!(
+                transform_par(&input, &options),
+                transform_with_options(&input, &options)
+            );
+
+            let mut hinted = TransformOptions::new(line_width);
+            hinted.preserve_paragraphs = true;
+            hinted.break_hint = Some(String::from("%%"));
+            for input in ["a b\n\n%%\n\nc d", "%%\n\na b", "%% %%"] {
+                assert_eq!(
+                    transform_par(input, &hinted),
+                    transform_with_options(input, &hinted)
+                );
+            }
+
+            options.preserve_paragraphs = false;
+            assert_eq!(
+                transform_par(&input, &options),
+                transform_with_options(&input, &options)
+            );
+
+            options.blank_line_for_empty = true;
+            assert_eq!(
+                transform_par(" \n\t", &options),
+                transform_with_options(" \n\t", &options)
+            );
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{measure, tokens, LinePlan, Lines, TransformOptions, Word, NEWLINE_STR};
+
+/// Text split into words and measured once, to be wrapped like
+/// [`crate::transform`] does at many widths.
+#[derive(Debug, Clone)]
+pub struct PreparedText<'a> {
+    input: &'a str,
+    words: Vec<Word<'a>>,
+    measure: for<'b> fn(&'b str) -> Word<'b>,
+    options: TransformOptions,
+}
+
+impl<'a> PreparedText<'a> {
+    pub fn new(input: &'a str) -> Self {
+        PreparedText::with_measure(input, measure(input))
+    }
+
+    fn with_measure(input: &'a str, measure: for<'b> fn(&'b str) -> Word<'b>) -> Self {
+        PreparedText {
+            input,
+            words: tokens(input).map(measure).collect(),
+            measure,
+            // Width is given to every line plan
+            options: TransformOptions::new(0),
+        }
+    }
+
+    /// Same as [`crate::transform`] of the input at `line_width`.
+    ///
+    /// Panics when `line_width` is 0 and the input is not blank.
+    pub fn wrap(&self, line_width: u32) -> String {
+        let mut result = String::with_capacity(self.input.len() + line_width as usize);
+        let mut lines = self.plan(line_width);
+        if let Some(plan) = lines.next_plan() {
+            plan.push(&mut result);
+        }
+        while let Some(plan) = lines.next_plan() {
+            result += NEWLINE_STR;
+            plan.push(&mut result);
+        }
+        result
+    }
+
+    /// Lines of [`PreparedText::wrap`], without line endings, one by one.
+    ///
+    /// Panics when `line_width` is 0 and the input is not blank.
+    pub fn lines(&self, line_width: u32) -> impl Iterator<Item = String> + '_ {
+        let mut lines = self.plan(line_width);
+        core::iter::from_fn(move || {
+            lines.next_plan().map(|plan: LinePlan<'_>| {
+                let mut line = String::with_capacity(plan.len());
+                plan.push(&mut line);
+                line
+            })
+        })
+    }
+
+    fn plan(&self, line_width: u32) -> Lines<'_> {
+        let line_width = line_width as usize;
+        Lines::prepared(
+            self.input,
+            &self.words,
+            &self.options,
+            line_width,
+            self.measure,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::PreparedText;
+    use crate::{transform, Word};
+
+    const INPUT: &str =
+        "Съешь ещё  этих мягких\tфранцузских булок,\n\nда выпей 🤩 Тридцатитрёхбуквенный";
+
+    #[test]
+    fn same_as_transform() {
+        for input in [INPUT, "Lorem ipsum dolor sit amet", "", " \n "] {
+            let prepared = PreparedText::new(input);
+            for line_width in [1, 3, 7, 12, 30, 100] {
+                let expected = transform(input, line_width);
+                assert_eq!(prepared.wrap(line_width), expected);
+                assert!(prepared.lines(line_width).eq(expected.lines()));
+            }
+        }
+    }
+
+    #[test]
+    fn measured_once() {
+        static MEASURED: AtomicUsize = AtomicUsize::new(0);
+        fn counting(text: &str) -> Word<'_> {
+            MEASURED.fetch_add(1, Ordering::Relaxed);
+            Word::new(text)
+        }
+
+        let prepared = PreparedText::with_measure(INPUT, counting);
+        let n_words = MEASURED.load(Ordering::Relaxed);
+        assert_eq!(n_words, 10);
+        for line_width in 1..=40 {
+            assert_eq!(prepared.wrap(line_width), transform(INPUT, line_width));
+            assert_eq!(
+                prepared.lines(line_width).count(),
+                transform(INPUT, line_width).lines().count()
+            );
+        }
+        assert_eq!(MEASURED.load(Ordering::Relaxed), n_words);
+    }
+}
@@ -0,0 +1,170 @@
+//! Python bindings, enabled by the `python` feature.
+//!
+//! Build the extension module with `maturin develop`. Panics surface as
+//! `pyo3_runtime.PanicException`, invalid arguments as `ValueError`.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{try_transform_with_options, Alignment, TransformError, TransformOptions};
+
+impl From<TransformError> for PyErr {
+    fn from(err: TransformError) -> Self {
+        PyValueError::new_err(err.to_string())
+    }
+}
+
+/// Mirrors [`TransformOptions`].
+#[pyclass(name = "Options", from_py_object)]
+#[derive(Clone)]
+struct PyOptions {
+    options: TransformOptions,
+}
+
+#[pymethods]
+impl PyOptions {
+    #[new]
+    #[pyo3(signature = (width, align = "justify", pad_last_line = true, preserve_paragraphs = false))]
+    fn new(
+        width: u32,
+        align: &str,
+        pad_last_line: bool,
+        preserve_paragraphs: bool,
+    ) -> PyResult<Self> {
+        let mut options = TransformOptions::new(width);
+        options.align = parse_align(align)?;
+        options.pad_last_line = pad_last_line;
+        options.preserve_paragraphs = preserve_paragraphs;
+        Ok(PyOptions { options })
+    }
+
+    #[getter]
+    fn width(&self) -> u32 {
+        self.options.line_width
+    }
+
+    #[setter]
+    fn set_width(&mut self, width: u32) {
+        self.options.line_width = width;
+    }
+
+    #[getter]
+    fn align(&self) -> &'static str {
+        match self.options.align {
+            Alignment::Left => "left",
+            Alignment::Right => "right",
+            Alignment::Center => "center",
+            Alignment::Justify => "justify",
+        }
+    }
+
+    #[setter]
+    fn set_align(&mut self, align: &str) -> PyResult<()> {
+        self.options.align = parse_align(align)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn pad_last_line(&self) -> bool {
+        self.options.pad_last_line
+    }
+
+    #[setter]
+    fn set_pad_last_line(&mut self, pad_last_line: bool) {
+        self.options.pad_last_line = pad_last_line;
+    }
+
+    #[getter]
+    fn preserve_paragraphs(&self) -> bool {
+        self.options.preserve_paragraphs
+    }
+
+    #[setter]
+    fn set_preserve_paragraphs(&mut self, preserve_paragraphs: bool) {
+        self.options.preserve_paragraphs = preserve_paragraphs;
+    }
+
+    /// Adjusts `text` according to these options.
+    fn transform(&self, text: &str) -> PyResult<String> {
+        Ok(try_transform_with_options(text, &self.options)?)
+    }
+}
+
+fn parse_align(align: &str) -> PyResult<Alignment> {
+    align
+        .parse()
+        .map_err(|err| PyValueError::new_err(format!("{err}")))
+}
+
+#[pymodule]
+mod line_adjustment {
+    use pyo3::prelude::*;
+
+    use crate::{try_transform_with_options, TransformOptions};
+
+    #[pymodule_export]
+    use super::PyOptions;
+
+    /// Adjusts `text` to lines of `width` chars.
+    #[pyfunction]
+    fn transform(text: &str, width: u32) -> PyResult<String> {
+        Ok(try_transform_with_options(
+            text,
+            &TransformOptions::new(width),
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::exceptions::PyValueError;
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+
+    use super::line_adjustment;
+
+    fn run(code: &str) -> PyResult<()> {
+        Python::initialize();
+        Python::attach(|py| {
+            let module = pyo3::wrap_pymodule!(line_adjustment)(py);
+
+            let globals = PyDict::new(py);
+            globals.set_item("line_adjustment", module)?;
+            let code = std::ffi::CString::new(code).unwrap();
+            py.run(&code, Some(&globals), None)
+        })
+    }
+
+    #[test]
+    fn transform_unicode() {
+        run(r#"
+assert line_adjustment.transform("Съешь ещё этих мягких", 12) == "Съешь    ещё\nэтих  мягких"
+assert line_adjustment.transform("🤩 привет", 3) == "🤩  \nпри\nвет"
+"#)
+        .unwrap();
+    }
+
+    #[test]
+    fn options_class() {
+        run(r#"
+options = line_adjustment.Options(12, align="right", pad_last_line=False)
+assert options.width == 12
+assert options.align == "right"
+assert options.transform("Съешь ещё этих") == "   Съешь ещё\n        этих"
+
+options.align = "left"
+options.preserve_paragraphs = True
+assert options.transform("one\n\ntwo") == "one\n\ntwo"
+"#)
+        .unwrap();
+    }
+
+    #[test]
+    fn zero_width_raises_value_error() {
+        let err = run(r#"line_adjustment.transform("text", 0)"#).unwrap_err();
+        Python::attach(|py| assert!(err.is_instance_of::<PyValueError>(py)));
+
+        let err = run(r#"line_adjustment.Options(10, align="middle")"#).unwrap_err();
+        Python::attach(|py| assert!(err.is_instance_of::<PyValueError>(py)));
+    }
+}
@@ -0,0 +1,77 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{badness, transform, Paragraphs, TransformOptions};
+
+/// Same as [`crate::transform`] with `preserve_paragraphs`, but every
+/// paragraph is justified at the width from `min_width` to `max_width`
+/// that gives it the lowest [`crate::badness`], and the wider one of
+/// widths that score the same. Returns the output with the width of
+/// every paragraph in input order.
+///
+/// All lines of a paragraph are as wide as the width chosen for it,
+/// except empty lines between paragraphs.
+///
+/// Panics when `min_width` is greater than `max_width`, or is 0 and
+/// `input` is not blank.
+pub fn transform_ranged(input: &str, min_width: u32, max_width: u32) -> (String, Vec<u32>) {
+    assert!(min_width <= max_width, "width range must not be empty");
+
+    let mut result = String::new();
+    let mut widths = Vec::new();
+    for paragraph in Paragraphs::new(input, true) {
+        let scores = (min_width..=max_width)
+            .map(|width| (badness(paragraph, &TransformOptions::new(width)), width));
+        let (_, width) = scores
+            .reduce(|best, score| match score.0 <= best.0 {
+                true => score,
+                false => best,
+            })
+            .expect("Range is not empty");
+
+        if !widths.is_empty() {
+            result += "\n\n";
+        }
+        result += &transform(paragraph, width);
+        widths.push(width);
+    }
+    (result, widths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transform_ranged;
+    use crate::{badness, transform, TransformOptions};
+
+    #[test]
+    fn narrower_width() {
+        // "Тридцатитрёхбуквенный\nи               слово" fills the first line
+        let input = "Тридцатитрёхбуквенный и слово";
+        let (result, widths) = transform_ranged(input, 21, 23);
+        assert_eq!(widths, [21]);
+        assert_eq!(result, transform(input, 21));
+        assert!(badness(input, &TransformOptions::new(23)) > 0.0);
+
+        // Lines split a word at narrower widths
+        assert_eq!(transform_ranged(input, 15, 21).1, [21]);
+        assert_eq!(transform_ranged(input, 15, 15).1, [15]);
+    }
+
+    #[test]
+    fn every_paragraph() {
+        let input = "Съешь ещё этих мягких французских булок\n\n \nТридцатитрёхбуквенный и слово\n";
+        let (result, widths) = transform_ranged(input, 10, 12);
+        assert_eq!(widths, [11, 10]);
+        assert_eq!(
+            result,
+            "Съешь   ещё\nэтих мягких\nфранцузских\nбулок      \n\n\
+             Тридцатитр\nёхбуквенны\nй  и слово"
+        );
+
+        // Widths that score the same give the wider one
+        assert_eq!(
+            transform_ranged("Съешь ещё", 9, 12),
+            (String::from("Съешь    ещё"), vec![12])
+        );
+        assert_eq!(transform_ranged(" \n", 0, 12), (String::new(), vec![]));
+    }
+}
@@ -0,0 +1,151 @@
+use std::io::{self, BufRead, Read};
+
+use crate::{adjuster::Adjuster, TransformOptions};
+
+/// Reads text from `inner` and produces it adjusted like [`crate::transform`].
+///
+/// Input is consumed chunk by chunk as output is read, so the whole
+/// output is never kept in memory. At the end of `inner` the last line is
+/// produced according to `pad_last_line`.
+///
+/// Reading fails with [`io::ErrorKind::InvalidData`] when `inner` is not
+/// valid UTF-8.
+pub struct JustifiedReader<R: BufRead> {
+    inner: R,
+    adjuster: Adjuster,
+    /// Adjusted output of the last consumed chunk
+    out: String,
+    /// Part of `out` that is already read
+    pos: usize,
+    eof: bool,
+}
+
+impl<R: BufRead> JustifiedReader<R> {
+    pub fn new(inner: R, line_width: u32) -> Self {
+        JustifiedReader::with_options(inner, TransformOptions::new(line_width))
+    }
+
+    /// Options are used like [`crate::Adjuster::with_options`] uses them.
+    pub fn with_options(inner: R, options: TransformOptions) -> Self {
+        JustifiedReader {
+            inner,
+            adjuster: Adjuster::with_options(options),
+            out: String::new(),
+            pos: 0,
+            eof: false,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+impl<R: BufRead> Read for JustifiedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // A chunk may be adjusted into nothing, when it does not complete a line
+        while self.pos == self.out.len() {
+            if self.eof {
+                return Ok(0);
+            }
+
+            self.out.clear();
+            self.pos = 0;
+
+            let chunk = self.inner.fill_buf()?;
+            if chunk.is_empty() {
+                self.eof = true;
+                self.adjuster.finish_bytes_to(&mut self.out)?;
+                continue;
+            }
+
+            self.adjuster.push_bytes_to(chunk, &mut self.out)?;
+            let len = chunk.len();
+            self.inner.consume(len);
+        }
+
+        let available = &self.out.as_bytes()[self.pos..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Read};
+
+    use super::JustifiedReader;
+    use crate::{transform, transform_with_options, TransformOptions};
+
+    const INPUT: &str =
+        "Съешь ещё этих мягких французских булок, да выпей чаю. Тридцатитрёхбуквенный \
+         ab—cdefgh xy cd—efghijk";
+
+    fn read_all(mut reader: impl Read, buf_size: usize) -> Vec<u8> {
+        let mut output = Vec::new();
+        let mut buf = vec![0; buf_size];
+        loop {
+            let len = reader.read(&mut buf).unwrap();
+            if len == 0 {
+                return output;
+            }
+            output.extend_from_slice(&buf[..len]);
+        }
+    }
+
+    #[test]
+    fn tiny_reads_match_transform() {
+        for buf_size in 1..=3 {
+            for line_width in [1, 5, 12, 40] {
+                let inner = BufReader::with_capacity(4, INPUT.as_bytes());
+                let output = read_all(JustifiedReader::new(inner, line_width), buf_size);
+
+                assert_eq!(
+                    String::from_utf8(output).unwrap(),
+                    transform(INPUT, line_width)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn last_line_policy() {
+        let mut options = TransformOptions::new(12);
+        options.pad_last_line = false;
+
+        let reader = JustifiedReader::with_options(INPUT.as_bytes(), options.clone());
+        let output = read_all(reader, 2);
+        assert!(!output.ends_with(b" "));
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            transform_with_options(INPUT, &options)
+        );
+    }
+
+    #[test]
+    fn final_newline() {
+        let mut options = TransformOptions::new(12);
+        options.final_newline = true;
+
+        let inner = BufReader::with_capacity(4, INPUT.as_bytes());
+        let output = read_all(JustifiedReader::with_options(inner, options.clone()), 3);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            transform_with_options(INPUT, &options)
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut reader = JustifiedReader::new(&b"valid \xFFinvalid"[..], 10);
+        let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
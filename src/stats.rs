@@ -0,0 +1,314 @@
+use alloc::vec::Vec;
+use core::{fmt, ops::Range};
+
+use crate::{width::in_units, LinePart, LinePlan, Lines, TransformOptions};
+
+/// Metrics of wrapping `input` like [`crate::transform`] does, computed
+/// without building the output.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn stats(input: &str, line_width: u32) -> WrapStats {
+    let options = TransformOptions::new(line_width);
+    let mut stats = StatsBuilder::default();
+    let mut lines = Lines::new(input, &options, line_width as usize);
+    while stats.add_next(&mut lines).is_some() {}
+    stats.finish()
+}
+
+/// Quality of wrapping `input` with `options`, the sum of
+/// [`line_badness`]. Lower is better, 0 when every line but the last ones
+/// of paragraphs is filled by words with single spaces between them.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn badness(input: &str, options: &TransformOptions) -> f64 {
+    line_badness(input, options).iter().sum()
+}
+
+/// Badness of every line of wrapping `input` with `options`, including
+/// empty lines between paragraphs, which score 0:
+///
+/// - a line followed by another line of its paragraph scores the square
+///   of its padding, i.e. of the spaces beyond a single one between
+///   adjacent words;
+/// - a line that ends with the first part of a split word adds
+///   `line_width` squared, as much as a line left empty;
+/// - the last line of a paragraph scores 0, or `line_width` when it holds
+///   a single word and the paragraph has other lines.
+///
+/// Lines are planned like [`crate::transform_with_options`] plans them,
+/// so the score does not depend on `align`. `french_spacing`,
+/// `atomic_delimiters`, `cjk_latin_spacing`, `number_lines` and whatever
+/// is added around the lines are left out.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn line_badness(input: &str, options: &TransformOptions) -> Vec<f64> {
+    let options = &*in_units(options);
+    let line_width = options.line_width as usize;
+    let penalty = |width: usize| (width * width) as f64;
+
+    let mut scores = Vec::new();
+    // Number of words and badness of the last line, when it is not empty
+    let mut last_line: Option<(usize, f64)> = None;
+    let mut paragraph_lines = 0;
+    let mut stats = StatsBuilder::default();
+    let mut lines = Lines::new(input, options, line_width);
+    loop {
+        let forced_splits = stats.stats.forced_splits;
+        let plan = stats.add_next(&mut lines);
+        let ends_paragraph = plan.as_ref().is_none_or(|plan| plan.len() == 0);
+
+        if let Some((n_words, score)) = last_line.take() {
+            scores.push(match ends_paragraph {
+                true if n_words == 1 && paragraph_lines > 1 => line_width as f64,
+                true => 0.0,
+                false => score,
+            });
+        }
+
+        let Some(plan) = plan else {
+            break;
+        };
+        if plan.len() == 0 {
+            scores.push(0.0);
+            paragraph_lines = 0;
+            continue;
+        }
+
+        let mut score = penalty(plan.padding());
+        if stats.stats.forced_splits > forced_splits {
+            score += penalty(plan.line_width);
+        }
+        last_line = Some((plan.n_words, score));
+        paragraph_lines += 1;
+    }
+    scores
+}
+
+/// Collects [`WrapStats`] of lines one by one.
+#[derive(Default)]
+pub(crate) struct StatsBuilder {
+    stats: WrapStats,
+    n_gaps: usize,
+    gaps_width: usize,
+}
+
+impl StatsBuilder {
+    /// Plans the next line of `lines` and adds it to the stats.
+    pub(crate) fn add_next<'a>(&mut self, lines: &mut Lines<'a>) -> Option<LinePlan<'a>> {
+        let splitting = !lines.unfitted.text.is_empty();
+        let plan = lines.next_plan()?;
+        // Split word leaves its rest behind once its first part is planned
+        if !splitting && !lines.unfitted.text.is_empty() {
+            self.stats.forced_splits += 1;
+        }
+
+        let mut words_width = 0;
+        plan.for_each_part(|part| {
+            if let LinePart::Word(word) = part {
+                words_width += word.chars().count();
+            }
+        });
+
+        let line_gaps = plan.n_words.saturating_sub(1);
+        let line_gaps_width = match line_gaps {
+            0 => 0,
+            line_gaps => plan.gap_widths_base * (line_gaps - 1) + plan.remainder,
+        };
+        let stats = &mut self.stats;
+        if line_gaps > 0 {
+            stats.max_gap = stats.max_gap.max(plan.gap_widths_base.max(plan.remainder));
+        }
+        self.n_gaps += line_gaps;
+        self.gaps_width += line_gaps_width;
+
+        stats.lines += 1;
+        stats.total_padding_chars += plan.padding();
+        stats.widest_natural_line = stats.widest_natural_line.max(words_width + line_gaps);
+        Some(plan)
+    }
+
+    pub(crate) fn finish(mut self) -> WrapStats {
+        if self.n_gaps > 0 {
+            self.stats.avg_gap = self.gaps_width as f64 / self.n_gaps as f64;
+        }
+        self.stats
+    }
+}
+
+/// Words of `input` that [`crate::transform`] splits into parts because
+/// they are wider than `line_width`, in input order.
+///
+/// Panics when `line_width` is 0 and `input` is not blank.
+pub fn find_forced_splits(input: &str, line_width: u32) -> Vec<SplitInfo<'_>> {
+    let options = TransformOptions::new(line_width);
+    let mut splits = Vec::new();
+
+    let mut lines = Lines::new(input, &options, line_width as usize);
+    while let Some(plan) = lines.next_plan() {
+        let rest = lines.unfitted.text;
+        if rest.is_empty() {
+            continue;
+        }
+
+        // First part ends the line and the rest is left for the next
+        // lines, and its last part starts the next line
+        let first_part = plan.text.rsplit(char::is_whitespace).next().unwrap_or("");
+        let start = first_part.as_ptr() as usize - input.as_ptr() as usize;
+        let end = rest.as_ptr() as usize - input.as_ptr() as usize + rest.len();
+        splits.push(SplitInfo {
+            word: &input[start..end],
+            range: start..end,
+            fragments: 2 + lines.skip_unfitted(),
+        });
+    }
+    splits
+}
+
+/// Word split into parts, returned by [`find_forced_splits`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitInfo<'a> {
+    /// Whole word.
+    pub word: &'a str,
+    /// Byte range of the word in the input.
+    pub range: Range<usize>,
+    /// Number of lines the parts take.
+    pub fragments: usize,
+}
+
+/// Returned by [`stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct WrapStats {
+    /// Number of output lines.
+    pub lines: usize,
+    /// Spaces added to fill lines, i.e. all spaces except a single one
+    /// between adjacent words.
+    pub total_padding_chars: usize,
+    /// Widest gap between two words of a line, 0 without such gaps.
+    pub max_gap: usize,
+    /// Average width of gaps between words of a line, 0 without such gaps.
+    pub avg_gap: f64,
+    /// Number of words wider than a line, which are split into parts.
+    pub forced_splits: usize,
+    /// Width of the widest line with single spaces between words and
+    /// without padding, in chars.
+    pub widest_natural_line: usize,
+}
+
+impl fmt::Display for WrapStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} lines, {} padding chars, gaps up to {} (avg {:.2}), {} forced splits, \
+             widest natural line {}",
+            self.lines,
+            self.total_padding_chars,
+            self.max_gap,
+            self.avg_gap,
+            self.forced_splits,
+            self.widest_natural_line
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{badness, find_forced_splits, line_badness, stats, SplitInfo, WrapStats};
+    use crate::{Alignment, TransformOptions};
+
+    #[test]
+    fn known_inputs() {
+        // "Съешь    ещё\nэтих  мягких\nфранцузских \nбулок,    да\nвыпей    чаю"
+        let result = stats("Съешь ещё этих мягких французских булок, да выпей чаю", 12);
+        let expected = WrapStats {
+            lines: 5,
+            total_padding_chars: 11,
+            max_gap: 4,
+            avg_gap: 3.5,
+            forced_splits: 0,
+            widest_natural_line: 11,
+        };
+        assert_eq!(result, expected);
+        assert_eq!(
+            result.to_string(),
+            "5 lines, 11 padding chars, gaps up to 4 (avg 3.50), 0 forced splits, \
+             widest natural line 11"
+        );
+
+        // "🤩  \nпри\nвет\n💨  \nhel\nlo "
+        let expected = WrapStats {
+            lines: 6,
+            total_padding_chars: 5,
+            max_gap: 0,
+            avg_gap: 0.0,
+            forced_splits: 2,
+            widest_natural_line: 3,
+        };
+        assert_eq!(stats("🤩 привет  💨 hello", 3), expected);
+
+        assert_eq!(stats(" \n ", 3), WrapStats::default());
+    }
+
+    #[test]
+    fn forced_splits() {
+        assert_eq!(find_forced_splits("Съешь ещё этих", 12), []);
+        assert_eq!(find_forced_splits("", 1), []);
+
+        let input = "see https://example.com/путь";
+        let expected = [SplitInfo {
+            word: "https://example.com/путь",
+            range: 4..32,
+            fragments: 3,
+        }];
+        assert_eq!(find_forced_splits(input, 10), expected);
+
+        let input = "🤩 привет  💨 hello";
+        let expected = [
+            SplitInfo {
+                word: "привет",
+                range: 5..17,
+                fragments: 2,
+            },
+            SplitInfo {
+                word: "hello",
+                range: 24..29,
+                fragments: 2,
+            },
+        ];
+        assert_eq!(find_forced_splits(input, 3), expected);
+        assert_eq!(stats(input, 3).forced_splits, expected.len());
+
+        // First part ends a line with other words
+        let expected = [SplitInfo {
+            word: "cd\u{2014}efghijk",
+            range: 3..15,
+            fragments: 3,
+        }];
+        assert_eq!(find_forced_splits("ab cd\u{2014}efghijk", 6), expected);
+    }
+
+    #[test]
+    fn badness_scores() {
+        let input = "Съешь ещё этих мягких французских булок, да выпей чаю";
+        let mut options = TransformOptions::new(12);
+        assert_eq!(line_badness(input, &options), [9.0, 1.0, 1.0, 9.0, 0.0]);
+        for align in [Alignment::Left, Alignment::Right, Alignment::Center] {
+            options.align = align;
+            assert_eq!(badness(input, &options), 20.0);
+        }
+
+        // Forced splits and lone words on last lines
+        // "🤩  \nпри\nвет\n💨  \nhel\nlo "
+        let options = TransformOptions::new(3);
+        let scores = line_badness("🤩 привет  💨 hello", &options);
+        assert_eq!(scores, [4.0, 9.0, 0.0, 4.0, 9.0, 3.0]);
+
+        let mut options = TransformOptions::new(3);
+        options.preserve_paragraphs = true;
+        assert_eq!(
+            line_badness("aaa bbb\n\nccc", &options),
+            [0.0, 3.0, 0.0, 0.0]
+        );
+        assert!(line_badness(" \n ", &options).is_empty());
+        assert_eq!(badness("", &options), 0.0);
+    }
+}
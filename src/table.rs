@@ -0,0 +1,105 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{transform_with_options, Alignment, TransformOptions, NEWLINE_STR};
+
+/// Table of `rows` with every cell justified at the width of its column,
+/// columns separated by `gutter`.
+///
+/// See [`format_table_aligned`].
+pub fn format_table(rows: &[Vec<&str>], widths: &[u32], gutter: &str) -> String {
+    format_table_aligned(rows, widths, &[], gutter)
+}
+
+/// Same as [`format_table`], but cells of a column are aligned by the
+/// alignment of the column in `aligns`, and justified when it has none.
+///
+/// Every cell is wrapped like [`crate::transform_with_options`] wraps it
+/// at the width of its column in `widths`, and cells of a row are padded
+/// with lines of spaces to the height of the tallest one, so every line
+/// of the table is as wide as the columns and the gutters between them.
+/// Rows with fewer cells than `widths` get blank cells, cells beyond
+/// `widths` are left out, and a row of blank cells takes a single line.
+///
+/// Panics when a width is 0 and a cell of its column is not blank.
+pub fn format_table_aligned(
+    rows: &[Vec<&str>],
+    widths: &[u32],
+    aligns: &[Alignment],
+    gutter: &str,
+) -> String {
+    let mut result = String::new();
+    for (idx, row) in rows.iter().enumerate() {
+        let cells: Vec<Vec<String>> = widths
+            .iter()
+            .enumerate()
+            .map(|(column, &width)| {
+                let mut options = TransformOptions::new(width);
+                options.align = aligns.get(column).copied().unwrap_or_default();
+                let cell = row.get(column).copied().unwrap_or_default();
+                let text = transform_with_options(cell, &options);
+                text.lines().map(String::from).collect()
+            })
+            .collect();
+        let height = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+
+        for line in 0..height {
+            if idx > 0 || line > 0 {
+                result += NEWLINE_STR;
+            }
+            for (column, (cell, &width)) in cells.iter().zip(widths).enumerate() {
+                if column > 0 {
+                    result += gutter;
+                }
+                match cell.get(line) {
+                    Some(text) => result += text,
+                    None => result.extend(core::iter::repeat_n(' ', width as usize)),
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_table, format_table_aligned};
+    use crate::Alignment;
+
+    #[test]
+    fn three_by_three() {
+        let rows = [
+            vec!["Имя", "Описание", "Цена"],
+            vec!["Булка", "Мягкая французская булка к чаю", "12"],
+            vec!["Чай", "Чёрный", "5"],
+        ];
+        let table = format_table(&rows, &[6, 12, 4], " | ");
+        assert_eq!(
+            table,
+            "Имя    | Описание     | Цена\n\
+             Булка  | Мягкая       | 12  \n       \
+             | французская  |     \n       \
+             | булка  к чаю |     \n\
+             Чай    | Чёрный       | 5   "
+        );
+        assert!(table.lines().all(|line| line.chars().count() == 28));
+
+        let aligns = [Alignment::Left, Alignment::Center, Alignment::Right];
+        let table = format_table_aligned(&rows, &[6, 12, 4], &aligns, "|");
+        assert_eq!(
+            table,
+            "Имя   |  Описание  |Цена\n\
+             Булка |   Мягкая   |  12\n      \
+             |французская |    \n      \
+             |булка к чаю |    \n\
+             Чай   |   Чёрный   |   5"
+        );
+    }
+
+    #[test]
+    fn missing_cells() {
+        let rows = [vec!["a b"], vec![], vec!["a", "b", "c"]];
+        assert_eq!(format_table(&rows, &[3, 2], " "), "a b   \n      \na   b ");
+        assert_eq!(format_table(&rows, &[], " "), "\n\n");
+        assert_eq!(format_table(&[], &[3], " "), "");
+    }
+}
@@ -0,0 +1,82 @@
+use std::num::NonZeroUsize;
+
+use crate::TransformOptions;
+
+/// Used when the width can not be detected.
+const DEFAULT_WIDTH: u32 = 80;
+
+impl TransformOptions {
+    /// Options with `line_width` set to [`TransformOptions::terminal_width`].
+    pub fn for_terminal() -> Self {
+        TransformOptions::new(TransformOptions::terminal_width())
+    }
+
+    /// Width of the terminal, as reported by [`detect_width`], or 80
+    /// when it can not be detected.
+    pub fn terminal_width() -> u32 {
+        detect_width()
+            .and_then(|width| u32::try_from(width.get()).ok())
+            .unwrap_or(DEFAULT_WIDTH)
+    }
+}
+
+/// Detects the width of the terminal attached to stdout.
+///
+/// With the `term-size` feature the terminal is queried directly. When
+/// stdout is not a terminal, or the feature is disabled, the `COLUMNS`
+/// environment variable is used instead.
+pub fn detect_width() -> Option<NonZeroUsize> {
+    stdout_width().or_else(|| columns_width(std::env::var("COLUMNS").ok().as_deref()))
+}
+
+fn columns_width(columns: Option<&str>) -> Option<NonZeroUsize> {
+    columns?.trim().parse().ok()
+}
+
+#[cfg(all(feature = "term-size", unix))]
+fn stdout_width() -> Option<NonZeroUsize> {
+    // SAFETY: `winsize` is plain data and is only read after ioctl succeeds.
+    unsafe {
+        let mut size: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) != 0 {
+            return None;
+        }
+        NonZeroUsize::new(size.ws_col as usize)
+    }
+}
+
+#[cfg(all(feature = "term-size", windows))]
+fn stdout_width() -> Option<NonZeroUsize> {
+    use windows_sys::Win32::System::Console::{
+        GetConsoleScreenBufferInfo, GetStdHandle, CONSOLE_SCREEN_BUFFER_INFO, STD_OUTPUT_HANDLE,
+    };
+
+    // SAFETY: `info` is plain data and is only read after the call succeeds.
+    unsafe {
+        let mut info: CONSOLE_SCREEN_BUFFER_INFO = std::mem::zeroed();
+        if GetConsoleScreenBufferInfo(GetStdHandle(STD_OUTPUT_HANDLE), &mut info) == 0 {
+            return None;
+        }
+        let width = info.srWindow.Right - info.srWindow.Left + 1;
+        NonZeroUsize::new(usize::try_from(width).ok()?)
+    }
+}
+
+#[cfg(not(all(feature = "term-size", any(unix, windows))))]
+fn stdout_width() -> Option<NonZeroUsize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::columns_width;
+
+    #[test]
+    fn columns_fallback() {
+        assert_eq!(columns_width(Some("120")).map(|w| w.get()), Some(120));
+        assert_eq!(columns_width(Some(" 72\n")).map(|w| w.get()), Some(72));
+        assert_eq!(columns_width(Some("0")), None);
+        assert_eq!(columns_width(Some("wide")), None);
+        assert_eq!(columns_width(None), None);
+    }
+}
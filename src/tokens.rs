@@ -0,0 +1,121 @@
+//! Splitting of text into words.
+
+/// Words of `text`, separated by any whitespace. Yields the same words
+/// as [`str::split_whitespace`].
+pub(crate) fn tokens(text: &str) -> Tokens<'_> {
+    imp::tokens(text)
+}
+
+pub(crate) use imp::Tokens;
+
+#[cfg(not(feature = "fast-scan"))]
+mod imp {
+    pub(crate) type Tokens<'a> = core::str::SplitWhitespace<'a>;
+
+    pub(super) fn tokens(text: &str) -> Tokens<'_> {
+        text.split_whitespace()
+    }
+}
+
+#[cfg(feature = "fast-scan")]
+mod imp {
+    use memchr::memchr3;
+
+    /// Looks for word boundaries with `memchr`. Words containing rare
+    /// whitespace bytes or non-ASCII chars are rescanned char by char.
+    #[derive(Clone)]
+    pub(crate) struct Tokens<'a> {
+        rest: &'a str,
+    }
+
+    pub(super) fn tokens(text: &str) -> Tokens<'_> {
+        Tokens { rest: text }
+    }
+
+    impl<'a> Iterator for Tokens<'a> {
+        type Item = &'a str;
+
+        fn next(&mut self) -> Option<&'a str> {
+            let start = word_start(self.rest)?;
+            let text = &self.rest[start..];
+            let end = word_end(text);
+
+            let (word, rest) = text.split_at(end);
+            self.rest = rest;
+            Some(word)
+        }
+    }
+
+    fn is_ascii_space(byte: u8) -> bool {
+        // Same as `char::is_whitespace` for ASCII, unlike
+        // `u8::is_ascii_whitespace`, which misses the vertical tab
+        matches!(byte, b'\t'..=b'\r' | b' ')
+    }
+
+    /// Offset of the first char that is not whitespace.
+    fn word_start(text: &str) -> Option<usize> {
+        let bytes = text.as_bytes();
+        let mut idx = 0;
+        while idx < bytes.len() {
+            if is_ascii_space(bytes[idx]) {
+                idx += 1;
+            } else if bytes[idx].is_ascii() {
+                return Some(idx);
+            } else {
+                let chr = text[idx..]
+                    .chars()
+                    .next()
+                    .expect("Index is at a char boundary");
+                if !chr.is_whitespace() {
+                    return Some(idx);
+                }
+                idx += chr.len_utf8();
+            }
+        }
+        None
+    }
+
+    /// Length of the word `text` starts with.
+    fn word_end(text: &str) -> usize {
+        let bytes = text.as_bytes();
+        let candidate = memchr3(b' ', b'\n', b'\t', bytes).unwrap_or(bytes.len());
+
+        let span = &bytes[..candidate];
+        if span.is_ascii() && memchr3(0x0B, 0x0C, b'\r', span).is_none() {
+            return candidate;
+        }
+
+        text.char_indices()
+            .find(|(_, chr)| chr.is_whitespace())
+            .map_or(text.len(), |(idx, _)| idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tokens;
+
+    #[test]
+    fn same_as_split_whitespace() {
+        const ALPHABET: &[char] = &[
+            'a', 'b', 'c', '.', ' ', ' ', '\t', '\n', '\r', '\x0B', '\x0C', 'é', 'Ж', '🤩',
+            '\u{85}', '\u{A0}', '\u{2003}', '\u{3000}',
+        ];
+
+        let mut state = 11u32;
+        for _ in 0..500 {
+            // Tiny linear congruential generator for input chars
+            let mut next = || {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as usize
+            };
+            let len = next() % 40;
+            let input: String = (0..len)
+                .map(|_| ALPHABET[next() % ALPHABET.len()])
+                .collect();
+
+            let expected: Vec<&str> = input.split_whitespace().collect();
+            assert_eq!(tokens(&input).collect::<Vec<_>>(), expected, "{input:?}");
+        }
+    }
+}
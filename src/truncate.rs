@@ -0,0 +1,170 @@
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{fit_strs, words, LinePart, LinePlan, LinePolicy, TransformOptions, Word, SPACE_STR};
+
+/// Cuts `input` to at most `width` chars, measured like words of
+/// [`crate::transform`], ending with `ellipsis` when anything is cut.
+///
+/// Returns `input` as it is when it fits, otherwise the longest prefix of
+/// `input` that fits together with `ellipsis`. Whitespace is kept and
+/// counted like any other char. When `ellipsis` alone is wider than
+/// `width`, its first `width` chars are returned.
+pub fn truncate_to_width<'a>(input: &'a str, width: u32, ellipsis: &str) -> Cow<'a, str> {
+    let width = width as usize;
+    let Some((end, _)) = input.char_indices().nth(width) else {
+        return Cow::Borrowed(input);
+    };
+
+    let ellipsis_width = Word::new(ellipsis).width;
+    let Some(room) = width.checked_sub(ellipsis_width) else {
+        return Cow::Owned(ellipsis.chars().take(width).collect());
+    };
+
+    let prefix_end = input[..end]
+        .char_indices()
+        .nth(room)
+        .map_or(end, |(idx, _)| idx);
+    Cow::Owned(String::from(&input[..prefix_end]) + ellipsis)
+}
+
+/// Lays out words of `plan` again, followed by `ellipsis`, within the same
+/// width. Words are dropped from the end of the line, and then chars of
+/// the only word left, until the ellipsis fits.
+pub(crate) fn ellipsized(
+    plan: &LinePlan<'_>,
+    ellipsis: &str,
+    options: &TransformOptions,
+) -> String {
+    let line_width = plan.line_width;
+    // Whitespace before the ellipsis is the first to go
+    let ellipsis = match ellipsis.chars().count() > line_width {
+        true => ellipsis.trim_start().chars().take(line_width).collect(),
+        false => ellipsis.to_string(),
+    };
+    let room = line_width - ellipsis.chars().count();
+
+    let mut kept = Vec::new();
+    plan.for_each_part(|part| match part {
+        LinePart::Word(word) if !word.is_empty() => kept.push(word),
+        _ => {}
+    });
+
+    // Words with single spaces between them
+    let text_width = |words: &[&str]| {
+        let gaps = words.len().saturating_sub(1);
+        words.iter().map(|word| word.chars().count()).sum::<usize>() + gaps
+    };
+    while kept.len() > 1 && text_width(&kept) > room {
+        kept.pop();
+    }
+
+    let mut text = kept.join(SPACE_STR);
+    if let Some((idx, _)) = text.char_indices().nth(room) {
+        text.truncate(idx);
+    }
+    text += &ellipsis;
+
+    let mut tokens = words(&text, Word::new, options);
+    let fit_result = fit_strs(Word::new(""), &mut tokens, line_width, &text);
+    let mut line = String::with_capacity(text.len() + line_width);
+    LinePlan::new(&fit_result, line_width, LinePolicy::new(options, true)).push(&mut line);
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::truncate_to_width;
+    use crate::{transform_with_options, Alignment, TransformOptions, Truncation};
+
+    const INPUT: &str = "Вез корабль карамель, наскочил корабль на мель, матросы две недели \
+                         карамель на мели ели.";
+
+    #[test]
+    fn clipped() {
+        let mut options = TransformOptions::new(20);
+        options.max_lines = Some(2);
+        assert_eq!(
+            transform_with_options(INPUT, &options),
+            "Вез          корабль\nкарамель,   наскочил"
+        );
+
+        options.max_lines = Some(0);
+        assert_eq!(transform_with_options(INPUT, &options), "");
+    }
+
+    #[test]
+    fn ellipsis() {
+        let mut options = TransformOptions::new(20);
+        options.max_lines = Some(2);
+        options.truncation = Truncation::Ellipsis(String::from("…"));
+        let result = transform_with_options(INPUT, &options);
+        assert_eq!(result, "Вез          корабль\nкарамель,  наскочил…");
+        assert!(result.lines().all(|line| line.chars().count() == 20));
+
+        // Words are dropped to make room
+        options.truncation = Truncation::Ellipsis(String::from(" [...]"));
+        let result = transform_with_options(INPUT, &options);
+        assert_eq!(result, "Вез          корабль\nкарамель,      [...]");
+
+        options.align = Alignment::Left;
+        options.line_width = 12;
+        options.max_lines = Some(1);
+        options.truncation = Truncation::Ellipsis(String::from("..."));
+        let result = transform_with_options("Тридцатитрёхбуквенный слово", &options);
+        assert_eq!(result, "Тридцатит...");
+
+        // Rest of a split word is dropped with the words after it
+        options.line_width = 4;
+        options.max_lines = Some(2);
+        options.truncation = Truncation::Ellipsis(String::from("…"));
+        let result = transform_with_options("consectetur adipiscing", &options);
+        assert_eq!(result, "cons\nect…");
+
+        // Text that fits is left as is
+        let mut options = TransformOptions::new(20);
+        options.max_lines = Some(2);
+        options.truncation = Truncation::Ellipsis(String::from("…"));
+        let input = "Вез корабль карамель";
+        assert_eq!(
+            transform_with_options(input, &options),
+            transform_with_options(input, &TransformOptions::new(20))
+        );
+        options.pad_last_line = false;
+        let input = "Вез корабль карамель, наскочил";
+        assert_eq!(
+            transform_with_options(input, &options),
+            "Вез          корабль\nкарамель, наскочил"
+        );
+    }
+
+    #[test]
+    fn single_line() {
+        let input = "Съешь ещё 🍞";
+        assert!(matches!(
+            truncate_to_width(input, 11, "…"),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            truncate_to_width(input, 40, "…"),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(truncate_to_width(input, 10, "…"), "Съешь ещё…");
+        assert_eq!(truncate_to_width(input, 6, "…"), "Съешь…");
+        assert_eq!(truncate_to_width(input, 6, "..."), "Съе...");
+        assert_eq!(truncate_to_width(input, 3, "🍞🍞"), "С🍞🍞");
+
+        // Ellipsis is clipped when it is wider than the whole line
+        assert_eq!(truncate_to_width(input, 2, "…"), "С…");
+        assert_eq!(truncate_to_width(input, 1, "…"), "…");
+        assert_eq!(truncate_to_width(input, 2, "🍞🍞🍞"), "🍞🍞");
+        assert_eq!(truncate_to_width(input, 0, "…"), "");
+        assert_eq!(truncate_to_width("", 0, "…"), "");
+        assert_eq!(truncate_to_width("🍞 🍞", 2, ""), "🍞 ");
+    }
+}
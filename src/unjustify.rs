@@ -0,0 +1,154 @@
+use alloc::string::String;
+
+use crate::{tokens, transform_with_options, Paragraphs, TransformOptions, SPACE_STR};
+
+/// Undoes [`crate::transform`]: joins lines of `input` into a single line
+/// with single spaces between words.
+///
+/// Parts of split words stay apart, see [`unjustify_with_options`].
+pub fn unjustify(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    join_words(input, None, &mut result);
+    result
+}
+
+/// Same as [`unjustify`], but for text adjusted with `options`.
+///
+/// With `preserve_paragraphs`, every paragraph gets a line of its own and
+/// paragraphs are separated by an empty line, both ended by `line_ending`.
+///
+/// A line with a single word exactly `line_width` chars wide is taken for
+/// a part of a split word and joined with the next word without a space.
+/// Such a line is ambiguous: a word that fits a line exactly is joined
+/// with the next word as well.
+pub fn unjustify_with_options(input: &str, options: &TransformOptions) -> String {
+    let line_width = Some(options.line_width as usize).filter(|&width| width > 0);
+    let line_ending = options.line_ending.as_str();
+
+    let mut result = String::with_capacity(input.len());
+    for (idx, paragraph) in Paragraphs::new(input, options.preserve_paragraphs).enumerate() {
+        if idx > 0 {
+            result.push_str(line_ending);
+            result.push_str(line_ending);
+        }
+        join_words(paragraph, line_width, &mut result);
+    }
+    result
+}
+
+/// Wraps text that is already wrapped, at any widths, again at
+/// `new_width`. Lines of every paragraph are joined like [`unjustify`]
+/// does and empty lines between paragraphs are kept.
+///
+/// Reflowing the output again at the same width gives the same output.
+/// Parts of split words are not joined, as the old width is not known.
+pub fn reflow(input: &str, new_width: u32) -> String {
+    let mut options = TransformOptions::new(new_width);
+    options.preserve_paragraphs = true;
+
+    // Adjusting collapses whitespace within a paragraph the same way
+    transform_with_options(input, &options)
+}
+
+/// Appends words of `text` to `result`, separated by single spaces. With
+/// `line_width`, words that fill a line alone are joined with the next word.
+fn join_words(text: &str, line_width: Option<usize>, result: &mut String) {
+    // No space before the first word
+    let mut glue = true;
+    for line in text.lines() {
+        let mut n_words = 0;
+        let mut last_word = "";
+        for word in tokens(line) {
+            if !glue {
+                result.push_str(SPACE_STR);
+            }
+            result.push_str(word);
+            glue = false;
+            n_words += 1;
+            last_word = word;
+        }
+
+        if n_words == 1 {
+            glue = line_width == Some(last_word.chars().count());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reflow, unjustify, unjustify_with_options};
+    use crate::{transform, transform_with_options, Alignment, LineEnding, TransformOptions};
+
+    #[test]
+    fn without_splits() {
+        let inputs = [
+            "Съешь ещё этих мягких французских булок, да выпей чаю",
+            "Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.",
+            "У переп\tела и перепелки\t\t\t пять  \t\tперепелят    .",
+        ];
+        for input in inputs {
+            let collapsed = input.split_whitespace().collect::<Vec<_>>().join(" ");
+            for line_width in 12..=40 {
+                assert_eq!(unjustify(&transform(input, line_width)), collapsed);
+            }
+        }
+        assert_eq!(unjustify(" \n "), "");
+    }
+
+    #[test]
+    fn with_splits() {
+        let input = "Тридцатитрёхбуквенный  🤩 hello\nсверхдлинныйидентификатор ok";
+        for line_width in [4, 6, 8, 9, 10, 11] {
+            let mut options = TransformOptions::new(line_width);
+            options.align = Alignment::Center;
+            let result = transform_with_options(input, &options);
+            let expected = input.split_whitespace().collect::<Vec<_>>().join(" ");
+            assert_eq!(unjustify_with_options(&result, &options), expected);
+            assert_ne!(unjustify(&result), expected);
+        }
+
+        // Word that fits a line exactly looks like a part of a split one
+        let options = TransformOptions::new(5);
+        assert_eq!(
+            unjustify_with_options("hello\nthere", &options),
+            "hellothere"
+        );
+    }
+
+    #[test]
+    fn paragraphs() {
+        let input = "First paragraph, a longer one.\n\nSecond\n  \nThird one";
+        let mut options = TransformOptions::new(12);
+        options.preserve_paragraphs = true;
+        options.line_ending = LineEnding::CrLf;
+
+        let result = transform_with_options(input, &options);
+        let expected = "First paragraph, a longer one.\r\n\r\nSecond\r\n\r\nThird one";
+        assert_eq!(unjustify_with_options(&result, &options), expected);
+        assert_eq!(
+            unjustify(&result),
+            "First paragraph, a longer one. Second Third one"
+        );
+    }
+
+    #[test]
+    fn reflowed() {
+        let input = "Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели.";
+        assert_eq!(reflow(&transform(input, 12), 30), transform(input, 30));
+
+        let input = "Съешь ещё этих мягких французских булок,\n да выпей чаю.\n\n\
+                     Бык тупогуб, губастый бычок,\nу быка губа тупа.\n \n\
+                     Тридцать три корабля лавировали";
+        let mut options = TransformOptions::new(12);
+        options.preserve_paragraphs = true;
+        let wrapped = transform_with_options(input, &options);
+
+        options.line_width = 30;
+        let expected = transform_with_options(input, &options);
+        assert_eq!(reflow(&wrapped, 30), expected);
+        assert_eq!(reflow(&expected, 30), expected);
+        // Lines of different widths
+        assert_eq!(reflow(input, 30), expected);
+        assert_eq!(expected.matches("\n\n").count(), 2);
+    }
+}
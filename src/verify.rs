@@ -0,0 +1,484 @@
+//! Checks of the output for property tests.
+
+use alloc::{borrow::Cow, format, string::String, string::ToString, vec, vec::Vec};
+
+use crate::{
+    check::{is_wrong_width, visible_width},
+    field_offset, hint, tokens,
+    width::in_units,
+    LineEnding, MarkupConfig, TransformOptions, Truncation, Violation,
+};
+
+/// Checks that `output` of [`crate::transform_with_options`] for `input`
+/// and `options` follows the rules every output follows:
+///
+/// - every line is exactly `line_width` chars wide, or `initial_width`
+///   for the first line of a paragraph, except the last line
+///   of a paragraph without `pad_last_line`, which may be narrower, and
+///   lines next to a gutter that takes a whole line. With
+///   `hang_punctuation`, a line that ends with one of
+///   `hanging_punctuation` may be wider by that char;
+/// - words of the output, joined without whitespace, are the words of
+///   `input` joined the same way. Parts of a split word join back too;
+/// - lines are empty only between paragraphs, with `preserve_paragraphs`,
+///   or last when text is cut by `max_lines`;
+/// - pages have at most `lines_per_page` lines;
+/// - lines that match `skip_line`, with `preserve_paragraphs`, may have
+///   any width, and paragraphs start and end around them;
+/// - non-empty lines are surrounded by the margins, which are left out
+///   of the rules below;
+/// - with `field_width`, non-empty lines start with the same padding,
+///   and are as wide as the rest of the field instead of `line_width`;
+/// - there are at least `min_lines` lines, where whitespace-only lines
+///   before and after the text are blank lines of `line_width` spaces,
+///   or empty ones without `pad_last_line`;
+/// - there are at most `max_lines` lines of text. When there are that
+///   many, words of the input may be left out at the end and the last
+///   word may lack chars before the ellipsis.
+///
+/// Markers of `markup` take no width, and with `reopen_on_wrap` they are
+/// left out of the words, like `break_hint` is. Widths are in half units with
+/// `WidthMode::HalfWidthUnits`.
+///
+/// A line ending after the last line, with `final_newline`, is left out.
+/// Returns every broken rule. Line numbers count from 0 and include lines
+/// of page separators.
+pub fn verify_invariants(
+    input: &str,
+    options: &TransformOptions,
+    output: &str,
+) -> Result<(), Vec<Violation>> {
+    let options = &*in_units(options);
+    let mut violations = Vec::new();
+
+    let normalized;
+    let (output, page_separator) = match options.line_ending {
+        LineEnding::Lf => (output, format!("\n{}", options.page_separator)),
+        LineEnding::CrLf => {
+            normalized = output.replace("\r\n", "\n");
+            let separator = options.page_separator.replace("\r\n", "\n");
+            (normalized.as_str(), format!("\n{separator}"))
+        }
+    };
+
+    let output = match options.final_newline {
+        true => output.strip_suffix('\n').unwrap_or(output),
+        false => output,
+    };
+
+    // Body lines together with their number in the output
+    let mut lines = Vec::new();
+    if !output.is_empty() {
+        let pages: Vec<&str> = match options.lines_per_page {
+            Some(_) => output.split(page_separator.as_str()).collect(),
+            None => vec![output],
+        };
+        for (page_idx, page) in pages.into_iter().enumerate() {
+            let start = page.as_ptr() as usize - output.as_ptr() as usize;
+            let first_line = output[..start].matches('\n').count();
+            let n_lines = page.split('\n').count();
+
+            let max_lines = options
+                .lines_per_page
+                .map_or(usize::MAX, |lines| lines.max(1));
+            if n_lines > max_lines {
+                violations.push(Violation::PageTooLong {
+                    page: page_idx,
+                    lines: n_lines,
+                    max_lines,
+                });
+            }
+            lines.extend((first_line..).zip(page.split('\n')));
+        }
+    }
+
+    // Padding before lines in a field is stripped like a margin, and
+    // lines take the rest of the field
+    let field = options.field_width.map(|field_width| {
+        let before = field_offset(field_width as usize, options);
+        (before, field_width as usize - before)
+    });
+    let (before, field_rest) = field.unwrap_or((0, 0));
+    let left = " ".repeat(options.margin_left + before);
+    let right = " ".repeat(options.margin_right);
+    for (line, text) in &mut lines {
+        if text.is_empty() {
+            continue;
+        }
+        match text.strip_prefix(left.as_str()) {
+            Some(rest) => *text = rest.strip_suffix(right.as_str()).unwrap_or(rest),
+            None => violations.push(Violation::MissingMargin { line: *line }),
+        }
+    }
+
+    if let Some(min_lines) = options.min_lines {
+        if lines.len() < min_lines {
+            violations.push(Violation::TooFewLines {
+                lines: lines.len(),
+                min_lines,
+            });
+        }
+
+        // Blank lines are kept out of the text
+        let blank_width = match (options.pad_last_line, field) {
+            (true, Some(_)) => field_rest,
+            (true, None) => options.line_width as usize,
+            (false, _) => 0,
+        };
+        let is_padding = |(_, text): &&(usize, &str)| text.trim().is_empty();
+        let mut n_above = lines.iter().take_while(is_padding).count();
+        // First line of text is left blank when its first word only fits
+        // lines of `line_width`
+        let initial_blank = options
+            .initial_width
+            .filter(|&initial_width| field.is_none() && initial_width as usize != blank_width);
+        let starts_blank = match (initial_blank, n_above.checked_sub(1)) {
+            (Some(initial_width), Some(last)) if n_above < lines.len() => {
+                visible_width(lines[last].1, options) == initial_width as usize
+            }
+            _ => false,
+        };
+        n_above -= usize::from(starts_blank);
+        let n_below = lines[n_above..].iter().rev().take_while(is_padding).count();
+        let (above, below) = (&lines[..n_above], &lines[lines.len() - n_below..]);
+        for &(line, text) in above.iter().chain(below) {
+            let width = visible_width(text, options);
+            if width != blank_width {
+                violations.push(Violation::WrongWidth {
+                    line,
+                    width,
+                    expected: blank_width,
+                });
+            }
+        }
+        lines.truncate(lines.len() - n_below);
+        lines.drain(..n_above);
+    }
+
+    if let Some(max_lines) = options.max_lines {
+        if lines.len() > max_lines {
+            violations.push(Violation::TooManyLines {
+                lines: lines.len(),
+                max_lines,
+            });
+        }
+    }
+    let truncated = options
+        .max_lines
+        .is_some_and(|max_lines| lines.len() >= max_lines);
+
+    // Spans reopened on every line add markers to words
+    let reopened = options
+        .markup
+        .as_ref()
+        .filter(|markup| markup.reopen_on_wrap);
+    let mut words = String::new();
+    for input_line in input.lines() {
+        let line_words = tokens(input_line).map(|word| strip(word, reopened));
+        match hint::break_hint(options) {
+            // Lines copied as they are keep their hints
+            Some(hint) if !is_skipped(input_line, options) => {
+                words.extend(line_words.map(|word| word.replace(hint, "")))
+            }
+            _ => words.extend(line_words),
+        }
+    }
+    let mut words_pos = 0;
+    let line_width = options.line_width as usize;
+
+    for (idx, &(line, text)) in lines.iter().enumerate() {
+        let is_blank = |idx: Option<usize>| {
+            idx.and_then(|idx| lines.get(idx))
+                .is_none_or(|&(_, text)| text.is_empty())
+        };
+        let next_blank = is_blank(Some(idx + 1));
+        let is_verbatim = |idx: Option<usize>| {
+            idx.and_then(|idx| lines.get(idx))
+                .is_some_and(|&(_, text)| is_skipped(split_gutter(text, options).1, options))
+        };
+
+        if text.is_empty() {
+            let between =
+                options.preserve_paragraphs && !is_blank(idx.checked_sub(1)) && !next_blank;
+            // Text may be cut right after a paragraph
+            let cut = truncated && idx + 1 == lines.len();
+            if !between && !cut {
+                violations.push(Violation::EmptyLine { line });
+            }
+            continue;
+        }
+
+        let (gutter_width, body) = split_gutter(text, options);
+
+        // Paragraphs start and end around lines copied as they are
+        let prev = idx.checked_sub(1);
+        let line_width = match options.initial_width {
+            _ if field.is_some() => field_rest,
+            Some(initial_width) if is_blank(prev) || is_verbatim(prev) => initial_width as usize,
+            _ => line_width,
+        };
+        let expected = line_width.max(gutter_width + 1);
+        let width = visible_width(text, options);
+        let may_be_narrower = !options.pad_last_line && (next_blank || is_verbatim(Some(idx + 1)));
+        let wrong_width = is_wrong_width(body, width, expected, may_be_narrower, options);
+        if wrong_width && !is_skipped(body, options) {
+            violations.push(Violation::WrongWidth {
+                line,
+                width,
+                expected,
+            });
+        }
+
+        let body = match &options.truncation {
+            Truncation::Ellipsis(ellipsis) if truncated && idx + 1 == lines.len() => {
+                // Ellipsis loses chars at the end when the line is too narrow
+                let (body, ellipsis) = (body.trim_end(), ellipsis.trim());
+                let ends = ellipsis
+                    .char_indices()
+                    .map(|(idx, chr)| idx + chr.len_utf8());
+                ends.rev()
+                    .find_map(|end| body.strip_suffix(&ellipsis[..end]))
+                    .unwrap_or(body)
+            }
+            _ => body,
+        };
+        for word in body.split_whitespace() {
+            let word = strip(word, reopened);
+            let rest = &words[words_pos..];
+            if rest.starts_with(&*word) {
+                words_pos += word.len();
+                continue;
+            }
+
+            violations.push(Violation::WordsMismatch {
+                line,
+                expected: rest.chars().take(word.chars().count()).collect(),
+                found: word.to_string(),
+            });
+            // Nothing after the first mismatch lines up anymore
+            words_pos = words.len();
+            break;
+        }
+    }
+
+    let unmatched = !violations
+        .iter()
+        .any(|violation| matches!(violation, Violation::WordsMismatch { .. }));
+    if words_pos < words.len() && unmatched && !truncated {
+        violations.push(Violation::WordsMismatch {
+            line: lines.last().map_or(0, |&(line, _)| line + 1),
+            expected: words[words_pos..].to_string(),
+            found: String::new(),
+        });
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+fn strip<'a>(word: &'a str, markup: Option<&MarkupConfig>) -> Cow<'a, str> {
+    match markup {
+        Some(markup) => Cow::Owned(markup.strip_markers(word)),
+        None => Cow::Borrowed(word),
+    }
+}
+
+/// Splits the gutter off `text` and returns its width with the rest.
+/// Gutter is kept out of the words, but counts in the width.
+fn split_gutter<'a>(text: &'a str, options: &TransformOptions) -> (usize, &'a str) {
+    let Some(style) = &options.number_lines else {
+        return (0, text);
+    };
+    match text.split_once(style.separator.as_str()) {
+        Some((number, body)) => (
+            number.chars().count() + style.separator.chars().count(),
+            body,
+        ),
+        None => (0, text),
+    }
+}
+
+/// Whether `body` may be a line copied as it is, by `skip_line`.
+fn is_skipped(body: &str, options: &TransformOptions) -> bool {
+    let filter = options
+        .skip_line
+        .as_ref()
+        .filter(|_| options.preserve_paragraphs);
+    filter.is_some_and(|filter| filter.matches(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_invariants, Violation};
+    use crate::{
+        transform_with_options, Alignment, BlockAlignment, LineEnding, LineFilter, NumberStyle,
+        TransformOptions, Truncation, VerticalAlignment,
+    };
+
+    const INPUT: &str = "Вез корабль карамель,\n \nнаскочил корабль на мель, матросы две недели \
+                         карамель на мели ели. Тридцатитрёхбуквенный 🤩 hello";
+
+    #[test]
+    fn valid_outputs() {
+        for line_width in [1, 3, 7, 12, 30] {
+            for seed in 0..32 {
+                let mut options = TransformOptions::new(line_width);
+                options.align = [
+                    Alignment::Left,
+                    Alignment::Right,
+                    Alignment::Center,
+                    Alignment::Justify,
+                ][seed % 4];
+                options.pad_last_line = seed & 4 == 0;
+                options.preserve_paragraphs = seed & 8 == 0;
+                options.initial_width = Some(line_width / 2 + 1 + seed as u32 % 3);
+                if seed % 3 == 1 {
+                    options.max_lines = Some(seed % 5);
+                    let ellipsis = ["…", " [...]"][seed & 1];
+                    options.truncation = Truncation::Ellipsis(String::from(ellipsis));
+                }
+                if seed % 3 == 2 {
+                    options.min_lines = Some(seed % 7 * 4);
+                    options.vertical_align = [
+                        VerticalAlignment::Top,
+                        VerticalAlignment::Middle,
+                        VerticalAlignment::Bottom,
+                    ][seed % 9 / 3];
+                }
+                if seed % 5 == 4 {
+                    options.skip_line = Some(LineFilter::new(|line| line.ends_with(',')));
+                }
+                if seed % 7 == 5 {
+                    let widest = options.initial_width.unwrap_or(0).max(line_width);
+                    options.field_width = Some(widest + seed as u32 % 4 * 3);
+                    options.block_align = [
+                        BlockAlignment::Left,
+                        BlockAlignment::Center,
+                        BlockAlignment::Right,
+                    ][seed % 3];
+                }
+                options.hang_punctuation = seed % 4 == 3;
+                options.final_newline = seed % 6 == 0;
+                options.margin_left = seed % 4;
+                options.margin_right = seed % 5 / 2;
+                if seed & 16 == 0 {
+                    options.line_ending = LineEnding::CrLf;
+                    options.lines_per_page = Some(seed % 5);
+                    options.number_lines = Some(NumberStyle::default());
+                }
+
+                let output = transform_with_options(INPUT, &options);
+                assert_eq!(
+                    verify_invariants(INPUT, &options, &output),
+                    Ok(()),
+                    "{options:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn violations() {
+        let options = TransformOptions::new(8);
+        assert_eq!(verify_invariants("", &options, ""), Ok(()));
+        assert_eq!(verify_invariants("one two", &options, "one  two"), Ok(()));
+
+        let expected = vec![
+            Violation::WrongWidth {
+                line: 0,
+                width: 7,
+                expected: 8,
+            },
+            Violation::EmptyLine { line: 1 },
+            Violation::WordsMismatch {
+                line: 2,
+                expected: String::from("three"),
+                found: String::from("thre3"),
+            },
+        ];
+        let output = "one two\n\nthre3   ";
+        assert_eq!(
+            verify_invariants("one two three", &options, output),
+            Err(expected)
+        );
+
+        let expected = vec![Violation::WordsMismatch {
+            line: 1,
+            expected: String::from("three"),
+            found: String::new(),
+        }];
+        assert_eq!(
+            verify_invariants("one two three", &options, "one  two"),
+            Err(expected)
+        );
+
+        let mut options = options;
+        options.lines_per_page = Some(1);
+        let expected = vec![Violation::PageTooLong {
+            page: 0,
+            lines: 2,
+            max_lines: 1,
+        }];
+        let output = "one  two\nthree   ";
+        assert_eq!(
+            verify_invariants("one two three", &options, output),
+            Err(expected)
+        );
+
+        let mut options = TransformOptions::new(8);
+        options.max_lines = Some(1);
+        assert_eq!(
+            verify_invariants("one two three", &options, "one  two"),
+            Ok(())
+        );
+        let expected = vec![Violation::TooManyLines {
+            lines: 2,
+            max_lines: 1,
+        }];
+        assert_eq!(
+            verify_invariants("one two three", &options, output),
+            Err(expected)
+        );
+
+        let mut options = TransformOptions::new(8);
+        options.min_lines = Some(3);
+        let output = "        \none  two\n";
+        let expected = vec![Violation::WrongWidth {
+            line: 2,
+            width: 0,
+            expected: 8,
+        }];
+        assert_eq!(
+            verify_invariants("one two", &options, output),
+            Err(expected)
+        );
+        let mut initial = options.clone();
+        initial.line_width = 9;
+        initial.initial_width = Some(3);
+        initial.preserve_paragraphs = true;
+        for min_lines in [Some(0), Some(4)] {
+            initial.min_lines = min_lines;
+            for input in ["abcd", "abcd\n\nefgh"] {
+                let output = transform_with_options(input, &initial);
+                assert!(output.starts_with("   \nabcd     "), "{output:?}");
+                assert_eq!(verify_invariants(input, &initial, &output), Ok(()));
+            }
+        }
+
+        let expected = vec![Violation::TooFewLines {
+            lines: 1,
+            min_lines: 3,
+        }];
+        assert_eq!(
+            verify_invariants("one two", &options, "one  two"),
+            Err(expected)
+        );
+        assert_eq!(
+            Violation::EmptyLine { line: 1 }.to_string(),
+            "line 1 is empty"
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{Alignment, Lines, TransformOptions, NEWLINE_STR};
+
+/// Ideographic space, one cell of a column.
+const BLANK_CELL: char = '\u{3000}';
+
+/// Lays out `input` in columns of `column_height` chars, read top to
+/// bottom and right to left, like vertical Japanese text.
+///
+/// Columns are lines of [`crate::transform`] with `Alignment::Left`: they
+/// break between words, and words taller than a column, such as text
+/// without spaces, break between any chars. Line `i` of the output holds
+/// char `i` of every column, the right-most column first. At least
+/// `min_columns` columns are written, blank ones are added on the left.
+/// Every char takes a cell, and whitespace and the cells after the end of
+/// a column are ideographic spaces.
+///
+/// Panics when `column_height` is 0 and `input` is not blank.
+pub fn transform_vertical(input: &str, column_height: u32, min_columns: usize) -> String {
+    let mut options = TransformOptions::new(column_height);
+    options.align = Alignment::Left;
+    let height = column_height as usize;
+
+    let mut columns = Vec::new();
+    let mut lines = Lines::new(input, &options, height);
+    while let Some(plan) = lines.next_plan() {
+        let mut column = String::with_capacity(plan.len());
+        plan.push(&mut column);
+        columns.push(column);
+    }
+    if columns.is_empty() && min_columns == 0 {
+        return String::new();
+    }
+
+    let mut cells: Vec<_> = columns.iter().rev().map(|column| column.chars()).collect();
+    let n_blank = min_columns.saturating_sub(cells.len());
+    let mut result = String::with_capacity((n_blank + cells.len()) * height * 4);
+    for row in 0..height {
+        if row > 0 {
+            result += NEWLINE_STR;
+        }
+        result.extend(core::iter::repeat_n(BLANK_CELL, n_blank));
+        for column in &mut cells {
+            match column.next() {
+                Some(chr) if !chr.is_whitespace() => result.push(chr),
+                _ => result.push(BLANK_CELL),
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transform_vertical;
+
+    #[test]
+    fn columns() {
+        let input = "吾輩は猫である。名前はまだ無い。";
+        assert_eq!(
+            transform_vertical(input, 4, 0),
+            "だ名で吾\n無前あ輩\nいはるは\n。ま。猫"
+        );
+        assert_eq!(
+            transform_vertical(input, 4, 5),
+            "\u{3000}だ名で吾\n\u{3000}無前あ輩\n\u{3000}いはるは\n\u{3000}。ま。猫"
+        );
+    }
+
+    #[test]
+    fn words_and_blanks() {
+        assert_eq!(
+            transform_vertical("東京 Tokyo", 3, 0),
+            "yT東\noo京\n\u{3000}k\u{3000}"
+        );
+        assert_eq!(transform_vertical("", 3, 0), "");
+        assert_eq!(transform_vertical(" ", 2, 1), "\u{3000}\n\u{3000}");
+    }
+}
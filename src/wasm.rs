@@ -0,0 +1,66 @@
+//! JavaScript bindings, enabled by the `wasm` feature.
+//!
+//! Invalid arguments are reported as thrown `Error`s instead of panics,
+//! which would abort the whole wasm instance.
+//!
+//! The module for `wasm-bindgen` is built with e.g. `cargo rustc --release
+//! --lib --target wasm32-unknown-unknown --features wasm --crate-type cdylib`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{try_transform_with_options, TransformOptions};
+
+#[wasm_bindgen]
+extern "C" {
+    /// Any JS object with the properties below. Only `width` is required.
+    #[wasm_bindgen(typescript_type = "TransformOptions")]
+    pub type JsTransformOptions;
+
+    #[wasm_bindgen(method, getter)]
+    fn width(this: &JsTransformOptions) -> Option<u32>;
+
+    #[wasm_bindgen(method, getter)]
+    fn align(this: &JsTransformOptions) -> Option<String>;
+
+    #[wasm_bindgen(method, getter = padLastLine)]
+    fn pad_last_line(this: &JsTransformOptions) -> Option<bool>;
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TRANSFORM_OPTIONS: &str = r#"
+export interface TransformOptions {
+    width: number;
+    align?: "left" | "right" | "center" | "justify";
+    padLastLine?: boolean;
+}
+"#;
+
+/// See [`crate::transform`].
+#[wasm_bindgen(js_name = transform)]
+pub fn transform(input: &str, width: u32) -> Result<String, JsError> {
+    Ok(try_transform_with_options(
+        input,
+        &TransformOptions::new(width),
+    )?)
+}
+
+/// See [`crate::transform_with_options`].
+#[wasm_bindgen(js_name = transformWithOptions)]
+pub fn transform_with_options(
+    input: &str,
+    options: &JsTransformOptions,
+) -> Result<String, JsError> {
+    let width = options
+        .width()
+        .ok_or_else(|| JsError::new("options.width must be a number"))?;
+
+    let mut transform_options = TransformOptions::new(width);
+    if let Some(align) = options.align() {
+        transform_options.align = align.parse()?;
+    }
+    if let Some(pad_last_line) = options.pad_last_line() {
+        transform_options.pad_last_line = pad_last_line;
+    }
+
+    Ok(try_transform_with_options(input, &transform_options)?)
+}
@@ -0,0 +1,184 @@
+//! Widths in half units, see [`WidthMode::HalfWidthUnits`].
+
+use alloc::borrow::Cow;
+
+use crate::{TransformOptions, WidthMode, Word};
+
+/// Whether `chr` takes a full-width cell: CJK ideographs, kana, hangul,
+/// fullwidth forms and emoji. Other chars take half of it.
+fn is_wide(chr: char) -> bool {
+    matches!(
+        chr,
+        '\u{1100}'..='\u{115F}'
+            | '\u{2E80}'..='\u{303E}'
+            | '\u{3041}'..='\u{A4CF}'
+            | '\u{AC00}'..='\u{D7A3}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{FE30}'..='\u{FE4F}'
+            | '\u{FF00}'..='\u{FF60}'
+            | '\u{FFE0}'..='\u{FFE6}'
+            | '\u{1F300}'..='\u{1F64F}'
+            | '\u{1F900}'..='\u{1F9FF}'
+            | '\u{20000}'..='\u{3FFFD}'
+    )
+}
+
+pub(crate) fn char_units(chr: char) -> usize {
+    match is_wide(chr) {
+        true => 2,
+        false => 1,
+    }
+}
+
+impl<'a> Word<'a> {
+    /// Same as [`Word::new`], but the width is in half units.
+    pub(crate) fn half_units(text: &'a str) -> Self {
+        Word {
+            text,
+            width: text.chars().map(char_units).sum(),
+        }
+    }
+}
+
+/// Same as [`crate::split_manually`], but widths are in half units. The
+/// part gets at least one char.
+pub(crate) fn split_half_units(unfitted: Word<'_>, line_width: usize) -> (Word<'_>, Word<'_>) {
+    let mut width = 0;
+    let mut end = unfitted.text.len();
+    for (idx, chr) in unfitted.text.char_indices() {
+        let units = char_units(chr);
+        if width + units > line_width && idx > 0 {
+            end = idx;
+            break;
+        }
+        width += units;
+    }
+
+    let (part, rest) = unfitted.text.split_at(end);
+    let part = Word { text: part, width };
+    let rest = Word {
+        text: rest,
+        width: unfitted.width - width,
+    };
+    (part, rest)
+}
+
+/// `options` with widths and margins in half units, as lines are laid out
+/// with [`WidthMode::HalfWidthUnits`].
+pub(crate) fn in_units(options: &TransformOptions) -> Cow<'_, TransformOptions> {
+    if options.width_mode == WidthMode::Chars {
+        return Cow::Borrowed(options);
+    }
+
+    let mut options = options.clone();
+    options.line_width = options.line_width.saturating_mul(2);
+    options.initial_width = options.initial_width.map(|width| width.saturating_mul(2));
+    options.field_width = options.field_width.map(|width| width.saturating_mul(2));
+    options.margin_left = options.margin_left.saturating_mul(2);
+    options.margin_right = options.margin_right.saturating_mul(2);
+    Cow::Owned(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Word;
+    use crate::{
+        line_count_with_options, transform_with_options, try_transform_with_options,
+        verify::verify_invariants, Alignment, BlockAlignment, Document, MarkupConfig,
+        TransformOptions, WidthMode,
+    };
+
+    fn units(line: &str) -> usize {
+        Word::half_units(line).width
+    }
+
+    #[test]
+    fn half_units() {
+        let input = "日本語 abc テスト";
+        let mut options = TransformOptions::new(6);
+        options.width_mode = WidthMode::HalfWidthUnits;
+        let output = transform_with_options(input, &options);
+        assert_eq!(output, "日本語   abc\nテスト      ");
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+
+        for line_width in [3, 4, 5, 7, 10] {
+            options.line_width = line_width;
+            let output = transform_with_options(input, &options);
+            assert!(output
+                .lines()
+                .all(|line| units(line) == 2 * line_width as usize));
+            assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        }
+        options.line_width = 5;
+        assert_eq!(
+            transform_with_options(input, &options),
+            "日本語 abc\nテスト    "
+        );
+
+        // Words wider than a line are split between chars
+        options.line_width = 2;
+        options.align = Alignment::Left;
+        assert_eq!(
+            transform_with_options("日本語 abcde", &options),
+            "日本\n語  \nabcd\ne   "
+        );
+
+        options.margin_left = 1;
+        options.line_width = 5;
+        let output = transform_with_options(input, &options);
+        assert_eq!(output, "  日本語 abc\n  テスト    ");
+        assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+
+        options.preserve_paragraphs = true;
+        let input = "日本語 abc テスト\n\nabc 日本語";
+        let mut document = Document::with_options(options.clone());
+        assert_eq!(
+            document.update(input),
+            transform_with_options(input, &options)
+        );
+        assert_eq!(line_count_with_options(input, &options), 4);
+    }
+
+    #[test]
+    fn field() {
+        let mut options = TransformOptions::new(10);
+        options.width_mode = WidthMode::HalfWidthUnits;
+        options.field_width = Some(14);
+        options.block_align = BlockAlignment::Center;
+        for input in ["字", "日本語 abc テスト"] {
+            let output = transform_with_options(input, &options);
+            assert!(output.lines().all(|line| units(line) == 28), "{output:?}");
+            assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        }
+        assert_eq!(
+            transform_with_options("字", &options),
+            "    字                      "
+        );
+    }
+
+    #[test]
+    fn markup() {
+        let mut options = TransformOptions::new(2);
+        options.width_mode = WidthMode::HalfWidthUnits;
+        options.markup = Some(MarkupConfig {
+            open: String::from("\u{1}"),
+            close: String::from("\u{2}"),
+            reopen_on_wrap: false,
+            hyperlinks: false,
+        });
+        options.max_output_bytes = Some(1 << 12);
+
+        let cases = [
+            ("漢字漢字", 2, "漢字\n漢字"),
+            ("。漢漢", 1, "。\n漢\n漢"),
+            ("a\u{1}漢\u{2}b", 1, "a \n\u{1}漢\u{2}\nb "),
+            ("\u{1}日本語\u{2} abc", 4, "\u{1}日本語\u{2}  \nabc     "),
+        ];
+        for (input, line_width, expected) in cases {
+            options.line_width = line_width;
+            let output = try_transform_with_options(input, &options).unwrap();
+            assert_eq!(output, expected);
+            assert_eq!(verify_invariants(input, &options, &output), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use alloc::string::String;
+
+use crate::{
+    adjust, transform_by, transform_with_options, validate, TransformError, TransformOptions,
+};
+
+/// Options checked once, to adjust many texts with them.
+///
+/// Every call adjusts like [`crate::transform_with_options`] and cannot
+/// fail because of the options, as they are checked by [`Wrapper::new`]
+/// like [`crate::try_transform_with_options`] checks them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wrapper {
+    options: TransformOptions,
+}
+
+impl Wrapper {
+    pub fn new(options: TransformOptions) -> Result<Self, TransformError> {
+        validate(&options)?;
+        Ok(Wrapper { options })
+    }
+
+    pub fn options(&self) -> &TransformOptions {
+        &self.options
+    }
+
+    /// Same as [`crate::transform_with_options`] with the options of the
+    /// wrapper. `max_output_bytes` is ignored, see [`Wrapper::try_transform`].
+    pub fn transform(&self, input: &str) -> String {
+        transform_with_options(input, &self.options)
+    }
+
+    /// Same as [`Wrapper::transform`], but fails as soon as the output
+    /// grows beyond `max_output_bytes`, the only error left.
+    pub fn try_transform(&self, input: &str) -> Result<String, TransformError> {
+        transform_by(input, &self.options, adjust, self.options.max_output_bytes)
+    }
+
+    /// Writes the output of [`Wrapper::transform`] to `writer` line by
+    /// line, like [`crate::transform_to_writer`] does.
+    #[cfg(feature = "std")]
+    pub fn transform_to_writer<W: std::io::Write>(
+        &self,
+        input: &str,
+        writer: W,
+    ) -> std::io::Result<()> {
+        crate::write::write_adjusted(input, &self.options, writer)
+    }
+}
+
+impl TryFrom<TransformOptions> for Wrapper {
+    type Error = TransformError;
+
+    fn try_from(options: TransformOptions) -> Result<Self, Self::Error> {
+        Wrapper::new(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Wrapper;
+    use crate::{try_transform_with_options, TransformError, TransformOptions};
+
+    const INPUT: &str = "Съешь ещё этих мягких французских булок, да выпей чаю";
+
+    #[test]
+    fn checked_once() {
+        assert_eq!(
+            Wrapper::new(TransformOptions::new(0)),
+            Err(TransformError::ZeroWidth)
+        );
+        let mut options = TransformOptions::new(12);
+        options.field_width = Some(10);
+        assert_eq!(
+            Wrapper::try_from(options),
+            Err(TransformError::FieldTooNarrow {
+                field_width: 10,
+                line_width: 12
+            })
+        );
+
+        let mut options = TransformOptions::new(12);
+        options.margin_left = 2;
+        options.max_output_bytes = Some(200);
+        let wrapper = Wrapper::new(options.clone()).unwrap();
+        assert_eq!(wrapper.options(), &options);
+        for input in [INPUT, "", "Тридцатитрёхбуквенный"] {
+            let expected = try_transform_with_options(input, &options).unwrap();
+            assert_eq!(wrapper.transform(input), expected);
+            assert_eq!(wrapper.try_transform(input), Ok(expected.clone()));
+
+            #[cfg(feature = "std")]
+            {
+                let mut written = Vec::new();
+                wrapper.transform_to_writer(input, &mut written).unwrap();
+                assert_eq!(written, expected.as_bytes());
+            }
+        }
+
+        // Output size is still checked for every text
+        let input = INPUT.repeat(2);
+        assert!(matches!(
+            wrapper.try_transform(&input),
+            Err(TransformError::OutputTooLarge { limit: 200, .. })
+        ));
+        assert_eq!(wrapper.transform(&input).lines().count(), 10);
+    }
+}
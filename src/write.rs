@@ -0,0 +1,532 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{
+    adjuster::Adjuster, is_glued, transform_with_options, width::in_units, Lines, TransformOptions,
+};
+
+/// Writes `input`, adjusted like [`crate::transform`], to `writer`.
+///
+/// Lines are written as soon as they are adjusted, so the whole output
+/// is never kept in memory. Output is byte-identical to `transform`.
+///
+/// Memory use does not depend on the size of `input`: a single line is
+/// kept at a time, and every `write` call on `writer` gets at most one
+/// line or line ending.
+pub fn transform_to_writer<W: Write>(input: &str, line_width: u32, writer: W) -> io::Result<()> {
+    write_adjusted(input, &TransformOptions::new(line_width), writer)
+}
+
+/// Writes `input`, adjusted like [`crate::transform_with_options`], to
+/// `writer` line by line, as [`transform_to_writer`] does. Output of
+/// options that change the whole text, e.g. `number_lines`, `max_lines`
+/// or margins, is built in memory first.
+pub(crate) fn write_adjusted<W: Write>(
+    input: &str,
+    options: &TransformOptions,
+    mut writer: W,
+) -> io::Result<()> {
+    if !is_line_by_line(options) {
+        return writer.write_all(transform_with_options(input, options).as_bytes());
+    }
+
+    let options = &*in_units(options);
+    let mut lines = Lines::new(input, options, options.line_width as usize);
+    let line_ending = options.line_ending.as_str().as_bytes();
+
+    let mut line = String::new();
+    let mut need_newline = false;
+    let mut is_empty = true;
+    while lines.next_line(&mut line) {
+        if need_newline {
+            writer.write_all(line_ending)?;
+            is_empty = false;
+        }
+        writer.write_all(line.as_bytes())?;
+
+        is_empty &= line.is_empty();
+        line.clear();
+        need_newline = true;
+    }
+    if options.final_newline && !is_empty {
+        writer.write_all(line_ending)?;
+    }
+
+    Ok(())
+}
+
+/// Whether every line of the output is a line of [`Lines`] as it is.
+fn is_line_by_line(options: &TransformOptions) -> bool {
+    let reopened = options
+        .markup
+        .as_ref()
+        .is_some_and(|markup| markup.reopen_on_wrap);
+    options.number_lines.is_none()
+        && options.max_lines.is_none()
+        && options.min_lines.is_none()
+        && options.field_width.is_none()
+        && options.lines_per_page.is_none()
+        && options.margin_left == 0
+        && options.margin_right == 0
+        && options.break_hint.is_none()
+        && !options.cjk_latin_spacing
+        && !is_glued(options)
+        && !reopened
+}
+
+/// Same as [`transform_to_writer`], but writes to a tokio `AsyncWrite`.
+///
+/// Every line is awaited separately, so a slow reader on the other end
+/// holds the producer back instead of letting output pile up.
+#[cfg(feature = "async")]
+pub async fn transform_to_async_writer<W>(input: &str, line_width: u32, w: &mut W) -> io::Result<()>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    use tokio::io::AsyncWriteExt;
+
+    let options = TransformOptions::new(line_width);
+    let mut lines = Lines::new(input, &options, line_width as usize);
+    let line_ending = options.line_ending.as_str().as_bytes();
+
+    let mut line = String::new();
+    let mut need_newline = false;
+    while lines.next_line(&mut line) {
+        if need_newline {
+            w.write_all(line_ending).await?;
+        }
+        w.write_all(line.as_bytes()).await?;
+
+        line.clear();
+        need_newline = true;
+    }
+
+    w.flush().await
+}
+
+/// Reads `reader` to the end and writes its text, adjusted like
+/// [`crate::transform`], to `writer`.
+///
+/// Input is processed chunk by chunk as the reader provides it. Memory
+/// use is bounded by the reader's buffer, the line width and the longest
+/// word, not by the size of the input. See [`JustifyWriter`] for the size
+/// of writes to `writer`.
+pub fn transform_stream<R: BufRead, W: Write>(
+    reader: R,
+    line_width: u32,
+    writer: W,
+) -> io::Result<()> {
+    transform_stream_with_options(reader, &TransformOptions::new(line_width), writer)
+}
+
+/// Same as [`transform_stream`], but configured by `options`. With
+/// `preserve_paragraphs` every blank line ends a paragraph.
+///
+/// Options are used like [`crate::Adjuster::with_options`] uses them.
+pub fn transform_stream_with_options<R: BufRead, W: Write>(
+    mut reader: R,
+    options: &TransformOptions,
+    writer: W,
+) -> io::Result<()> {
+    let mut writer = JustifyWriter::with_options(writer, options.clone());
+
+    loop {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+        writer.write_all(chunk)?;
+
+        let len = chunk.len();
+        reader.consume(len);
+    }
+
+    writer.flush()
+}
+
+/// Smallest piece of input [`JustifyWriter`] adjusts at once.
+const MIN_PIECE_LEN: usize = 64;
+
+/// Adjusts text written to it and passes complete lines to `inner`.
+///
+/// Bytes may be written in arbitrary pieces, even in the middle of a word
+/// or a char. A line is passed on as soon as the next word does not fit
+/// into it. [`Write::flush`] and dropping the writer end the text: the
+/// last line is written according to `pad_last_line`, and text written
+/// after `flush` starts on a new line.
+///
+/// Writing bytes that are not valid UTF-8 fails with
+/// [`io::ErrorKind::InvalidData`].
+///
+/// However large a single write is, it is adjusted in pieces, so only a
+/// few lines are buffered before they are passed to `inner`. Words are
+/// buffered until they end, so a long word is passed on at once.
+pub struct JustifyWriter<W: Write> {
+    inner: W,
+    adjuster: Adjuster,
+    out: String,
+}
+
+impl<W: Write> JustifyWriter<W> {
+    pub fn new(inner: W, line_width: u32) -> Self {
+        JustifyWriter::with_options(inner, TransformOptions::new(line_width))
+    }
+
+    /// Options are used like [`crate::Adjuster::with_options`] uses them.
+    pub fn with_options(inner: W, options: TransformOptions) -> Self {
+        JustifyWriter {
+            inner,
+            adjuster: Adjuster::with_options(options),
+            out: String::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    fn write_out(&mut self) -> io::Result<()> {
+        let result = self.inner.write_all(self.out.as_bytes());
+        self.out.clear();
+        result
+    }
+}
+
+impl<W: Write> Write for JustifyWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // A piece completes a handful of lines at most, unless it ends a long word
+        let piece_len = self.adjuster.line_width().max(MIN_PIECE_LEN);
+        for piece in buf.chunks(piece_len) {
+            self.adjuster.push_bytes_to(piece, &mut self.out)?;
+            self.write_out()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.adjuster.finish_bytes_to(&mut self.out)?;
+        self.write_out()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for JustifyWriter<W> {
+    fn drop(&mut self) {
+        // Errors can not be reported from here, call `flush` to see them
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Write};
+
+    use super::{
+        transform_stream, transform_stream_with_options, transform_to_writer, JustifyWriter,
+    };
+    use crate::{
+        transform, transform_with_options, Alignment, LineEnding, TransformOptions, WidthMode,
+    };
+
+    const INPUT: &str =
+        "Вез корабль карамель, наскочил корабль на мель, матросы две недели карамель на мели ели. \
+         ab—cdefgh xy cd—efghijk";
+
+    #[test]
+    fn writer_matches_transform() {
+        for line_width in [1, 4, 7, 18, 100] {
+            let mut output = Vec::new();
+            transform_to_writer(INPUT, line_width, &mut output).unwrap();
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                transform(INPUT, line_width)
+            );
+        }
+    }
+
+    #[test]
+    fn adjusted_with_options() {
+        let mut cases = Vec::new();
+        for line_width in [1, 4, 7, 18, 100] {
+            let mut options = TransformOptions::new(line_width);
+            options.align = Alignment::Right;
+            options.pad_last_line = false;
+            options.final_newline = true;
+            options.line_ending = LineEnding::CrLf;
+            options.width_mode = WidthMode::HalfWidthUnits;
+            cases.push(options.clone());
+
+            options.preserve_paragraphs = true;
+            options.blank_line_for_empty = true;
+            options.hang_punctuation = true;
+            cases.push(options.clone());
+
+            // Built in memory first
+            options.margin_left = 2;
+            options.max_lines = Some(3);
+            cases.push(options);
+        }
+
+        for options in cases {
+            for input in [INPUT, "Съешь ещё.\n\nЧаю", " \n"] {
+                let mut output = Vec::new();
+                super::write_adjusted(input, &options, &mut output).unwrap();
+                assert_eq!(
+                    String::from_utf8(output).unwrap(),
+                    transform_with_options(input, &options)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn justify_writer_in_chunks() {
+        for line_width in [1, 4, 7, 18, 100] {
+            let mut output = Vec::new();
+            let mut writer = JustifyWriter::new(&mut output, line_width);
+            for chunk in INPUT.as_bytes().chunks(3) {
+                writer.write_all(chunk).unwrap();
+            }
+            drop(writer);
+
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                transform(INPUT, line_width)
+            );
+        }
+    }
+
+    #[test]
+    fn justify_writer_emits_full_lines_early() {
+        let mut output = Vec::new();
+        let mut writer = JustifyWriter::new(&mut output, 10);
+
+        writer.write_all(b"first para").unwrap();
+        writer.write_all(b"graph").unwrap();
+        assert_eq!(writer.get_ref().as_slice(), b"");
+
+        writer.write_all(b" continues").unwrap();
+        assert_eq!(writer.get_ref().as_slice(), b"first     ");
+
+        writer.flush().unwrap();
+        drop(writer);
+        assert_eq!(output, b"first     \nparagraph \ncontinues ");
+    }
+
+    #[test]
+    fn justify_writer_rejects_invalid_utf8() {
+        let mut writer = JustifyWriter::new(Vec::new(), 10);
+        writer.write_all("Прив".as_bytes()).unwrap();
+
+        let err = writer.write_all(b"\xFF\xFE").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // Truncated char at the end of text
+        writer.write_all(&"ет".as_bytes()[..3]).unwrap();
+        assert_eq!(
+            writer.flush().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn stream_matches_transform() {
+        for capacity in [1, 2, 5, 64] {
+            for line_width in [1, 4, 7, 18, 100] {
+                let reader = BufReader::with_capacity(capacity, INPUT.as_bytes());
+                let mut output = Vec::new();
+                transform_stream(reader, line_width, &mut output).unwrap();
+
+                assert_eq!(
+                    String::from_utf8(output).unwrap(),
+                    transform(INPUT, line_width)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stream_word_across_buffer_boundary() {
+        let input = "abc defghij klm";
+        let reader = BufReader::with_capacity(6, input.as_bytes());
+        let mut output = Vec::new();
+        transform_stream(reader, 8, &mut output).unwrap();
+
+        assert_eq!(output, b"abc     \ndefghij \nklm     ");
+    }
+
+    #[test]
+    fn stream_paragraphs() {
+        let input = "First paragraph\nof text.\n \t \n\n\nSecond paragraph.\n\n";
+        let mut options = TransformOptions::new(10);
+        options.preserve_paragraphs = true;
+        options.pad_last_line = false;
+
+        let reader = BufReader::with_capacity(3, input.as_bytes());
+        let mut output = Vec::new();
+        transform_stream_with_options(reader, &options, &mut output).unwrap();
+
+        let expected = transform_with_options(input, &options);
+        assert_eq!(
+            expected,
+            "First     \nparagraph \nof text.\n\nSecond    \nparagraph."
+        );
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    /// Discards everything written to it, but remembers the longest write.
+    #[derive(Default)]
+    struct LongestWrite {
+        longest: usize,
+        total: usize,
+    }
+
+    impl Write for LongestWrite {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.longest = self.longest.max(buf.len());
+            self.total += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn bounded_memory() {
+        fn synthetic(size: usize) -> String {
+            let words = [
+                "Вез",
+                "корабль",
+                "карамель,",
+                "a",
+                "ship",
+                "of",
+                "caramel",
+                "🤩",
+                "наскочил",
+            ];
+            let mut result = String::with_capacity(size + 32);
+            for idx in 0.. {
+                if result.len() >= size {
+                    break;
+                }
+                result += words[idx * 7 % words.len()];
+                result += if idx % 11 == 0 { "\n\n" } else { " " };
+            }
+            result
+        }
+
+        const MB: usize = 1 << 20;
+        let small = synthetic(MB);
+        let large = synthetic(10 * MB);
+
+        for line_width in [7, 80] {
+            let mut counts = Vec::new();
+            for input in [&small, &large] {
+                let mut sink = LongestWrite::default();
+                let (result, to_writer) = crate::alloc_counter::count(|| {
+                    transform_to_writer(input, line_width, &mut sink)
+                });
+                result.unwrap();
+                assert!(
+                    sink.longest <= 4 * line_width as usize,
+                    "{} bytes in a write",
+                    sink.longest
+                );
+                assert!(sink.total >= input.len());
+
+                let mut sink = LongestWrite::default();
+                let mut options = TransformOptions::new(line_width);
+                options.preserve_paragraphs = true;
+                let (result, stream) = crate::alloc_counter::count(|| {
+                    transform_stream_with_options(input.as_bytes(), &options, &mut sink)
+                });
+                result.unwrap();
+                let piece_len = (line_width as usize).max(super::MIN_PIECE_LEN);
+                assert!(
+                    sink.longest <= 4 * piece_len,
+                    "{} bytes in a write",
+                    sink.longest
+                );
+
+                counts.push((
+                    to_writer.allocs + to_writer.reallocs,
+                    stream.allocs + stream.reallocs,
+                ));
+            }
+
+            // Buffers only grow while the longest line or word is not seen yet
+            let (small, large) = (counts[0], counts[1]);
+            assert!(small.0 <= 16 && small.1 <= 24, "{small:?} allocations");
+            assert_eq!(small, large);
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_writer_matches_transform() {
+        use tokio::io::AsyncReadExt;
+
+        for line_width in [1, 4, 7, 18, 100] {
+            // Small buffer makes the writer wait for the reader between lines
+            let (mut client, mut server) = tokio::io::duplex(16);
+
+            let writer = async move {
+                let result = super::transform_to_async_writer(INPUT, line_width, &mut client).await;
+                drop(client);
+                result
+            };
+            let mut output = Vec::new();
+            let (written, read) = tokio::join!(writer, server.read_to_end(&mut output));
+
+            written.unwrap();
+            read.unwrap();
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                transform(INPUT, line_width)
+            );
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_writer_propagates_errors() {
+        let (mut client, server) = tokio::io::duplex(4);
+        drop(server);
+
+        let result = super::transform_to_async_writer(INPUT, 10, &mut client).await;
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn final_newline() {
+        let mut options = TransformOptions::new(10);
+        options.final_newline = true;
+
+        let mut output = Vec::new();
+        let reader = BufReader::with_capacity(3, INPUT.as_bytes());
+        transform_stream_with_options(reader, &options, &mut output).unwrap();
+        let expected = transform_with_options(INPUT, &options);
+        assert!(expected.ends_with("cd—efghijk\n"));
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+
+        // Text after a flush starts right after the line ending
+        let mut output = Vec::new();
+        let mut writer = JustifyWriter::with_options(&mut output, options.clone());
+        writer.write_all(b"first").unwrap();
+        writer.flush().unwrap();
+        writer.flush().unwrap();
+        writer.write_all(b"second").unwrap();
+        drop(writer);
+        assert_eq!(output, b"first     \nsecond    \n");
+
+        let mut output = Vec::new();
+        transform_stream_with_options(&b" \n "[..], &options, &mut output).unwrap();
+        assert_eq!(output, b"");
+    }
+}
@@ -0,0 +1,99 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Output, Stdio};
+
+use line_adjustment::transform;
+
+fn run(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_line-adjust"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start line-adjust");
+
+    // Binary may exit on invalid arguments before reading its input
+    let _ = child.stdin.take().unwrap().write_all(input);
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn matches_library() {
+    let input = "Вез корабль карамель, наскочил корабль на мель,\nматросы две недели\nкарамель на мели ели. \
+                 Тридцатитрёхбуквенный  сверхдлинныйидентификатор конец";
+
+    for width in [1, 3, 6, 12, 18, 40] {
+        let output = run(&["--width", &width.to_string()], input.as_bytes());
+        assert_eq!(stdout(&output), transform(input, width) + "\n");
+    }
+}
+
+#[test]
+fn flags() {
+    let input = b"first paragraph\n\nsecond one\n";
+
+    let output = run(
+        &["-w", "10", "--align=right", "--preserve-paragraphs"],
+        input,
+    );
+    assert_eq!(stdout(&output), "     first\n paragraph\n\nsecond one\n");
+
+    let output = run(&["-w", "10", "--no-pad-last", "--crlf"], input);
+    assert_eq!(
+        stdout(&output),
+        "first     \r\nparagraph \r\nsecond one\r\n"
+    );
+
+    let output = run(&["-w", "12", "--align", "center", "--no-pad-last"], input);
+    assert_eq!(stdout(&output), "   first    \n paragraph  \n second one\n");
+}
+
+#[test]
+fn errors() {
+    let output = run(&["--width", "0"], b"text");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("width must be greater than 0"));
+
+    let output = run(&["--width", "4294967295"], b"text");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("width must be at most"));
+
+    let output = run(&["--align", "middle"], b"text");
+    assert!(!output.status.success());
+
+    let output = run(&["-w", "10"], b"valid line\ninvalid \xFF\n");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not valid UTF-8 (line 2)"));
+}
+
+#[test]
+fn streams_before_end_of_input() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_line-adjust"))
+        .args(["-w", "9"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to start line-adjust");
+
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"y\ny\ny\ny\ny\ny\n").unwrap();
+    stdin.flush().unwrap();
+
+    // Input is still open, but the first line is already complete
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "y y y y y\n");
+
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+}
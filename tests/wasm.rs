@@ -0,0 +1,36 @@
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use line_adjustment::wasm::{transform, transform_with_options, JsTransformOptions};
+
+fn options(width: u32, align: Option<&str>, pad_last_line: Option<bool>) -> JsTransformOptions {
+    let object = js_sys::Object::new();
+    js_sys::Reflect::set(&object, &"width".into(), &width.into()).unwrap();
+    if let Some(align) = align {
+        js_sys::Reflect::set(&object, &"align".into(), &align.into()).unwrap();
+    }
+    if let Some(pad_last_line) = pad_last_line {
+        js_sys::Reflect::set(&object, &"padLastLine".into(), &pad_last_line.into()).unwrap();
+    }
+    JsValue::from(object).unchecked_into()
+}
+
+#[wasm_bindgen_test]
+fn transform_exported() {
+    assert_eq!(transform("consectetur", 4).unwrap(), "cons\necte\ntur ");
+    assert!(transform("consectetur", 0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn transform_with_options_exported() {
+    let result = transform_with_options("Съешь ещё этих", &options(12, None, None));
+    assert_eq!(result.unwrap(), "Съешь    ещё\nэтих        ");
+
+    let result = transform_with_options("Съешь ещё этих", &options(12, Some("right"), Some(false)));
+    assert_eq!(result.unwrap(), "   Съешь ещё\n        этих");
+
+    assert!(transform_with_options("text", &options(12, Some("middle"), None)).is_err());
+    assert!(transform_with_options("text", &options(0, None, None)).is_err());
+}